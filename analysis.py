@@ -11,8 +11,8 @@ iterations = 0
 
 def get_score(row):
     try:
-        return int(row[116:])
-    except ValueError:
+        return int(row[116:].split()[0])
+    except (ValueError, IndexError):
         return 0
 
 while True:
@@ -0,0 +1,83 @@
+/// An alternative base that addresses can be rendered in, so that a target
+/// can be matched against the address as it would appear in another
+/// ecosystem (see `Config::encoded_target`).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Base {
+    /// RFC 4648 base32 (`A-Z2-7`), without padding: 32 characters.
+    Base32,
+    /// Base58 with the Bitcoin alphabet, where each leading zero byte is
+    /// written as a `1`: up to 28 characters.
+    Base58,
+}
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+impl Base {
+    /// The base with the given name (`base32` or `base58`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "base32" => Some(Base::Base32),
+            "base58" => Some(Base::Base58),
+            _ => None,
+        }
+    }
+
+    /// The characters that can appear in an address rendered in this base.
+    pub fn alphabet(self) -> &'static [u8] {
+        match self {
+            Base::Base32 => BASE32_ALPHABET,
+            Base::Base58 => BASE58_ALPHABET,
+        }
+    }
+
+    /// Render an address in this base.
+    pub fn encode(self, address: &[u8; 20]) -> String {
+        match self {
+            Base::Base32 => encode_base32(address),
+            Base::Base58 => encode_base58(address),
+        }
+    }
+}
+
+/// Encode 20 bytes (160 bits, i.e. exactly 32 characters) as base32.
+fn encode_base32(bytes: &[u8; 20]) -> String {
+    let mut encoded = String::with_capacity(32);
+    for chunk in bytes.chunks(5) {
+        let bits = chunk
+            .iter()
+            .fold(0u64, |bits, &byte| (bits << 8) | u64::from(byte));
+        for i in (0..8).rev() {
+            encoded.push(BASE32_ALPHABET[((bits >> (i * 5)) & 0x1f) as usize] as char);
+        }
+    }
+    encoded
+}
+
+/// Encode bytes as base58 by repeated division of the big-endian number.
+fn encode_base58(bytes: &[u8; 20]) -> String {
+    // the digits of the number in base 58, least significant first
+    let mut digits: Vec<u8> = Vec::with_capacity(28);
+    for &byte in bytes.iter() {
+        let mut carry = u32::from(byte);
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let leading_zeroes = bytes.iter().take_while(|&&b| b == 0).count();
+    let mut encoded = "1".repeat(leading_zeroes);
+    encoded.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|&digit| BASE58_ALPHABET[usize::from(digit)] as char),
+    );
+    encoded
+}
@@ -0,0 +1,89 @@
+use super::{init_code_hash, Config, ConfigError};
+
+/// Builds a `Config` from values that have already been parsed, for
+/// applications that embed the search rather than run it from the command
+/// line (see `Config::builder`). `Config::new` parses the command line into
+/// one of these, so the result is validated exactly as the command line is.
+#[derive(Clone, Debug, Default)]
+pub struct ConfigBuilder {
+    factory_address: Option<[u8; 20]>,
+    calling_addresses: Vec<[u8; 20]>,
+    init_code_hash: Option<[u8; 32]>,
+    targets: Vec<String>,
+    resume_best: bool,
+    options: Config,
+}
+
+impl ConfigBuilder {
+    /// The address of the contract that will call CREATE2.
+    pub fn factory_address(mut self, factory_address: [u8; 20]) -> Self {
+        self.factory_address = Some(factory_address);
+        self
+    }
+
+    /// Add a caller of the factory to mine salts for. Without one, salts are
+    /// mined for the null address.
+    pub fn calling_address(mut self, calling_address: [u8; 20]) -> Self {
+        self.calling_addresses.push(calling_address);
+        self
+    }
+
+    /// The keccak-256 hash of the init code of the contract to deploy.
+    pub fn init_code_hash(mut self, init_code_hash: [u8; 32]) -> Self {
+        self.init_code_hash = Some(init_code_hash);
+        self
+    }
+
+    /// The init code of the contract to deploy (its creation bytecode,
+    /// including any constructor arguments), in place of its hash.
+    pub fn init_code(mut self, init_code: &[u8]) -> Self {
+        self.options.init_code = Some(init_code.to_vec());
+        self.init_code_hash(init_code_hash(init_code))
+    }
+
+    /// Add a `0x`-prefixed target that addresses must start with; an address
+    /// matching any one of several targets is recorded. Without one, the
+    /// target is a bare `0x`.
+    pub fn target_prefix(mut self, target_prefix: &str) -> Self {
+        self.targets.push(target_prefix.to_string());
+        self
+    }
+
+    /// Only record addresses that score higher than the best one already in
+    /// `efficient_addresses.txt`, as `--resume-best` does.
+    pub fn resume_best(mut self) -> Self {
+        self.resume_best = true;
+        self
+    }
+
+    /// Set any other option as a field of the configuration being built, e.g.
+    /// `.configure(|options| options.max_results = Some(1))`. The factory,
+    /// callers, init code hash and targets are taken from the builder rather
+    /// than from these fields.
+    pub fn configure<F: FnOnce(&mut Config)>(mut self, configure: F) -> Self {
+        configure(&mut self.options);
+        self
+    }
+
+    /// Validate the configuration and build it.
+    pub fn build(self) -> Result<Config, ConfigError> {
+        let mut config = self.options;
+        config.factory_address = self
+            .factory_address
+            .ok_or(ConfigError::MissingFactoryAddress)?;
+        config.init_code_hash = self
+            .init_code_hash
+            .ok_or(ConfigError::MissingInitCodeHash)?;
+        if !self.calling_addresses.is_empty() {
+            config.calling_addresses = self.calling_addresses;
+        }
+
+        let mut targets = self.targets.into_iter();
+        if let Some(target) = targets.next() {
+            config.target_start_string = target;
+            config.extra_targets = targets.collect();
+        }
+
+        config.validate(self.resume_best)
+    }
+}
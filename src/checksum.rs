@@ -0,0 +1,59 @@
+use std::str;
+
+use super::keccak::keccak256;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Computes EIP-55 checksummed addresses without allocating along the way:
+/// the address is hex-encoded into a fixed scratch buffer, which is then
+/// hashed and recased in place. Reusing one `Checksummer` (and one output
+/// `String`, via `checksum_into`) keeps checksumming cheap even when loose
+/// targets make it a hot path.
+#[derive(Clone, Debug)]
+pub struct Checksummer {
+    ascii: [u8; 40],
+}
+
+impl Default for Checksummer {
+    fn default() -> Self {
+        Self { ascii: [0; 40] }
+    }
+}
+
+impl Checksummer {
+    /// Write the checksummed form of an address (including the `0x` prefix)
+    /// to `out`, replacing its contents.
+    pub fn checksum_into(&mut self, address: &[u8; 20], out: &mut String) {
+        // hex-encode the address as lowercase ASCII
+        for (pair, byte) in self.ascii.chunks_exact_mut(2).zip(address.iter()) {
+            pair[0] = HEX_DIGITS[usize::from(byte >> 4)];
+            pair[1] = HEX_DIGITS[usize::from(byte & 0xf)];
+        }
+
+        // hash the ASCII form of the address
+        let hash = keccak256(&self.ascii);
+
+        // uppercase each letter whose corresponding nibble of the hash is > 7
+        for (i, character) in self.ascii.iter_mut().enumerate() {
+            let nibble = if i % 2 == 0 {
+                hash[i / 2] >> 4
+            } else {
+                hash[i / 2] & 0xf
+            };
+            if nibble > 7 {
+                character.make_ascii_uppercase();
+            }
+        }
+
+        out.clear();
+        out.push_str("0x");
+        out.push_str(str::from_utf8(&self.ascii).unwrap());
+    }
+
+    /// The checksummed form of an address, including the `0x` prefix.
+    pub fn checksum(&mut self, address: &[u8; 20]) -> String {
+        let mut out = String::with_capacity(42);
+        self.checksum_into(address, &mut out);
+        out
+    }
+}
@@ -0,0 +1,82 @@
+use super::keccak::keccak256;
+use super::serde_hex;
+
+/// How deployed addresses are derived.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum AddressScheme {
+    /// CREATE2 (EIP-1014): `keccak256(0xff ++ factory ++ salt ++
+    /// init_code_hash)[12:]`, searched over salts.
+    #[default]
+    Create2,
+    /// CREATE: `keccak256(rlp([deployer, nonce]))[12:]`, searched over the
+    /// deployer's nonces. The factory address is taken as the deployer, and the
+    /// calling address and init code hash play no part.
+    Create,
+}
+
+/// How the factory turns the input it's given into the salt that CREATE2
+/// derives the address from. The salts searched (and written to the results)
+/// are always that input.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum SaltScheme {
+    /// The factory takes a `bytes32` salt and uses it as is, as the immutable
+    /// create2 factory does. (CreateX also takes a `bytes32` salt, so it has
+    /// no salt nonce to give either.)
+    #[default]
+    Raw,
+    /// Safe's proxy factory takes a `uint256 saltNonce` and deploys with
+    /// `keccak256(keccak256(initializer) ++ saltNonce)` as the salt, so each
+    /// salt searched is a salt nonce for the given initializer.
+    Safe {
+        #[serde(with = "serde_hex")]
+        initializer_hash: [u8; 32],
+    },
+}
+
+impl SaltScheme {
+    /// The salt that CREATE2 derives the address from when the factory is
+    /// given `salt`.
+    pub fn create2_salt(&self, salt: &[u8; 32]) -> [u8; 32] {
+        match *self {
+            SaltScheme::Raw => *salt,
+            SaltScheme::Safe { initializer_hash } => {
+                let mut preimage = [0; 64];
+                preimage[..32].copy_from_slice(&initializer_hash);
+                preimage[32..].copy_from_slice(salt);
+                keccak256(&preimage)
+            }
+        }
+    }
+}
+
+/// The keccak-256 hash from which CREATE derives the address that `deployer`
+/// deploys to with the given nonce.
+pub fn create_hash(deployer: &[u8; 20], nonce: u64) -> [u8; 32] {
+    // rlp([deployer, nonce]): a short list holding a 20-byte string and the
+    // nonce as a minimal big-endian integer (a single byte below 0x80, with
+    // zero being the empty string)
+    let nonce_bytes = nonce.to_be_bytes();
+    let significant = &nonce_bytes[nonce.leading_zeros() as usize / 8..];
+
+    let mut encoded = [0u8; 31];
+    let mut len = 22;
+    encoded[1] = 0x80 + 20;
+    encoded[2..22].copy_from_slice(deployer);
+    if nonce == 0 || nonce >= 0x80 {
+        encoded[len] = 0x80 + significant.len() as u8;
+        len += 1;
+    }
+    encoded[len..len + significant.len()].copy_from_slice(significant);
+    len += significant.len();
+    encoded[0] = 0xc0 + (len - 1) as u8;
+
+    keccak256(&encoded[..len])
+}
+
+/// The address that `deployer` deploys a contract to via CREATE with the given
+/// nonce (i.e. as its `nonce`th transaction or contract creation).
+pub fn create_address(deployer: &[u8; 20], nonce: u64) -> [u8; 20] {
+    let mut address_bytes = [0; 20];
+    address_bytes.copy_from_slice(&create_hash(deployer, nonce)[12..]);
+    address_bytes
+}
@@ -0,0 +1,99 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Why the arguments given to `Config::new` were rejected.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    /// No factory address was given.
+    MissingFactoryAddress,
+    /// No calling address was given.
+    MissingCallingAddress,
+    /// No init code hash was given.
+    MissingInitCodeHash,
+    /// No target was given.
+    MissingTarget,
+    /// The target doesn't start with `0x`.
+    TargetMissingPrefix,
+    /// An argument isn't a valid hex string.
+    NotHex { field: &'static str },
+    /// The target has more nibbles than an address (40), so nothing could
+    /// ever match it.
+    TargetTooLong { nibbles: usize },
+    /// An argument decodes to the wrong number of bytes.
+    InvalidHexLength {
+        field: &'static str,
+        expected: usize,
+        got: usize,
+    },
+    /// A file named by an argument (described by `what`) couldn't be read,
+    /// along with why.
+    ReadFile {
+        what: &'static str,
+        path: String,
+        error: String,
+    },
+    /// Any other invalid argument or combination of arguments, described by
+    /// the message.
+    Invalid(&'static str),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::MissingFactoryAddress => {
+                write!(f, "didn't get a factory_address argument.")
+            }
+            ConfigError::MissingCallingAddress => {
+                write!(f, "didn't get a calling_address argument.")
+            }
+            ConfigError::MissingInitCodeHash => write!(f, "didn't get an init_code_hash argument."),
+            ConfigError::MissingTarget => write!(f, "didn't get an target_start argument."),
+            ConfigError::TargetMissingPrefix => {
+                write!(f, "target_start argument must start with 0x.")
+            }
+            ConfigError::NotHex { field } => {
+                write!(f, "could not decode {} argument, not hex string.", field)
+            }
+            ConfigError::TargetTooLong { nibbles } => write!(
+                f,
+                "target_start argument is {} hex characters long, but an address only has 40.",
+                nibbles
+            ),
+            ConfigError::InvalidHexLength {
+                field,
+                expected,
+                got,
+            } => write!(
+                f,
+                "invalid length for {} argument: expected {} bytes, got {}.",
+                field, expected, got
+            ),
+            ConfigError::ReadFile {
+                what,
+                ref path,
+                ref error,
+            } => write!(f, "could not read {} {}: {}.", what, path, error),
+            ConfigError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl ConfigError {
+    /// The error for a file that couldn't be read.
+    pub(crate) fn read_file(what: &'static str, path: &str, error: io::Error) -> Self {
+        ConfigError::ReadFile {
+            what,
+            path: path.to_string(),
+            error: error.to_string(),
+        }
+    }
+}
+
+impl From<&'static str> for ConfigError {
+    fn from(message: &'static str) -> Self {
+        ConfigError::Invalid(message)
+    }
+}
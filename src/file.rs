@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use super::ConfigError;
+
+/// The keys of a configuration file that stand for the positional arguments,
+/// in order. Every other key stands for a flag.
+const POSITIONAL_KEYS: [&str; 4] = ["factory", "caller", "init_code_hash", "target"];
+
+/// The key of the table in a configuration file that names addresses, so
+/// that the factory and callers can be given as `@name`.
+const PRESETS_KEY: &str = "presets";
+
+/// A value in a configuration file.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum FileValue {
+    /// A flag that takes no value, given if `true`.
+    Switch(bool),
+    Number(u64),
+    Text(String),
+    /// Several values for a positional argument (joined with commas, as for
+    /// several callers or targets) or a flag (given once for each).
+    List(Vec<FileValue>),
+    /// Named values, which only the presets table holds.
+    Table(BTreeMap<String, FileValue>),
+}
+
+impl FileValue {
+    /// The value as a single argument.
+    fn arg(&self) -> Result<String, ConfigError> {
+        match *self {
+            FileValue::Number(n) => Ok(n.to_string()),
+            FileValue::Text(ref text) => Ok(text.clone()),
+            FileValue::List(ref values) => values
+                .iter()
+                .map(FileValue::arg)
+                .collect::<Result<Vec<_>, _>>()
+                .map(|args| args.join(",")),
+            FileValue::Switch(_) => Err(ConfigError::Invalid(
+                "only flags can be set to true or false in a config file.",
+            )),
+            FileValue::Table(_) => Err(ConfigError::Invalid(
+                "only the presets can be given as a table in a config file.",
+            )),
+        }
+    }
+
+    /// The arguments that set the given flag to the value.
+    fn flag_args(&self, flag: &str) -> Result<Vec<String>, ConfigError> {
+        match *self {
+            FileValue::Switch(true) => Ok(vec![flag.to_string()]),
+            FileValue::Switch(false) => Ok(vec![]),
+            FileValue::List(ref values) => values
+                .iter()
+                .map(|value| value.flag_args(flag))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|args| args.concat()),
+            _ => Ok(vec![flag.to_string(), self.arg()?]),
+        }
+    }
+}
+
+/// Merge the arguments in a configuration file with those given on the
+/// command line after `--config <path>`. The file is TOML, or JSON if its
+/// path ends in `.json`, and holds the positional arguments as `factory`,
+/// `caller`, `init_code_hash` and `target` (a list of callers or targets
+/// stands for several), and any flag under its name without the dashes, e.g.
+/// `threads = 8` for `--threads 8` or `progress = true` for `--progress`.
+/// A `presets` table may name addresses, e.g. `factory = "0x..."`, so that
+/// the factory and callers (in the file or on the command line) can be given
+/// as `@factory`; naming an address the table doesn't hold is an error.
+///
+/// Positional arguments on the command line replace the file's in order, and
+/// its flags follow the file's, so that they override them. A flag set in
+/// the file can't be unset on the command line.
+pub fn with_config_file(path: &str, cli: Vec<String>) -> Result<Vec<String>, ConfigError> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| ConfigError::read_file("config file", path, e))?;
+    let file: BTreeMap<String, FileValue> = if path.ends_with(".json") {
+        serde_json::from_str(&contents).map_err(|_| "config file must be a JSON object.")?
+    } else {
+        ::toml::from_str(&contents).map_err(|_| "config file must be a TOML table.")?
+    };
+
+    let presets = match file.get(PRESETS_KEY) {
+        Some(FileValue::Table(presets)) => presets
+            .iter()
+            .map(|(name, address)| match *address {
+                FileValue::Text(ref address) => Ok((name.clone(), address.clone())),
+                _ => Err("presets must map names to addresses."),
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()?,
+        Some(_) => return Err(ConfigError::Invalid("presets must be a table.")),
+        None => BTreeMap::new(),
+    };
+
+    let mut positional: Vec<Option<String>> = POSITIONAL_KEYS
+        .iter()
+        .map(|&key| file.get(key).map(FileValue::arg).transpose())
+        .collect::<Result<_, _>>()?;
+
+    // leading arguments on the command line are positional
+    let cli_positional = cli.iter().take_while(|arg| !arg.starts_with("--")).count();
+    for (i, arg) in cli[..cli_positional].iter().enumerate() {
+        match positional.get_mut(i) {
+            Some(slot) => *slot = Some(arg.clone()),
+            None => positional.push(Some(arg.clone())),
+        }
+    }
+
+    // resolve any `@name` factory or callers from the presets
+    if let Some(Some(factory)) = positional.get_mut(0) {
+        *factory = resolve_preset(&presets, factory)?;
+    }
+    if let Some(Some(callers)) = positional.get_mut(1) {
+        *callers = callers
+            .split(',')
+            .map(|caller| resolve_preset(&presets, caller))
+            .collect::<Result<Vec<_>, _>>()?
+            .join(",");
+    }
+
+    // a missing positional argument is reported as such by `Config::new`
+    let mut args: Vec<String> = positional.into_iter().map_while(|arg| arg).collect();
+    for (key, value) in &file {
+        if !POSITIONAL_KEYS.contains(&key.as_str()) && key != PRESETS_KEY {
+            args.extend(value.flag_args(&format!("--{}", key.replace('_', "-")))?);
+        }
+    }
+    args.extend(cli.into_iter().skip(cli_positional));
+    Ok(args)
+}
+
+/// Replace an `@name` address with the address of that name in the presets,
+/// leaving any other argument as it is.
+fn resolve_preset(presets: &BTreeMap<String, String>, arg: &str) -> Result<String, &'static str> {
+    match arg.strip_prefix('@') {
+        Some(name) => presets
+            .get(name)
+            .cloned()
+            .ok_or("an @name address does not match any name in the config file's presets."),
+        None => Ok(arg.to_string()),
+    }
+}
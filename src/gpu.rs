@@ -0,0 +1,635 @@
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use console::Term;
+use ocl::core;
+use ocl::{Buffer, Context, Device, MemFlags, Platform, ProQue, Program, Queue};
+use rand::{thread_rng, Rng};
+use separator::Separatable;
+use terminal_size::{terminal_size, Height, Width};
+
+use super::{
+    announce_search, handle_solution, line_format, open_sink, search_alongside, target_start_bytes,
+    Config, LineFormat, ResultSink, RunMeta, RUN_META_FILE,
+};
+
+// workset size (tweak this!)
+const WORK_SIZE: u32 = 0x4000000; // max. 0x15400000 to abs. max 0xffffffff
+
+const WORK_FACTOR: u128 = (WORK_SIZE as u128) / 1_000_000;
+const EIGHT_ZERO_BYTES: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 0];
+
+// seconds between progress lines when not writing to a terminal
+const PLAIN_PROGRESS_INTERVAL: u64 = 10;
+
+static KERNEL_SRC: &str = include_str!("./kernels/keccak256.cl");
+
+/// Given a Config object with a factory address, a caller address, a keccak-256
+/// hash of the contract initialization code, and a device ID, search for salts
+/// using OpenCL that will enable the factory contract to deploy a contract to a
+/// gas-efficient address via CREATE2. This method also takes threshold values
+/// for both leading zero bytes and total zero bytes - any address that does not
+/// meet or exceed the threshold will not be returned. Default threshold values
+/// are three leading zeroes or five total zeroes. The thresholds only apply
+/// when the target is a bare `0x`; otherwise addresses are matched against the
+/// target prefix, as on the CPU.
+///
+/// The 32-byte salt is constructed as follows:
+///   - the 20-byte calling address (to prevent frontrunning)
+///   - a random 4-byte segment (to prevent collisions with other runs)
+///   - a 4-byte segment unique to each work group running in parallel
+///   - a 4-byte nonce segment (incrementally stepped through during the run)
+///
+/// The last two together are a little-endian 8-byte nonce, the work item in its
+/// low half (see `NonceOrder` for the CPU's counterpart).
+///
+/// With `--gpu all`, every device on the default platform searches at once,
+/// each on its own thread. The range of random segments is split evenly
+/// between the devices (see `device_segment`) so that no two devices ever
+/// search the same salts.
+///
+/// When a salt that will result in the creation of a gas-efficient contract
+/// address is found, it will be appended to `efficient_addresses.txt` along
+/// with the resultant address and the "value" (i.e. approximate rarity) of the
+/// resultant address.
+///
+/// The search runs until `stop` is set (see `stop_on_termination`), then
+/// finishes the kernel run in hand and flushes its results.
+///
+/// This method is still highly experimental and could almost certainly use
+/// further optimization - contributions are more than welcome!
+pub fn gpu(config: Config, stop: &AtomicBool) -> Result<(), Box<dyn Error>> {
+    let format = line_format(&config);
+
+    // (create if necessary) and open the destination where found salts go
+    let sink = open_sink(&config, format);
+
+    // record the configuration of the run alongside its results
+    if config.run_meta {
+        RunMeta::new(&config).write(RUN_META_FILE)?;
+    }
+
+    gpu_search(&config, &*sink, stop, &Found::default())?;
+
+    sink.flush()?;
+    Ok(())
+}
+
+/// Search on the CPU and the GPU (or GPUs) at once, writing the addresses
+/// found by either to the same destination. The progress output of the GPU
+/// search also lists the addresses found on the CPU. The combined rate is
+/// roughly the sum of the two, though the CPU search competes with the host
+/// side of the GPU search for CPU time, so the gain over the GPU alone is
+/// usually modest. Both stop once `stop` is set.
+pub fn hybrid(config: Config, stop: &AtomicBool) -> Result<(), Box<dyn Error>> {
+    let format = line_format(&config);
+
+    // (create if necessary) and open the destination where found salts go
+    let sink = open_sink(&config, format);
+
+    // record the configuration of the run alongside its results
+    if config.run_meta {
+        RunMeta::new(&config).write(RUN_META_FILE)?;
+    }
+
+    let found = Found::default();
+
+    announce_search(&config);
+    let result = search_alongside(
+        &config,
+        &*sink,
+        stop,
+        &|address| found.record(format.line(address), config.announces(address)),
+        || gpu_search(&config, &*sink, stop, &found),
+    );
+
+    sink.flush()?;
+    Ok(result?)
+}
+
+/// Run the GPU search on the device (or devices) selected by the config,
+/// writing each address found to `sink` and showing the progress of each
+/// device along with the addresses in `found`, until `stop` is set or a device
+/// runs into an error.
+fn gpu_search(
+    config: &Config,
+    sink: &dyn ResultSink,
+    stop: &AtomicBool,
+    found: &Found,
+) -> ocl::Result<()> {
+    // set up a platform to use
+    let platform = Platform::default();
+
+    // set up the devices to use
+    let devices: Vec<Device> = if config.all_gpus {
+        Device::list_all(platform)?
+    } else {
+        vec![Device::by_idx_wrap(platform, config.gpu_device as usize)?]
+    };
+
+    if config.all_gpus {
+        info!(
+            "Setting up experimental OpenCL miner using all {} devices...",
+            devices.len()
+        );
+    } else {
+        info!(
+            "Setting up experimental OpenCL miner using device {}...",
+            config.gpu_device
+        );
+    }
+
+    let format = line_format(config);
+
+    // track the progress of each device
+    let progress: Vec<DeviceProgress> = devices
+        .iter()
+        .map(|device| {
+            Ok(DeviceProgress {
+                name: device.name()?,
+                cycles: AtomicU64::new(0),
+                search_space: Mutex::new(String::new()),
+            })
+        })
+        .collect::<ocl::Result<_>>()?;
+
+    // set up a controller for terminal output; without a terminal (e.g. when
+    // piped or run as a service) there's no screen to redraw, so progress is
+    // logged as plain lines instead
+    let term = Term::stdout();
+    let interactive = term.is_term();
+    let mut ticks: u64 = 0;
+    let mut logged_found = 0;
+
+    // determine the start time
+    let start_time: f64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as f64;
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = devices
+            .iter()
+            .zip(progress.iter())
+            .enumerate()
+            .map(|(slot, (&device, progress))| {
+                let segments = (slot, devices.len());
+                scope.spawn(move || {
+                    search_device(
+                        config, device, segments, sink, format, stop, found, progress,
+                    )
+                })
+            })
+            .collect();
+
+        loop {
+            // stop every device as soon as any device runs into an error
+            if stop.load(Ordering::Relaxed) || workers.iter().any(|worker| worker.is_finished()) {
+                stop.store(true, Ordering::Relaxed);
+                return workers
+                    .into_iter()
+                    .try_for_each(|worker| worker.join().unwrap());
+            }
+
+            // calculate the current time
+            let current_time: f64 = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as f64;
+
+            // get the total runtime and parse into hours : minutes : seconds
+            let total_runtime = current_time - start_time;
+            let total_runtime_hrs = total_runtime as u64 / (3600);
+            let total_runtime_mins = (total_runtime as u64 - total_runtime_hrs * 3600) / 60;
+            let total_runtime_secs = total_runtime
+                - (total_runtime_hrs * 3600) as f64
+                - (total_runtime_mins * 60) as f64;
+
+            // determine the number of attempts being made per second
+            let mut rate: f64 = 0.0;
+            if total_runtime > 0.0 {
+                rate = 1.0 / total_runtime;
+            }
+            let cycles: Vec<u64> = progress
+                .iter()
+                .map(|progress| progress.cycles.load(Ordering::Relaxed))
+                .collect();
+            let cumulative_nonce: u64 = cycles.iter().sum();
+            let work_rate: u128 = WORK_FACTOR * cumulative_nonce as u128;
+
+            if !interactive {
+                // log each newly found solution, and the progress periodically
+                let found_list = found.list.lock().unwrap();
+                for line in &found_list[logged_found..] {
+                    term.write_line(line)?;
+                }
+                logged_found = found_list.len();
+                drop(found_list);
+
+                if ticks.is_multiple_of(PLAIN_PROGRESS_INTERVAL) {
+                    term.write_line(&format!(
+                        "runtime {}:{:02}:{:02}, {} cycles, {:.2} million attempts per second, \
+                         {} found",
+                        total_runtime_hrs,
+                        total_runtime_mins,
+                        total_runtime_secs,
+                        cumulative_nonce,
+                        work_rate as f64 * rate,
+                        found.count.load(Ordering::Relaxed)
+                    ))?;
+                }
+                ticks += 1;
+
+                thread::sleep(Duration::from_secs(1));
+                continue;
+            }
+
+            // clear the terminal screen
+            term.clear_screen()?;
+
+            // calculate the terminal height, defaulting to a height of ten rows
+            let size = terminal_size();
+            let height: u16;
+            if let Some((Width(_w), Height(h))) = size {
+                height = h;
+            } else {
+                height = 10;
+            }
+
+            // display information about the total runtime and work size
+            term.write_line(&format!(
+                "total runtime: {}:{:02}:{:02} ({} cycles)\t\t\t\
+                  work size per cycle: {}",
+                total_runtime_hrs,
+                total_runtime_mins,
+                total_runtime_secs,
+                cumulative_nonce,
+                WORK_SIZE.separated_string()
+            ))?;
+
+            // display information about the attempt rate and found solutions
+            term.write_line(&format!(
+                "rate: {:.2} million attempts per second\t\t\t\
+                  total found this run: {}",
+                work_rate as f64 * rate,
+                found.count.load(Ordering::Relaxed)
+            ))?;
+
+            // display information about the search criteria of each device
+            term.write_line(&format!(
+                "threshold: {} leading or {} total zeroes",
+                config.leading_zeroes_threshold, config.total_zeroes_threshold
+            ))?;
+            for (progress, cycles) in progress.iter().zip(cycles) {
+                term.write_line(&format!(
+                    "{}: {:.2} million attempts per second\t\t\
+                      current search space: {}",
+                    progress.name,
+                    (WORK_FACTOR * cycles as u128) as f64 * rate,
+                    progress.search_space.lock().unwrap()
+                ))?;
+            }
+
+            // display recently found solutions based on terminal height
+            let header_rows = 4 + progress.len() as u16;
+            let rows: usize = if height <= header_rows {
+                1
+            } else {
+                (height - header_rows) as usize
+            };
+            let found_list = found.list.lock().unwrap();
+            let last_rows: Vec<String> = found_list.iter().cloned().rev().take(rows).collect();
+            let ordered: Vec<String> = last_rows.iter().cloned().rev().collect();
+            let recently_found = &ordered.join("\n");
+            term.write_line(recently_found)?;
+            drop(found_list);
+
+            // we don't want to print too fast
+            thread::sleep(Duration::from_secs(1));
+        }
+    })
+}
+
+/// The addresses found by every device over the course of a run.
+#[derive(Default)]
+struct Found {
+    count: AtomicU64,
+    list: Mutex<Vec<String>>,
+}
+
+impl Found {
+    /// Count a found address, and list it in the progress output if it's to
+    /// be announced.
+    fn record(&self, line: String, announce: bool) {
+        if announce {
+            self.list.lock().unwrap().push(line);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// The progress of a single device, as shown in the progress output.
+struct DeviceProgress {
+    name: String,
+    cycles: AtomicU64,
+    search_space: Mutex<String>,
+}
+
+/// Choose the random 4-byte salt segment searched by the device in the given
+/// slot (out of `devices` devices in total). The range of possible segments is
+/// split into one contiguous range per device, so that devices searching side
+/// by side never search the same salts.
+pub fn device_segment(random: u32, slot: usize, devices: usize) -> [u8; 4] {
+    let span: u64 = (1 << 32) / devices as u64;
+    let segment = slot as u64 * span + u64::from(random) % span;
+    let mut bytes = [0; 4];
+    BigEndian::write_u32(&mut bytes, segment as u32);
+    bytes
+}
+
+/// The full salt searched by the kernel for the given random segment and
+/// nonce: the caller, then the segment, then the nonce little-endian.
+///
+/// This is the salt the CPU searches under `NonceOrder::LittleEndian` when its
+/// six-byte random segment is this segment followed by the two lowest bytes of
+/// the nonce, and its own nonce is the rest of the nonce (`nonce >> 16`).
+pub fn gpu_salt(caller: &[u8; 20], segment: [u8; 4], nonce: u64) -> [u8; 32] {
+    let mut salt = [0; 32];
+    salt[..20].copy_from_slice(caller);
+    salt[20..24].copy_from_slice(&segment);
+    salt[24..].copy_from_slice(&u64_to_le_fixed_8(&nonce));
+    salt
+}
+
+/// Repeatedly run the kernel on a single device, handling each solution that
+/// it finds, until `stop` is set or an error occurs.
+#[allow(clippy::too_many_arguments)]
+fn search_device(
+    config: &Config,
+    device: Device,
+    (slot, devices): (usize, usize),
+    sink: &dyn ResultSink,
+    format: LineFormat,
+    stop: &AtomicBool,
+    found: &Found,
+    progress: &DeviceProgress,
+) -> ocl::Result<()> {
+    // set up the context to use
+    let context = Context::builder()
+        .platform(Platform::default())
+        .devices(device)
+        .build()?;
+
+    // get the caller from config object
+    let caller: [u8; 20] = config.calling_addresses[0];
+
+    // generate the kernel source code with the define macros
+    let kernel_src = &kernel_source(config);
+
+    // set up the program to use
+    let program = Program::builder()
+        .devices(device)
+        .src(kernel_src)
+        .build(&context)?;
+
+    // set up the queue to use
+    let queue = Queue::new(&context, device, None)?;
+
+    // set up the "proqueue" (or amalgamation of various elements) to use
+    let ocl_pq = ProQue::new(context, queue, program, Some(WORK_SIZE));
+
+    // create a random number generator
+    let mut rng = thread_rng();
+
+    // the last work duration in milliseconds
+    let mut work_duration_millis: u64 = 0;
+
+    // begin searching for addresses
+    loop {
+        // choose a random 4-byte salt segment within this device's range
+        let salt = device_segment(rng.next_u32(), slot, devices);
+
+        // construct the 4-byte message to hash, leaving last 8 of salt empty
+        let message: [u8; 4] = salt;
+
+        // build a corresponding buffer for passing the message to the kernel
+        let message_buffer = Buffer::builder()
+            .queue(ocl_pq.queue().clone())
+            .flags(MemFlags::new().read_only())
+            .len(4)
+            .copy_host_slice(&message)
+            .build()?;
+
+        // reset nonce & create a buffer to view it in little-endian
+        // for more uniformly distributed nonces, we shall initialize it to a random value
+        let mut nonce: [u32; 1] = [rng.next_u32()];
+        let mut view_buf = [0; 8];
+
+        // build a corresponding buffer for passing the nonce to the kernel
+        let mut nonce_buffer = Buffer::builder()
+            .queue(ocl_pq.queue().clone())
+            .flags(MemFlags::new().read_only())
+            .len(1)
+            .copy_host_slice(&nonce)
+            .build()?;
+
+        // establish a buffer for nonces that result in desired addresses
+        let mut solutions: Vec<u64> = vec![0; 1];
+        let solutions_buffer: Buffer<u64> = Buffer::builder()
+            .queue(ocl_pq.queue().clone())
+            .flags(MemFlags::new().write_only())
+            .len(1)
+            .copy_host_slice(&solutions)
+            .build()?;
+
+        // repeatedly enqueue kernel to search for new addresses
+        loop {
+            if stop.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+
+            // build the kernel and define the type of each buffer
+            let kern = ocl_pq
+                .kernel_builder("hashMessage")
+                .arg_named("message", None::<&Buffer<u8>>)
+                .arg_named("nonce", None::<&Buffer<u32>>)
+                .arg_named("solutions", None::<&Buffer<u64>>)
+                .build()?;
+
+            // set each buffer
+            kern.set_arg("message", Some(&message_buffer))?;
+            kern.set_arg("nonce", Some(&nonce_buffer))?;
+            kern.set_arg("solutions", &solutions_buffer)?;
+
+            // enqueue the kernel
+            unsafe {
+                kern.enq()?;
+            }
+
+            // fill the buffer for viewing the properly-formatted nonce
+            LittleEndian::write_u64(&mut view_buf, (nonce[0] as u64) << 32);
+            *progress.search_space.lock().unwrap() = format!(
+                "{}xxxxxxxx{:08x}",
+                hex::encode(salt),
+                BigEndian::read_u64(&view_buf)
+            );
+
+            // record the start time of the work
+            let mut now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+            let work_start_time_millis = now.as_secs() * 1000 + now.subsec_nanos() as u64 / 1000000;
+
+            // sleep for 98% of the previous work duration to conserve CPU
+            if work_duration_millis != 0 {
+                thread::sleep(Duration::from_millis(work_duration_millis * 980 / 1000));
+            }
+
+            // read the solutions from the device
+            solutions_buffer.read(&mut solutions).enq()?;
+
+            // increment the cumulative nonce (does not reset after a match)
+            progress.cycles.fetch_add(1, Ordering::Relaxed);
+
+            // record the end time of the work and compute how long the work took
+            now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+            work_duration_millis = (now.as_secs() * 1000 + now.subsec_nanos() as u64 / 1000000)
+                - work_start_time_millis;
+
+            // if at least one solution is found, end the loop
+            if solutions[0] != 0 {
+                break;
+            }
+
+            // if no solution has yet been found, increment the nonce
+            nonce[0] += 1;
+
+            // update the nonce buffer with the incremented nonce value
+            nonce_buffer = Buffer::builder()
+                .queue(ocl_pq.queue().clone())
+                .flags(MemFlags::new().read_write())
+                .len(1)
+                .copy_host_slice(&nonce)
+                .build()?;
+        }
+
+        // iterate over each solution, first converting to a fixed array
+        solutions.iter().filter(|&i| *i != 0).for_each(|&solution| {
+            // proceed if a solution is found at the given location
+            if u64_to_le_fixed_8(&solution) != EIGHT_ZERO_BYTES {
+                let full_salt = gpu_salt(&caller, salt, solution);
+
+                if let Some(address) = handle_solution(config, &full_salt) {
+                    found.record(format.line(&address), config.announces(&address));
+
+                    sink.record(&address)
+                        .expect("Couldn't write found address to output file.");
+                }
+            }
+        });
+    }
+}
+
+/// An OpenCL device that a search can be run on.
+pub struct GpuDevice {
+    /// The name of the platform that the device belongs to.
+    pub platform: String,
+    /// Whether the device belongs to the default platform, whose devices are
+    /// the ones selected by the `gpu_device` argument. (The default platform
+    /// can be changed via the `OCL_DEFAULT_PLATFORM_IDX` environment variable.)
+    pub default_platform: bool,
+    /// The index of the device within its platform.
+    pub index: usize,
+    /// The name of the device.
+    pub name: String,
+}
+
+/// List every available OpenCL device, grouped by platform. Machines without
+/// any OpenCL platform installed yield an empty list rather than an error.
+pub fn list_devices() -> ocl::Result<Vec<GpuDevice>> {
+    // an error retrieving the platforms means that none are installed
+    let platform_ids = match core::get_platform_ids() {
+        Ok(platform_ids) => platform_ids,
+        Err(_) => return Ok(vec![]),
+    };
+    let default_platform = core::default_platform().ok();
+
+    let mut devices = vec![];
+    for platform_id in platform_ids {
+        let platform = Platform::new(platform_id);
+        let platform_name = platform.name()?;
+
+        for (index, device) in Device::list_all(platform)?.iter().enumerate() {
+            devices.push(GpuDevice {
+                platform: platform_name.clone(),
+                default_platform: Some(platform_id) == default_platform,
+                index,
+                name: device.name()?,
+            });
+        }
+    }
+
+    Ok(devices)
+}
+
+/// Generate the source of the OpenCL kernel for the given configuration: the
+/// factory address, calling address, and initialization code hash are baked
+/// in as `S_<n>` defines (one per byte of the message being hashed), along
+/// with the leading and total zero byte thresholds and the bytes of the target
+/// prefix (`PREFIX_LENGTH` and `PREFIX_BYTES`, with `PREFIX_LAST_MASK` to
+/// compare only the high nibble of the last byte of an odd-length prefix).
+/// When a prefix is given the kernel matches on it alone; the thresholds only
+/// apply to a bare `0x` target.
+pub fn kernel_source(config: &Config) -> String {
+    let factory: [u8; 20] = config.factory_address;
+    let caller: [u8; 20] = config.calling_addresses[0];
+    let init_hash: [u8; 32] = config.init_code_hash;
+    let prefix = target_start_bytes(config);
+
+    format!(
+        "{}\n{}\n{}\n#define LEADING_ZEROES {}\n#define TOTAL_ZEROES {}\n\
+         #define PREFIX_LENGTH {}\n#define PREFIX_BYTES {}\n#define PREFIX_LAST_MASK {}u\n{}",
+        factory
+            .iter()
+            .enumerate()
+            .map(|(i, x)| format!("#define S_{} {}u\n", i + 1, x))
+            .collect::<String>(),
+        caller
+            .iter()
+            .enumerate()
+            .map(|(i, x)| format!("#define S_{} {}u\n", i + 21, x))
+            .collect::<String>(),
+        init_hash
+            .iter()
+            .enumerate()
+            .map(|(i, x)| format!("#define S_{} {}u\n", i + 53, x))
+            .collect::<String>(),
+        config.leading_zeroes_threshold,
+        config.total_zeroes_threshold,
+        prefix.pattern.len(),
+        prefix
+            .bytes()
+            .iter()
+            .map(|x| format!("{}u", x))
+            .collect::<Vec<String>>()
+            .join(", "),
+        if prefix.nibbles % 2 == 1 { 0xf0 } else { 0xff },
+        KERNEL_SRC
+    )
+}
+
+/// Convert 64-bit unsigned integer to little-endian fixed array of eight bytes.
+fn u64_to_le_fixed_8(x: &u64) -> [u8; 8] {
+    let mask: u64 = 0xff;
+    let b1: u8 = ((x >> 56) & mask) as u8;
+    let b2: u8 = ((x >> 48) & mask) as u8;
+    let b3: u8 = ((x >> 40) & mask) as u8;
+    let b4: u8 = ((x >> 32) & mask) as u8;
+    let b5: u8 = ((x >> 24) & mask) as u8;
+    let b6: u8 = ((x >> 16) & mask) as u8;
+    let b7: u8 = ((x >> 8) & mask) as u8;
+    let b8: u8 = (x & mask) as u8;
+    [b8, b7, b6, b5, b4, b3, b2, b1]
+}
@@ -0,0 +1,97 @@
+use std::fmt;
+
+use super::keccak::Keccak256;
+
+/// The environment variable that the key for `--hmac` is read from.
+pub const HMAC_KEY_VAR: &str = "CREATE2CRUNCH_HMAC_KEY";
+
+/// The block size (rate) of keccak-256, in bytes.
+const BLOCK_SIZE: usize = 136;
+
+/// The marker that introduces the HMAC at the end of a line of output.
+const HMAC_MARKER: &str = " (hmac 0x";
+
+/// A shared secret for authenticating lines of output with HMAC-keccak256
+/// (HMAC as specified by RFC 2104, with keccak-256 as the hash), so that
+/// anyone holding the secret can check that a line hasn't been altered since
+/// it was written. The key is never printed.
+#[derive(Clone, Copy)]
+pub struct HmacKey {
+    inner_pad: [u8; BLOCK_SIZE],
+    outer_pad: [u8; BLOCK_SIZE],
+}
+
+impl HmacKey {
+    /// Prepare a key for signing; keys longer than a block are hashed first.
+    pub fn new(key: &[u8]) -> Self {
+        let mut block = [0; BLOCK_SIZE];
+        if key.len() > BLOCK_SIZE {
+            block[..32].copy_from_slice(&keccak256(&[key]));
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+
+        let mut inner_pad = [0x36; BLOCK_SIZE];
+        let mut outer_pad = [0x5c; BLOCK_SIZE];
+        for ((inner, outer), byte) in inner_pad.iter_mut().zip(outer_pad.iter_mut()).zip(&block) {
+            *inner ^= byte;
+            *outer ^= byte;
+        }
+
+        Self {
+            inner_pad,
+            outer_pad,
+        }
+    }
+
+    /// The HMAC of a message.
+    pub fn sign(&self, message: &[u8]) -> [u8; 32] {
+        let inner = keccak256(&[&self.inner_pad, message]);
+        keccak256(&[&self.outer_pad, &inner])
+    }
+
+    /// Append the HMAC of a line to it, as `<line> (hmac 0x<hmac>)`.
+    pub fn sign_line(&self, line: &str) -> String {
+        format!(
+            "{}{}{})",
+            line,
+            HMAC_MARKER,
+            hex::encode(self.sign(line.as_bytes()))
+        )
+    }
+
+    /// Whether a line written by `sign_line` is intact. Lines without an HMAC
+    /// don't verify.
+    pub fn verify_line(&self, line: &str) -> bool {
+        let (message, hmac) = match line.rfind(HMAC_MARKER) {
+            Some(index) => (&line[..index], &line[index + HMAC_MARKER.len()..]),
+            None => return false,
+        };
+
+        match hex::decode(hmac.trim_end().trim_end_matches(')')) {
+            Ok(hmac) => hmac == self.sign(message.as_bytes()),
+            Err(_) => false,
+        }
+    }
+}
+
+impl fmt::Debug for HmacKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HmacKey(..)")
+    }
+}
+
+impl PartialEq for HmacKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner_pad[..] == other.inner_pad[..]
+    }
+}
+
+/// Hash the concatenation of several byte strings with keccak-256.
+fn keccak256(parts: &[&[u8]]) -> [u8; 32] {
+    let mut keccak = Keccak256::new();
+    for part in parts {
+        keccak.update(part);
+    }
+    keccak.finalize()
+}
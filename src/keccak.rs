@@ -0,0 +1,215 @@
+use tiny_keccak::{Hasher, Keccak};
+
+/// The length of a CREATE2 preimage: `0xff ++ factory ++ salt ++ init_code_hash`.
+pub const PREIMAGE_LENGTH: usize = 85;
+
+/// The keccak-256 hash of `data`. Everything outside the hot path of the
+/// search hashes through this or `Keccak256`, so that the underlying
+/// implementation can be swapped out here alone.
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// An incremental keccak-256 hasher. Cloning one partway through lets a
+/// common prefix be absorbed once and reused for many messages.
+#[derive(Clone)]
+pub struct Keccak256(Keccak);
+
+impl Keccak256 {
+    pub fn new() -> Self {
+        Keccak256(Keccak::v256())
+    }
+
+    /// Absorb more of the message.
+    #[inline]
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    /// Finish hashing the message and return its hash.
+    #[inline]
+    pub fn finalize(self) -> [u8; 32] {
+        let mut hash = [0; 32];
+        self.0.finalize(&mut hash);
+        hash
+    }
+}
+
+impl Default for Keccak256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An implementation of keccak-256 that addresses can be derived with. The
+/// engine is selected at runtime based on the features of the CPU in use, so
+/// a single binary runs everywhere while still using faster instructions
+/// where they're available.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeccakEngine {
+    /// The portable implementation provided by `tiny_keccak`.
+    Scalar,
+    /// Keccak-f[1600] on NEON registers using the ARMv8.2 SHA3 instructions
+    /// (`EOR3`, `RAX1`, `XAR` and `BCAX`), available on Apple Silicon and most
+    /// recent ARM servers.
+    Neon,
+}
+
+impl KeccakEngine {
+    /// Select the fastest engine supported by the current CPU.
+    pub fn detect() -> Self {
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("sha3") {
+                return KeccakEngine::Neon;
+            }
+        }
+
+        KeccakEngine::Scalar
+    }
+
+    /// Compute the keccak-256 hash of a CREATE2 preimage.
+    #[inline]
+    pub fn hash_preimage(self, preimage: &[u8; PREIMAGE_LENGTH]) -> [u8; 32] {
+        match self {
+            // only ever selected by `detect` when the CPU supports it
+            #[cfg(target_arch = "aarch64")]
+            KeccakEngine::Neon => unsafe { neon::keccak256_single_block(preimage) },
+            _ => keccak256(preimage),
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::*;
+
+    use super::PREIMAGE_LENGTH;
+
+    /// The rate of keccak-256 in bytes, i.e. the size of an absorbed block.
+    const RATE: usize = 136;
+
+    /// The round constants applied by the iota step.
+    const ROUND_CONSTANTS: [u64; 24] = [
+        0x0000000000000001,
+        0x0000000000008082,
+        0x800000000000808a,
+        0x8000000080008000,
+        0x000000000000808b,
+        0x0000000080000001,
+        0x8000000080008081,
+        0x8000000000008009,
+        0x000000000000008a,
+        0x0000000000000088,
+        0x0000000080008009,
+        0x000000008000000a,
+        0x000000008000808b,
+        0x800000000000008b,
+        0x8000000000008089,
+        0x8000000000008003,
+        0x8000000000008002,
+        0x8000000000000080,
+        0x000000000000800a,
+        0x800000008000000a,
+        0x8000000080008081,
+        0x8000000000008080,
+        0x0000000080000001,
+        0x8000000080008008,
+    ];
+
+    /// Hash a message that fits within a single block. Only the low lane of
+    /// each NEON register is used; the SHA3 instructions still save work over
+    /// the scalar permutation by fusing the xors and rotations of each step.
+    #[target_feature(enable = "neon,sha3")]
+    pub unsafe fn keccak256_single_block(message: &[u8; PREIMAGE_LENGTH]) -> [u8; 32] {
+        // absorb the padded message into the first 17 lanes of the state
+        let mut block = [0u8; RATE];
+        block[..PREIMAGE_LENGTH].copy_from_slice(message);
+        block[PREIMAGE_LENGTH] ^= 0x01;
+        block[RATE - 1] ^= 0x80;
+
+        let mut a = [vdupq_n_u64(0); 25];
+        for (lane, bytes) in a.iter_mut().zip(block.chunks(8)) {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(bytes);
+            *lane = vdupq_n_u64(u64::from_le_bytes(word));
+        }
+
+        keccak_f1600(&mut a);
+
+        // squeeze the first four lanes
+        let mut res = [0u8; 32];
+        for (bytes, lane) in res.chunks_mut(8).zip(a.iter()) {
+            bytes.copy_from_slice(&vgetq_lane_u64::<0>(*lane).to_le_bytes());
+        }
+        res
+    }
+
+    /// Apply the keccak-f[1600] permutation to a state held in NEON registers.
+    #[target_feature(enable = "neon,sha3")]
+    unsafe fn keccak_f1600(a: &mut [uint64x2_t; 25]) {
+        let mut b = [vdupq_n_u64(0); 25];
+
+        for &round_constant in ROUND_CONSTANTS.iter() {
+            // theta: column parities
+            let c = [
+                veor3q_u64(veor3q_u64(a[0], a[5], a[10]), a[15], a[20]),
+                veor3q_u64(veor3q_u64(a[1], a[6], a[11]), a[16], a[21]),
+                veor3q_u64(veor3q_u64(a[2], a[7], a[12]), a[17], a[22]),
+                veor3q_u64(veor3q_u64(a[3], a[8], a[13]), a[18], a[23]),
+                veor3q_u64(veor3q_u64(a[4], a[9], a[14]), a[19], a[24]),
+            ];
+            let d = [
+                vrax1q_u64(c[4], c[1]),
+                vrax1q_u64(c[0], c[2]),
+                vrax1q_u64(c[1], c[3]),
+                vrax1q_u64(c[2], c[4]),
+                vrax1q_u64(c[3], c[0]),
+            ];
+
+            // theta, rho and pi: each lane is xored with its column's theta
+            // value, rotated, and moved to its new position (`XAR` rotates
+            // right, so each left rotation `r` is expressed as `64 - r`)
+            b[0] = veorq_u64(a[0], d[0]);
+            b[1] = vxarq_u64::<20>(a[6], d[1]);
+            b[2] = vxarq_u64::<21>(a[12], d[2]);
+            b[3] = vxarq_u64::<43>(a[18], d[3]);
+            b[4] = vxarq_u64::<50>(a[24], d[4]);
+            b[5] = vxarq_u64::<36>(a[3], d[3]);
+            b[6] = vxarq_u64::<44>(a[9], d[4]);
+            b[7] = vxarq_u64::<61>(a[10], d[0]);
+            b[8] = vxarq_u64::<19>(a[16], d[1]);
+            b[9] = vxarq_u64::<3>(a[22], d[2]);
+            b[10] = vxarq_u64::<63>(a[1], d[1]);
+            b[11] = vxarq_u64::<58>(a[7], d[2]);
+            b[12] = vxarq_u64::<39>(a[13], d[3]);
+            b[13] = vxarq_u64::<56>(a[19], d[4]);
+            b[14] = vxarq_u64::<46>(a[20], d[0]);
+            b[15] = vxarq_u64::<37>(a[4], d[4]);
+            b[16] = vxarq_u64::<28>(a[5], d[0]);
+            b[17] = vxarq_u64::<54>(a[11], d[1]);
+            b[18] = vxarq_u64::<49>(a[17], d[2]);
+            b[19] = vxarq_u64::<8>(a[23], d[3]);
+            b[20] = vxarq_u64::<2>(a[2], d[2]);
+            b[21] = vxarq_u64::<9>(a[8], d[3]);
+            b[22] = vxarq_u64::<25>(a[14], d[4]);
+            b[23] = vxarq_u64::<23>(a[15], d[0]);
+            b[24] = vxarq_u64::<62>(a[21], d[1]);
+
+            // chi: `BCAX` computes `x ^ (y & !z)`
+            for row in 0..5 {
+                let r = row * 5;
+                a[r] = vbcaxq_u64(b[r], b[r + 2], b[r + 1]);
+                a[r + 1] = vbcaxq_u64(b[r + 1], b[r + 3], b[r + 2]);
+                a[r + 2] = vbcaxq_u64(b[r + 2], b[r + 4], b[r + 3]);
+                a[r + 3] = vbcaxq_u64(b[r + 3], b[r], b[r + 4]);
+                a[r + 4] = vbcaxq_u64(b[r + 4], b[r + 1], b[r]);
+            }
+
+            // iota
+            a[0] = veorq_u64(a[0], vdupq_n_u64(round_constant));
+        }
+    }
+}
@@ -223,6 +223,19 @@ static inline bool hasLeading(uchar const *d)
 }
 #endif
 
+#if PREFIX_LENGTH > 0
+__constant uchar prefix[PREFIX_LENGTH] = { PREFIX_BYTES };
+
+static inline bool hasPrefix(uchar const *d)
+{
+#pragma unroll
+  for (uint i = 0; i < PREFIX_LENGTH - 1; ++i) {
+    if (d[i] != prefix[i]) return false;
+  }
+  return (d[PREFIX_LENGTH - 1] & PREFIX_LAST_MASK) == prefix[PREFIX_LENGTH - 1];
+}
+#endif
+
 __kernel void hashMessage(
   __constant uchar const *d_message,
   __constant uint const *d_nonce,
@@ -351,13 +364,18 @@ __kernel void hashMessage(
   // Apply keccakf
   keccakf(spongeBuffer);
 
-  // determine if the address meets the constraints
+  // determine if the address meets the constraints: the target prefix if
+  // one is given, otherwise the zero byte thresholds
+#if PREFIX_LENGTH > 0
+  if (hasPrefix(digest)) {
+#else
   if (
     hasLeading(digest) 
 #if TOTAL_ZEROES <= 20
     || hasTotal(digest)
 #endif
   ) {
+#endif
     // To be honest, if we are using OpenCL, 
     // we just need to write one solution for all practical purposes,
     // since the chance of multiple solutions appearing
@@ -3,40 +3,147 @@ extern crate console;
 extern crate fs2;
 extern crate hex;
 extern crate itertools;
+#[macro_use]
+extern crate log;
+#[cfg(feature = "gpu")]
+extern crate ocl;
 extern crate rand;
 extern crate rayon;
 extern crate separator;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate signal_hook;
 extern crate terminal_size;
 extern crate tiny_keccak;
-
+extern crate toml;
+
+mod base;
+mod builder;
+mod checksum;
+mod create;
+mod error;
+mod file;
+#[cfg(feature = "gpu")]
+mod gpu;
+mod hmac;
+mod keccak;
+mod meta;
+mod metrics;
+mod serde_hex;
+mod sink;
+mod state;
+
+use std::env;
 use std::error::Error;
-use std::fs::OpenOptions;
-use std::i64;
-use std::io::prelude::*;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::fmt;
+use std::fs;
+use std::hint::black_box;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use byteorder::{BigEndian, ByteOrder, LittleEndian};
 use console::Term;
-use fs2::FileExt;
+use create::create_hash;
 use hex::FromHex;
-use itertools::Itertools;
-use rand::{thread_rng, Rng};
+use keccak::{keccak256, Keccak256};
+use rand::{thread_rng, Isaac64Rng, Rng, SeedableRng};
 use rayon::prelude::*;
-use separator::Separatable;
-use terminal_size::{terminal_size, Height, Width};
-use tiny_keccak::Keccak;
-
-// workset size (tweak this!)
-const WORK_SIZE: u32 = 0x4000000; // max. 0x15400000 to abs. max 0xffffffff
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use terminal_size::{terminal_size, Height};
+
+pub use base::Base;
+pub use builder::ConfigBuilder;
+pub use checksum::Checksummer;
+pub use create::{create_address, AddressScheme, SaltScheme};
+pub use error::ConfigError;
+#[cfg(feature = "gpu")]
+pub use gpu::{device_segment, gpu, gpu_salt, hybrid, kernel_source, list_devices, GpuDevice};
+pub use hmac::{HmacKey, HMAC_KEY_VAR};
+pub use keccak::{KeccakEngine, PREIMAGE_LENGTH};
+pub use meta::{RunMeta, RUN_META_FILE};
+pub use metrics::{Progress, ProgressStats, SearchSummary, METRICS_INTERVAL};
+pub use sink::{
+    best_score_in, salt_to_decimal, BestSink, ChannelSink, DedupSink, FileSink, FormatSink,
+    LineFormat, OutputFormat, ResultSink, ScoreRoutedSink, SortedSink, SyncChannelSink, TeeSink,
+    CHAIN_SHORT_NAMES,
+};
+pub use state::{SearchState, CHECKPOINT_NONCES, STATE_FILE};
 
-const WORK_FACTOR: u128 = (WORK_SIZE as u128) / 1_000_000;
-const ZERO_BYTE: u8 = 0x00;
-const EIGHT_ZERO_BYTES: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 0];
 const CONTROL_CHARACTER: u8 = 0xff;
-const ZERO_REWARD: &str = "0";
-const MAX_INCREMENTER: u64 = 0xffffffffffff;
 
-static KERNEL_SRC: &'static str = include_str!("./kernels/keccak256.cl");
+/// The number of nonces in a random salt segment, and so the largest (and
+/// default) `--batch-size`.
+pub const MAX_INCREMENTER: u64 = 0xffffffffffff;
+
+/// Well-known CREATE2 factories, which are given as the factory rather than
+/// the caller: the immutable create2 factory and the deterministic deployment
+/// proxy.
+const KNOWN_FACTORIES: [[u8; 20]; 2] = [
+    [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xe8, 0xb4, 0x7b, 0x3e, 0x21, 0x30, 0x21, 0x3b, 0x80,
+        0x22, 0x12, 0x43, 0x94, 0x97,
+    ],
+    [
+        0x4e, 0x59, 0xb4, 0x48, 0x47, 0xb3, 0x79, 0x57, 0x85, 0x88, 0x92, 0x0c, 0xa7, 0x8f, 0xbf,
+        0x26, 0xc0, 0xb4, 0x95, 0x6c,
+    ],
+];
+
+/// The hash of empty init code, which deploys a contract with no code.
+const EMPTY_INIT_CODE_HASH: [u8; 32] = [
+    0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+    0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+];
+
+/// Targets constraining fewer bits than this match so often that writing every
+/// match would flood the output, so only improvements are written instead.
+const LOOSE_TARGET_BITS: u32 = 16;
+
+/// The weight given to each leading zero byte when scoring an address. It
+/// exceeds the largest possible number of total zero bytes, so that leading
+/// zero bytes always take precedence over total zero bytes.
+pub const LEADING_ZERO_WEIGHT: u32 = 21;
+
+/// The number of nonces searched between checks of the flags that stop a
+/// search. Lower values stop a search sooner; higher values spend less time
+/// checking.
+pub const CHECK_INTERVAL: u64 = 0x10000;
+
+/// The check interval used with `--fast-first`.
+pub const FAST_CHECK_INTERVAL: u64 = 0x400;
+
+/// Seconds between progress lines from `--progress` when not writing to a
+/// terminal.
+const PLAIN_PROGRESS_INTERVAL: u64 = 10;
+
+/// The number of attempts `--preview` makes before giving up on a match.
+pub const PREVIEW_MAX_ATTEMPTS: u64 = 0x10000000;
+
+/// The offset within the hash of the 20 bytes that form the address, per
+/// EIP-1014 (the last 20 of the 32 bytes).
+pub const ADDRESS_OFFSET: usize = 12;
+
+/// The number of results `--sorted` holds in memory before writing them out.
+pub const SORTED_BUFFER_CAPACITY: usize = 10_000;
+
+/// The number of found addresses `search` holds for its caller before the
+/// search waits for them to be taken.
+pub const SEARCH_BUFFER: usize = 64;
+
+/// The number of attempts `--simulate` makes.
+pub const SIMULATE_ATTEMPTS: u64 = 0x1000000;
+
+/// The number of attempts each phase of `--bench` makes.
+pub const BENCH_ATTEMPTS: u64 = 0x1000000;
+
+/// The number of seconds `--bench` runs the real search for.
+pub const BENCH_SEARCH_SECONDS: u64 = 10;
 
 /// Requires three hex-encoded arguments: the address of the contract that will
 /// be calling CREATE2, the address of the caller of said contract *(assuming
@@ -47,730 +154,3654 @@ static KERNEL_SRC: &'static str = include_str!("./kernels/keccak256.cl");
 /// of three optional values may be provided: a device to target for OpenCL GPU
 /// search, a threshold for leading zeroes to search for, and a threshold for
 /// total zeroes to search for.
+///
+/// Several comma-separated calling addresses may be given, in which case the
+/// search interleaves attempts between all of them. Since the caller
+/// forms part of the salt, each one yields an entirely different set of
+/// addresses, so the space searched grows with the number of callers (though
+/// the odds of any single attempt matching stay the same).
+///
+/// Likewise, several comma-separated targets may be given, in which case an
+/// address matching any one of them is recorded, noting the target it
+/// matched. The first is kept as `target_start_string` and the rest as
+/// `extra_targets`.
+///
+/// A target may contain `?` wildcards, each matching any nibble (so `0xab??ab`
+/// fixes the first, second, fifth and sixth nibbles of the address).
+///
+/// With `--config <path>` as the first argument, the arguments are read from a
+/// TOML (or JSON) file instead: the positional ones as `factory`, `caller`,
+/// `init_code_hash` and `target`, and each flag under its name (e.g.
+/// `threads = 8`). Any arguments given after the path take precedence over
+/// the file's. A `presets` table in the file names addresses, so that the
+/// factory and calling addresses can be given as `@name`.
+///
+/// Flags may follow the positional arguments:
+///   - `--split-by-score` routes found addresses into per-score files (see
+///     `ScoreRoutedSink`) rather than a single `efficient_addresses.txt`
+///   - `--preview` searches for a single matching address and prints it,
+///     along with the calldata for the factory (see `factory_calldata`),
+///     without writing anything (see `search_once`)
+///   - `--salt-decimal` adds the salt as a `uint256` decimal literal to each
+///     result
+///   - `--closest` treats the target as a full address and, rather than
+///     requiring an exact match, records each address that is closer to it
+///     (by the number of differing nibbles) than any recorded so far
+///   - `--lex-before <bound>` records each address matching the target whose
+///     checksummed form sorts before the bound (as a string, so casing counts)
+///     and before any recorded so far
+///   - `--match-all` records every address that passes the other criteria,
+///     even for a short or empty target (where only improvements, or only
+///     addresses meeting the zero thresholds, are otherwise recorded)
+///   - `--leading-zeroes <n>` / `--total-zeroes <n>` set the zero byte
+///     thresholds without giving a device; with a bare `0x` target and
+///     nothing else to match on, addresses with at least that many leading
+///     or total zero bytes are recorded (see `Config::meets_thresholds`)
+///   - `--format <text|json|csv>[=<path>]` also writes each result in the
+///     given format to the given file (or to stdout, in place of the usual
+///     output, if there's no path); may be repeated (see `FormatSink`)
+///   - `--chain <name>` writes each address as an EIP-3770 address for the
+///     chain with the given short name (one of `CHAIN_SHORT_NAMES`)
+///   - `--hmac` appends an HMAC of each line, keyed by the secret in the
+///     `CREATE2CRUNCH_HMAC_KEY` environment variable (see `HmacKey`)
+///   - `--case-sensitive` (or `--match-checksum-case`) also requires the
+///     letters of the target (and suffix) to match the case of the
+///     checksummed (EIP-55) address, which makes a match twice as rare for
+///     each letter. A target pasted as a full checksummed address (all 40
+///     characters, with both upper- and lowercase letters) turns this on by
+///     itself; a full address in a single case, or any shorter target, is
+///     matched in either case unless the flag is given
+///   - `--suffix <suffix>` also requires the address to end with the given
+///     `0x`-prefixed hex string, with the same casing rules as the target
+///   - `--forbid <characters>` skips any address whose checksummed form
+///     contains one of the given characters (in the given case)
+///   - `--min-leading-zero-nibbles <n>` only records addresses that start with
+///     at least `n` zero hex characters (see `leading_zero_nibbles`), a finer
+///     measure than the leading zero bytes of the thresholds
+///   - `--increasing <n>` / `--decreasing <n>` only records addresses whose
+///     first `n` bytes are strictly increasing (or decreasing), noting the
+///     length of the run (see `MonotoneBytes`)
+///   - `--palindrome` only records addresses whose 40 hex characters read the
+///     same backwards (see `is_palindrome`)
+///   - `--min-repeat-run <n>` only records addresses with a run of at least
+///     `n` identical hex characters somewhere in them (see
+///     `longest_repeat_run`)
+///   - `--base32 <prefix>` / `--base58 <prefix>` only records addresses that
+///     start with the given prefix when rendered in that base (see `Base`)
+///   - `--dedupe-addresses` skips any address that has already been written
+///     by previous runs as well; an address found twice in the same run is
+///     only written once either way, except with `--match-all` (see
+///     `DedupSink`)
+///   - `--sorted` holds results in memory and writes them sorted by score once
+///     the search stops (see `SortedSink`)
+///   - `--progress` shows the runtime, rate and current salt segment of the
+///     search, refreshed every second (or logs them every ten seconds when
+///     not writing to a terminal)
+///   - `--quiet` doesn't log each address found to stderr, leaving only the
+///     results themselves
+///   - `--fast-first` favors reporting the first match quickly over the peak
+///     rate of the search (see `Config::check_interval`)
+///   - `--attempt-number` adds the (approximate) number of attempts made
+///     before each result was found
+///   - `--bench` measures the hash rate with and without matching (see
+///     `benchmark`), the rate of the keccak engine in use (see
+///     `benchmark_engine`), the checksum rate (see `benchmark_checksum`) and
+///     the rate of the search itself on each thread (see `benchmark_search`),
+///     and exits without searching
+///   - `--dry-parse` prints the configuration as parsed and exits without
+///     searching
+///   - `--simulate <targets>` counts how many addresses match each of a
+///     comma-separated list of targets over a fixed number of attempts,
+///     without writing anything (see `simulate`)
+///   - `--rotate-size <bytes>` starts a new `efficient_addresses.txt` once it
+///     grows beyond the given size (see `FileSink::rotate_at`)
+///   - `--max-segments <n>` stops the search once it has searched `n` random
+///     salt segments in full
+///   - `--batch-size <n>` searches `n` nonces under each random salt segment
+///     before drawing a new one (see `Config::batch_size`)
+///   - `--deterministic` searches a single salt segment of zeros instead of
+///     random ones, walking its nonces in order and finishing once all of
+///     them have been searched (see `Config::deterministic`)
+///   - `--segment <hex>` searches the given 6-byte salt segment
+///     deterministically, rather than one of zeros
+///   - `--random-bytes <n>` draws only the last `n` bytes (from 0 to 6) of
+///     each salt segment at random, leaving the rest zero (see
+///     `Config::random_bytes`)
+///   - `--resume` checkpoints the search to `search.state` and, if that file
+///     already exists, continues from where it left off (see `SearchState`)
+///   - `--max-results <n>` stops the search once it has written `n` results
+///   - `--timeout <seconds>` stops the search once it has run for the given
+///     time, whether or not it has found anything
+///   - `--init-code <code>` takes the init code itself, hashing it to derive
+///     the init code hash (which must then be given as `-`)
+///   - `--init-code-hash-file <path>` reads the init code hash from the given
+///     file (which it must then be given as `-`); an init code hash of
+///     `@<path>` does the same
+///   - `--create` mines CREATE addresses rather than CREATE2 ones, taking the
+///     factory address as the deployer and searching over its nonces (see
+///     `AddressScheme::Create`); each result's salt holds the nonce
+///   - `--safe-initializer-hash <hash>` mines for Safe's proxy factory, which
+///     takes a `uint256 saltNonce` and hashes it with the keccak-256 hash of
+///     the proxy's initializer (see `SaltScheme::Safe`); each result's salt
+///     is then the salt nonce to pass (see `salt_nonce_for`)
+///   - `--nonce-order <big|little>` lays out the nonce in the last six bytes
+///     of each salt in the given byte order (see `NonceOrder`)
+///   - `--threads <n>` searches on `n` threads rather than one per core
+///   - `--seed <n>` draws the random salt segments from a generator seeded
+///     with `n`, so that the same salts are searched on every run (see
+///     `Config::segment_rng`)
+///   - `--resume-best` only records addresses that score higher than the best
+///     one already in `efficient_addresses.txt` (see `best_score_in`)
+///   - `--announce-min-score <n>` only prints results with at least the given
+///     score, while still writing every result
+///   - `--best <n>` keeps only the best `n` results in `efficient_addresses.txt`
+///     (see `BestSink`)
+///   - `--truncation-offset <n>` takes the address from the 20 bytes of the
+///     hash starting at byte `n` rather than 12, for experimenting with
+///     non-standard derivations; the resulting addresses are *not* what
+///     CREATE2 produces
+///   - `--rotation <n>` matches the target against the address rotated left
+///     by `n` bytes; the address is still recorded in its usual form
+///   - `--warmup <seconds>` measures the rate of the search for the given
+///     time before starting it (see `measure_rate`)
+///   - `--metrics-file <path>` periodically writes the progress of the search
+///     to the given file in the Prometheus text format, every
+///     `--metrics-interval <seconds>` (default `METRICS_INTERVAL`)
+///   - `--sync-on-write` flushes each result to disk as soon as it's written,
+///     both to the results file and to any `--format` file (see
+///     `FileSink::sync_on_write`)
+///   - `--run-meta` writes the configuration of the run to `run_meta.json` (see
+///     `RunMeta`) so that the results can be verified or reproduced later
+///   - `--header` starts a new `efficient_addresses.txt` with a commented
+///     header recording the factory, callers, init code hash, targets and
+///     start time of the run (see `results_header`)
+///   - `--strict` refuses to start a search that `Config::warnings` flags
+///     as likely misconfigured, such as one with the factory and caller
+///     swapped
+///   - `--gpu <device>` selects the OpenCL device to search on, as an
+///     alternative to the positional arguments (the thresholds are then given
+///     with `--leading-zeroes` and `--total-zeroes`); `--gpu all` searches on
+///     every device at once
+///   - `--hybrid` searches on the CPU alongside the selected GPU (or GPUs)
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Config {
+    #[serde(with = "serde_hex")]
     pub factory_address: [u8; 20],
-    pub calling_address: [u8; 20],
+    #[serde(with = "serde_hex::list")]
+    pub calling_addresses: Vec<[u8; 20]>,
+    #[serde(with = "serde_hex")]
     pub init_code_hash: [u8; 32],
+    /// The init code itself, when given with `--init-code`.
+    #[serde(skip)]
+    pub init_code: Option<Vec<u8>>,
+    pub scheme: AddressScheme,
+    pub salt_scheme: SaltScheme,
+    pub nonce_order: NonceOrder,
     pub gpu_device: u8,
+    pub all_gpus: bool,
+    pub hybrid: bool,
+    pub leading_zeroes_threshold: u8,
+    pub total_zeroes_threshold: u8,
+    pub zero_thresholds: bool,
     pub target_start_string: String,
+    pub extra_targets: Vec<String>,
+    pub target_end_string: Option<String>,
+    pub case_sensitive: bool,
+    pub split_by_score: bool,
+    pub preview: bool,
+    pub salt_decimal: bool,
+    pub chain: Option<String>,
+    pub formats: Vec<(OutputFormat, Option<String>)>,
+    pub run_meta: bool,
+    pub write_header: bool,
+    pub strict: bool,
+    pub closest: bool,
+    pub lex_before: Option<String>,
+    pub forbidden: Option<String>,
+    pub min_leading_zero_nibbles: Option<u32>,
+    pub monotone: Option<MonotoneBytes>,
+    pub require_palindrome: bool,
+    pub min_repeat_run: Option<u8>,
+    pub encoded_target: Option<(Base, String)>,
+    pub rotate_size: Option<u64>,
+    pub max_segments: Option<u64>,
+    pub max_results: Option<u64>,
+    pub timeout: Option<Duration>,
+    pub seed: Option<u64>,
+    /// Whether to search the single salt segment `segment` rather than random
+    /// ones, so that the same salts are searched on every run and the search
+    /// finishes once it has searched each of them. Concurrent runs searching
+    /// the same segment search the same salts, so only one should search it.
+    pub deterministic: bool,
+    /// The salt segment searched by a deterministic search (zeros unless
+    /// given with `--segment`).
+    #[serde(with = "serde_hex")]
+    pub segment: [u8; 6],
+    /// The number of bytes at the end of each salt segment that are drawn at
+    /// random (6 by default); the rest are zero. Fewer random bytes make it
+    /// likelier that concurrent runs (or the same run, over enough segments)
+    /// search the same salts, and with none the segment of zeros is the only
+    /// one, so the search is deterministic.
+    pub random_bytes: u8,
+    /// The number of nonces searched under each random salt segment before
+    /// the next one is drawn (`MAX_INCREMENTER` by default). Every thread
+    /// finishes its share of a segment before the next segment starts, so a
+    /// smaller batch makes each segment (and so `--max-segments`) a shorter
+    /// unit of work, at the cost of that synchronization and of preparing
+    /// each segment. Both are negligible above a few million nonces per
+    /// thread; much below that, threads spend a growing share of their time
+    /// waiting on one another.
+    pub batch_size: u64,
+    pub threads: Option<usize>,
+    pub min_score: Option<u32>,
+    pub announce_min_score: Option<u32>,
+    pub best: Option<usize>,
+    pub truncation_offset: usize,
+    pub rotation: u8,
+    pub warmup: Option<u64>,
+    pub metrics_file: Option<String>,
+    pub metrics_interval: u64,
+    pub simulate: Vec<String>,
+    pub dedupe_addresses: bool,
+    pub sort_results: bool,
+    pub dry_parse: bool,
+    pub bench: bool,
+    pub fast_first: bool,
+    pub quiet: bool,
+    pub show_progress: bool,
+    pub resume: bool,
+    pub attempt_number: bool,
+    pub sync_on_write: bool,
+    pub match_all: bool,
+    #[serde(skip)]
+    pub hmac_key: Option<HmacKey>,
+    #[serde(skip)]
+    pub predicate: Option<CustomPredicate>,
+    #[serde(skip)]
+    pub on_progress: Option<OnProgress>,
+    pub improvements_only: bool,
+}
+
+/// The null factory, caller and init code hash, with a bare `0x` target and
+/// every option at its default, for `ConfigBuilder` to start from.
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            factory_address: [0; 20],
+            calling_addresses: vec![[0; 20]],
+            init_code_hash: [0; 32],
+            init_code: None,
+            scheme: AddressScheme::Create2,
+            salt_scheme: SaltScheme::Raw,
+            nonce_order: NonceOrder::BigEndian,
+            gpu_device: 255, // indicates that CPU will be used.
+            all_gpus: false,
+            hybrid: false,
+            leading_zeroes_threshold: 3,
+            total_zeroes_threshold: 5,
+            zero_thresholds: false,
+            target_start_string: String::from("0x"),
+            extra_targets: vec![],
+            target_end_string: None,
+            case_sensitive: false,
+            split_by_score: false,
+            preview: false,
+            salt_decimal: false,
+            chain: None,
+            formats: vec![],
+            run_meta: false,
+            write_header: false,
+            strict: false,
+            closest: false,
+            lex_before: None,
+            forbidden: None,
+            min_leading_zero_nibbles: None,
+            monotone: None,
+            require_palindrome: false,
+            min_repeat_run: None,
+            encoded_target: None,
+            rotate_size: None,
+            max_segments: None,
+            max_results: None,
+            timeout: None,
+            seed: None,
+            deterministic: false,
+            segment: [0; 6],
+            random_bytes: 6,
+            batch_size: MAX_INCREMENTER,
+            threads: None,
+            min_score: None,
+            announce_min_score: None,
+            best: None,
+            truncation_offset: ADDRESS_OFFSET,
+            rotation: 0,
+            warmup: None,
+            metrics_file: None,
+            metrics_interval: METRICS_INTERVAL,
+            simulate: vec![],
+            dedupe_addresses: false,
+            sort_results: false,
+            dry_parse: false,
+            bench: false,
+            fast_first: false,
+            quiet: false,
+            show_progress: false,
+            resume: false,
+            attempt_number: false,
+            sync_on_write: false,
+            match_all: false,
+            hmac_key: None,
+            predicate: None,
+            on_progress: None,
+            improvements_only: false,
+        }
+    }
 }
 
-/// Validate the provided arguments and construct the Config struct.
+/// Parse the provided arguments and build the Config struct from them.
 impl Config {
-    pub fn new(mut args: std::env::Args) -> Result<Self, &'static str> {
+    pub fn new<I: Iterator<Item = String>>(mut args: I) -> Result<Self, ConfigError> {
         // get args, skipping first arg (program name)
         args.next();
 
-        let mut factory_address_string = match args.next() {
-            Some(arg) => arg,
-            None => return Err("didn't get a factory_address argument."),
+        // the arguments may come from a config file instead, with any given
+        // on the command line taking precedence
+        let mut args: Box<dyn Iterator<Item = String>> = match args.next() {
+            Some(ref arg) if arg == "--config" => {
+                let path = args
+                    .next()
+                    .ok_or(ConfigError::Invalid("--config requires a path."))?;
+                Box::new(file::with_config_file(&path, args.collect())?.into_iter())
+            }
+            first => Box::new(first.into_iter().chain(args)),
         };
 
-        let mut calling_address_string = match args.next() {
+        let factory_address_string = match args.next() {
             Some(arg) => arg,
-            None => return Err("didn't get a calling_address argument."),
+            None => return Err(ConfigError::MissingFactoryAddress),
         };
 
-        let mut init_code_hash_string = match args.next() {
+        let calling_addresses_string = match args.next() {
             Some(arg) => arg,
-            None => return Err("didn't get an init_code_hash argument."),
+            None => return Err(ConfigError::MissingCallingAddress),
         };
 
-        let target_start_string = match args.next() {
+        let mut init_code_hash_string = match args.next() {
             Some(arg) => arg,
-            None => return Err("didn't get an target_start argument."),
+            None => return Err(ConfigError::MissingInitCodeHash),
         };
 
-        let gpu_device_string = match args.next() {
-            Some(arg) => arg,
-            None => String::from("255"), // indicates that CPU will be used.
+        // the target argument may list several comma-separated targets
+        let targets: Vec<String> = match args.next() {
+            Some(arg) => arg.split(',').map(String::from).collect(),
+            None => return Err(ConfigError::MissingTarget),
         };
 
-        // strip 0x from args if applicable
-        if factory_address_string.starts_with("0x") {
-            factory_address_string = without_prefix(factory_address_string)
+        // remaining arguments are either flags, which set the options of a
+        // default config, or the optional gpu values
+        let mut config = Config::default();
+        let mut gpu_args: Vec<String> = vec![];
+        let mut resume_best = false;
+        let mut gpu_flag = None;
+        let mut init_code_hash_file = None;
+        let mut leading_zeroes_flag = None;
+        let mut total_zeroes_flag = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--gpu" => match args.next() {
+                    _ if gpu_flag.is_some() => {
+                        return Err(ConfigError::Invalid("--gpu can only be given once."))
+                    }
+                    Some(ref device) if device == "all" => {
+                        config.all_gpus = true;
+                        gpu_flag = Some(String::from("255"));
+                    }
+                    Some(device) => gpu_flag = Some(device),
+                    None => return Err(ConfigError::Invalid("--gpu requires a device or `all`.")),
+                },
+                "--split-by-score" => config.split_by_score = true,
+                "--preview" => config.preview = true,
+                "--salt-decimal" => config.salt_decimal = true,
+                "--run-meta" => config.run_meta = true,
+                "--header" => config.write_header = true,
+                "--strict" => config.strict = true,
+                "--closest" => config.closest = true,
+                "--lex-before" => match args.next() {
+                    Some(bound) => config.lex_before = Some(bound),
+                    None => return Err(ConfigError::Invalid("--lex-before requires a bound.")),
+                },
+                "--suffix" => match args.next() {
+                    Some(suffix) => config.target_end_string = Some(suffix),
+                    None => return Err(ConfigError::Invalid("--suffix requires a hex string.")),
+                },
+                "--forbid" => match args.next() {
+                    Some(characters) => config.forbidden = Some(characters),
+                    None => {
+                        return Err(ConfigError::Invalid(
+                            "--forbid requires a set of characters.",
+                        ))
+                    }
+                },
+                "--min-leading-zero-nibbles" => match args.next().map(|n| n.parse::<u32>()) {
+                    Some(Ok(n)) if (1..=40).contains(&n) => {
+                        config.min_leading_zero_nibbles = Some(n)
+                    }
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--min-leading-zero-nibbles must be between 1 and 40.",
+                        ))
+                    }
+                },
+                "--palindrome" => config.require_palindrome = true,
+                "--min-repeat-run" => match args.next().map(|n| n.parse::<u8>()) {
+                    Some(Ok(n)) if (2..=40).contains(&n) => config.min_repeat_run = Some(n),
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--min-repeat-run must be between 2 and 40.",
+                        ))
+                    }
+                },
+                "--increasing" | "--decreasing" => {
+                    let direction = if arg == "--increasing" {
+                        Direction::Increasing
+                    } else {
+                        Direction::Decreasing
+                    };
+                    match args.next().map(|len| len.parse::<usize>()) {
+                        Some(Ok(len)) if (2..=20).contains(&len) => {
+                            config.monotone = Some(MonotoneBytes { len, direction })
+                        }
+                        _ => {
+                            return Err(ConfigError::Invalid(
+                                "--increasing and --decreasing require a length of 2 to 20 bytes.",
+                            ))
+                        }
+                    }
+                }
+                "--base32" | "--base58" => {
+                    let base = Base::from_name(&arg[2..]).unwrap();
+                    match args.next() {
+                        Some(ref prefix)
+                            if !prefix.is_empty()
+                                && prefix.bytes().all(|c| base.alphabet().contains(&c)) =>
+                        {
+                            config.encoded_target = Some((base, prefix.clone()))
+                        }
+                        _ => {
+                            return Err(ConfigError::Invalid(
+                                "--base32 and --base58 require a prefix in that base.",
+                            ))
+                        }
+                    }
+                }
+                "--dedupe-addresses" => config.dedupe_addresses = true,
+                "--sorted" => config.sort_results = true,
+                "--dry-parse" => config.dry_parse = true,
+                "--bench" => config.bench = true,
+                "--fast-first" => config.fast_first = true,
+                "--quiet" => config.quiet = true,
+                "--progress" => config.show_progress = true,
+                "--case-sensitive" | "--match-checksum-case" => config.case_sensitive = true,
+                "--resume" => config.resume = true,
+                "--attempt-number" => config.attempt_number = true,
+                "--sync-on-write" => config.sync_on_write = true,
+                "--match-all" => config.match_all = true,
+                "--create" => config.scheme = AddressScheme::Create,
+                "--safe-initializer-hash" => {
+                    let hash = args.next().unwrap_or_default();
+                    let hash = decode_hex_arg(&hash, "safe initializer hash", 32)?;
+                    let mut initializer_hash = [0; 32];
+                    initializer_hash.copy_from_slice(&hash);
+                    config.salt_scheme = SaltScheme::Safe { initializer_hash };
+                }
+                "--nonce-order" => match args.next().as_deref() {
+                    Some("big") => config.nonce_order = NonceOrder::BigEndian,
+                    Some("little") => config.nonce_order = NonceOrder::LittleEndian,
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--nonce-order must be either big or little.",
+                        ))
+                    }
+                },
+                "--resume-best" => resume_best = true,
+                "--hmac" => match env::var(HMAC_KEY_VAR) {
+                    Ok(ref key) if !key.is_empty() => {
+                        config.hmac_key = Some(HmacKey::new(key.as_bytes()))
+                    }
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--hmac requires a key in the CREATE2CRUNCH_HMAC_KEY variable.",
+                        ))
+                    }
+                },
+                "--simulate" => match args.next() {
+                    Some(targets) => {
+                        config.simulate = targets.split(',').map(String::from).collect()
+                    }
+                    None => {
+                        return Err(ConfigError::Invalid(
+                            "--simulate requires a list of targets.",
+                        ))
+                    }
+                },
+                "--best" => match args.next().map(|n| n.parse::<usize>()) {
+                    Some(Ok(n)) if n > 0 => config.best = Some(n),
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--best requires a positive number of results.",
+                        ))
+                    }
+                },
+                "--truncation-offset" => match args.next().map(|n| n.parse::<usize>()) {
+                    Some(Ok(n)) if n <= 12 => config.truncation_offset = n,
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--truncation-offset must be between 0 and 12.",
+                        ))
+                    }
+                },
+                "--rotation" => match args.next().map(|n| n.parse::<u8>()) {
+                    Some(Ok(n)) if n < 20 => config.rotation = n,
+                    _ => return Err(ConfigError::Invalid("--rotation must be between 0 and 19.")),
+                },
+                "--warmup" => match args.next().map(|secs| secs.parse::<u64>()) {
+                    Some(Ok(secs)) if secs > 0 => config.warmup = Some(secs),
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--warmup requires a positive number of seconds.",
+                        ))
+                    }
+                },
+                "--metrics-file" => match args.next() {
+                    Some(path) => config.metrics_file = Some(path),
+                    None => return Err(ConfigError::Invalid("--metrics-file requires a path.")),
+                },
+                "--metrics-interval" => match args.next().map(|secs| secs.parse::<u64>()) {
+                    Some(Ok(secs)) if secs > 0 => config.metrics_interval = secs,
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--metrics-interval requires a positive number of seconds.",
+                        ))
+                    }
+                },
+                "--rotate-size" => match args.next().map(|size| size.parse::<u64>()) {
+                    Some(Ok(size)) if size > 0 => config.rotate_size = Some(size),
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--rotate-size requires a positive number of bytes.",
+                        ))
+                    }
+                },
+                "--max-segments" => match args.next().map(|n| n.parse::<u64>()) {
+                    Some(Ok(n)) if n > 0 => config.max_segments = Some(n),
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--max-segments requires a positive number of segments.",
+                        ))
+                    }
+                },
+                "--batch-size" => {
+                    match args.next().map(|n| n.parse::<u64>()) {
+                        Some(Ok(n)) if n > 0 && n <= MAX_INCREMENTER => config.batch_size = n,
+                        _ => return Err(ConfigError::Invalid(
+                            "--batch-size requires a positive number of nonces, up to 2^48 - 1.",
+                        )),
+                    }
+                }
+                "--timeout" => match args.next().map(|secs| secs.parse::<u64>()) {
+                    Some(Ok(secs)) if secs > 0 => config.timeout = Some(Duration::from_secs(secs)),
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--timeout requires a positive number of seconds.",
+                        ))
+                    }
+                },
+                "--max-results" => match args.next().map(|n| n.parse::<u64>()) {
+                    Some(Ok(n)) if n > 0 => config.max_results = Some(n),
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--max-results requires a positive number of results.",
+                        ))
+                    }
+                },
+                "--threads" => match args.next().map(|n| n.parse::<usize>()) {
+                    Some(Ok(n)) if n > 0 => config.threads = Some(n),
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--threads requires a positive number of threads.",
+                        ))
+                    }
+                },
+                "--deterministic" => config.deterministic = true,
+                "--random-bytes" => match args.next().map(|n| n.parse::<u8>()) {
+                    Some(Ok(n)) if n <= 6 => config.random_bytes = n,
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--random-bytes must be between 0 and 6.",
+                        ))
+                    }
+                },
+                "--segment" => {
+                    let bytes = args
+                        .next()
+                        .and_then(|hex| Vec::from_hex(hex.trim_start_matches("0x")).ok());
+                    match bytes {
+                        Some(ref bytes) if bytes.len() == config.segment.len() => {
+                            config.segment.copy_from_slice(bytes);
+                            config.deterministic = true;
+                        }
+                        _ => {
+                            return Err(ConfigError::Invalid(
+                                "--segment requires 6 bytes of hex (12 characters).",
+                            ))
+                        }
+                    }
+                }
+                "--seed" => match args.next().map(|n| n.parse::<u64>()) {
+                    Some(Ok(n)) => config.seed = Some(n),
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--seed requires a 64-bit unsigned integer.",
+                        ))
+                    }
+                },
+                "--format" => {
+                    let spec = args.next().unwrap_or_default();
+                    let mut spec = spec.splitn(2, '=');
+                    match spec.next().and_then(OutputFormat::from_name) {
+                        Some(format) => {
+                            config.formats.push((format, spec.next().map(String::from)))
+                        }
+                        None => {
+                            return Err(ConfigError::Invalid(
+                                "--format requires text, json or csv (e.g. json=out.jsonl).",
+                            ))
+                        }
+                    }
+                }
+                "--chain" => match args.next() {
+                    Some(ref name) if CHAIN_SHORT_NAMES.contains(&name.as_str()) => {
+                        config.chain = Some(name.clone())
+                    }
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--chain requires a known chain short name (e.g. eth).",
+                        ))
+                    }
+                },
+                "--leading-zeroes" => match args.next() {
+                    Some(n) => leading_zeroes_flag = Some(n),
+                    None => {
+                        return Err(ConfigError::Invalid(
+                            "--leading-zeroes requires a number of bytes.",
+                        ))
+                    }
+                },
+                "--total-zeroes" => match args.next() {
+                    Some(n) => total_zeroes_flag = Some(n),
+                    None => {
+                        return Err(ConfigError::Invalid(
+                            "--total-zeroes requires a number of bytes.",
+                        ))
+                    }
+                },
+                "--announce-min-score" => match args.next().map(|n| n.parse::<u32>()) {
+                    Some(Ok(n)) => config.announce_min_score = Some(n),
+                    _ => {
+                        return Err(ConfigError::Invalid(
+                            "--announce-min-score requires a score.",
+                        ))
+                    }
+                },
+                "--hybrid" => config.hybrid = true,
+                "--init-code" => match args.next() {
+                    Some(code) => match Vec::from_hex(code.trim_start_matches("0x")) {
+                        Ok(code) => config.init_code = Some(code),
+                        Err(_) => return Err(ConfigError::NotHex { field: "init code" }),
+                    },
+                    None => return Err(ConfigError::Invalid("--init-code requires init code.")),
+                },
+                "--init-code-hash-file" => match args.next() {
+                    Some(path) => init_code_hash_file = Some(path),
+                    None => {
+                        return Err(ConfigError::Invalid(
+                            "--init-code-hash-file requires a path.",
+                        ))
+                    }
+                },
+                _ if arg.starts_with("--") => {
+                    return Err(ConfigError::Invalid("unrecognized flag."))
+                }
+                _ => gpu_args.push(arg),
+            }
         }
 
-        if calling_address_string.starts_with("0x") {
-            calling_address_string = without_prefix(calling_address_string)
+        // the device given with --gpu takes the place of the positional
+        // gpu arguments, which would otherwise be read as the thresholds
+        if let Some(device) = gpu_flag {
+            if !gpu_args.is_empty() {
+                return Err(ConfigError::Invalid(
+                    "--gpu cannot be combined with a positional device or thresholds; \
+                     pass the thresholds with --leading-zeroes and --total-zeroes.",
+                ));
+            }
+            gpu_args.push(device);
+        }
+        let mut gpu_args = gpu_args.into_iter();
+        // a device of 255 indicates that the CPU will be used
+        let gpu_device_string = gpu_args.next().unwrap_or_else(|| String::from("255"));
+        let leading_zeroes_threshold_string = gpu_args.next().unwrap_or_else(|| String::from("3"));
+        let total_zeroes_threshold_string = gpu_args.next().unwrap_or_else(|| String::from("5"));
+        if gpu_args.next().is_some() {
+            return Err(ConfigError::Invalid("too many arguments."));
         }
 
-        if init_code_hash_string.starts_with("0x") {
-            init_code_hash_string = without_prefix(init_code_hash_string)
+        // the flags save passing a device just to reach the thresholds
+        let leading_zeroes_threshold_string =
+            leading_zeroes_flag.unwrap_or(leading_zeroes_threshold_string);
+        let total_zeroes_threshold_string =
+            total_zeroes_flag.unwrap_or(total_zeroes_threshold_string);
+
+        // `@name` addresses are resolved from a config file's presets
+        if factory_address_string.starts_with('@') || calling_addresses_string.contains('@') {
+            return Err(ConfigError::Invalid(
+                "@name addresses require a presets table in a --config file.",
+            ));
         }
 
-        if !target_start_string.starts_with("0x") {
-            return Err("target_start argument must start with 0x.");
+        // the init code hash can be read from a file, named either in its
+        // place after an `@` or by a flag (in which case a `-` stands in for
+        // it), to save pasting it in; it's then checked as if given directly
+        let hash_file = match (init_code_hash_file, init_code_hash_string.strip_prefix('@')) {
+            (Some(_), _) if init_code_hash_string != "-" => return Err(ConfigError::Invalid(
+                "--init-code-hash-file replaces the init code hash, which must be given as `-`.",
+            )),
+            (Some(path), _) => Some(path),
+            (None, path) => path.map(String::from),
+        };
+        if let Some(path) = hash_file {
+            init_code_hash_string = fs::read_to_string(&path)
+                .map_err(|e| ConfigError::read_file("init code hash file", &path, e))?
+                .trim()
+                .to_string();
         }
 
-        // convert main arguments from hex string to vector of bytes
-        let factory_address_vec: Vec<u8> = match Vec::from_hex(&factory_address_string) {
+        // the init code hash can be derived from the init code itself, in
+        // which case a `-` stands in for it
+        let init_code_hash = match (config.init_code.as_ref(), init_code_hash_string.as_str()) {
+            (Some(code), "-") => init_code_hash(code),
+            (Some(_), _) => {
+                return Err(ConfigError::Invalid(
+                    "--init-code replaces the init code hash, which must be given as `-`.",
+                ))
+            }
+            (None, "-") => return Err(ConfigError::MissingInitCodeHash),
+            (None, hash) => to_fixed_32(decode_hex_arg(hash, "init code hash", 32)?),
+        };
+
+        // convert main arguments from hex strings to bytes; the calling
+        // address argument may list several comma-separated callers
+        let factory_address = to_fixed_20(decode_hex_arg(
+            &factory_address_string,
+            "factory address",
+            20,
+        )?);
+        let calling_addresses = calling_addresses_string
+            .split(',')
+            .map(|caller| decode_hex_arg(caller, "calling address", 20).map(to_fixed_20))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // convert gpu arguments to u8 values
+        config.gpu_device = match gpu_device_string.parse::<u8>() {
             Ok(t) => t,
-            Err(_) => return Err("could not decode factory address argument."),
+            Err(_) => return Err(ConfigError::Invalid("invalid gpu device value.")),
         };
 
-        let calling_address_vec: Vec<u8> = match Vec::from_hex(&calling_address_string) {
+        config.leading_zeroes_threshold = match leading_zeroes_threshold_string.parse::<u8>() {
             Ok(t) => t,
-            Err(_) => return Err("could not decode calling address argument."),
+            Err(_) => {
+                return Err(ConfigError::Invalid(
+                    "invalid leading zeroes threshold value supplied.",
+                ))
+            }
         };
 
-        let init_code_hash_vec: Vec<u8> = match Vec::from_hex(&init_code_hash_string) {
+        config.total_zeroes_threshold = match total_zeroes_threshold_string.parse::<u8>() {
             Ok(t) => t,
-            Err(_) => return Err("could not decode initialization code hash argument."),
+            Err(_) => {
+                return Err(ConfigError::Invalid(
+                    "invalid total zeroes threshold value supplied.",
+                ))
+            }
         };
 
-        // let is_convertible = target_start_string.chars().all(|c| c.is_ascii_hexdigit());
+        let mut builder = Config::builder()
+            .factory_address(factory_address)
+            .init_code_hash(init_code_hash);
+        for calling_address in calling_addresses {
+            builder = builder.calling_address(calling_address);
+        }
+        for target in targets {
+            builder = builder.target_prefix(&target);
+        }
+        if resume_best {
+            builder = builder.resume_best();
+        }
+        builder.configure(|options| *options = config).build()
+    }
 
-        // if !is_convertible {
-        //     return Err("invalid target address start provided, not hex string.");
-        // }
+    /// Check that the options of a configuration make sense together, and
+    /// derive the settings that follow from them (such as whether only
+    /// improvements are recorded). `ConfigBuilder::build` validates every
+    /// configuration this way, whether `Config::new` parsed it from the
+    /// command line or an application built it; with `resume_best`, only
+    /// addresses that beat the best in `efficient_addresses.txt` are recorded.
+    fn validate(self, resume_best: bool) -> Result<Self, ConfigError> {
+        let mut config = self;
+
+        if !config.target_start_string.starts_with("0x")
+            || config
+                .extra_targets
+                .iter()
+                .any(|target| !target.starts_with("0x"))
+        {
+            return Err(ConfigError::TargetMissingPrefix);
+        }
 
-        // validate length of each argument (20, 20, 32)
-        if factory_address_vec.len() != 20 {
-            return Err("invalid length for factory address argument.");
+        if config.leading_zeroes_threshold > 20 {
+            return Err(ConfigError::Invalid(
+                "invalid value for leading zeroes threshold argument. (valid: 0..=20)",
+            ));
         }
 
-        if calling_address_vec.len() != 20 {
-            return Err("invalid length for calling address argument.");
+        if config.total_zeroes_threshold > 20 {
+            return Err(ConfigError::Invalid(
+                "invalid value for total zeroes threshold argument. (valid: 0..=20)",
+            ));
         }
 
-        if init_code_hash_vec.len() != 32 {
-            return Err("invalid length for initialization code hash argument.");
+        // a target may hold `?` wildcards, each matching any nibble
+        let is_not_hex = |target: &String| {
+            target[2..]
+                .chars()
+                .any(|c| !c.is_ascii_hexdigit() && c != '?')
+        };
+
+        if is_not_hex(&config.target_start_string) || config.extra_targets.iter().any(is_not_hex) {
+            return Err(ConfigError::NotHex { field: "target" });
         }
 
-        // convert from vector to fixed array
-        let factory_address = to_fixed_20(factory_address_vec);
-        let calling_address = to_fixed_20(calling_address_vec);
-        let init_code_hash = to_fixed_32(init_code_hash_vec);
+        // a target longer than an address can never match (a bare `0x` is
+        // fine, and searches by the zero thresholds instead)
+        let longest = config
+            .extra_targets
+            .iter()
+            .chain(Some(&config.target_start_string))
+            .map(|target| target.len() - 2)
+            .max()
+            .unwrap();
+        if longest > 40 {
+            return Err(ConfigError::TargetTooLong { nibbles: longest });
+        }
 
-        // convert gpu arguments to u8 values
-        let gpu_device: u8 = match gpu_device_string.parse::<u8>() {
-            Ok(t) => t,
-            Err(_) => return Err("invalid gpu device value."),
+        // a full address pasted with mixed case is a checksummed one, whose
+        // casing is meant to be matched (with several targets, only if that
+        // goes for all of them); a single case carries no intent either way
+        let is_checksummed = |target: &String| {
+            let nibbles = &target[2..];
+            nibbles.len() == 40
+                && nibbles.chars().any(|c| c.is_ascii_lowercase())
+                && nibbles.chars().any(|c| c.is_ascii_uppercase())
         };
+        if is_checksummed(&config.target_start_string)
+            && config.extra_targets.iter().all(is_checksummed)
+        {
+            config.case_sensitive = true;
+        }
 
-        let is_not_hex = &target_start_string[2..]
-            .chars()
-            .any(|c| !c.is_ascii_hexdigit());
+        // with no random bytes, the segment of zeros is the only one there is
+        match config.random_bytes {
+            0 => config.deterministic = true,
+            6 => {}
+            _ if config.deterministic => {
+                return Err(ConfigError::Invalid(
+                    "--random-bytes cannot be combined with --deterministic or --segment.",
+                ))
+            }
+            _ => {}
+        }
 
-        if *is_not_hex {
-            return Err("invalid target address start provided, not hex string.");
+        if config.hybrid && !config.uses_gpu() {
+            return Err(ConfigError::Invalid("--hybrid requires a gpu device."));
         }
 
-        // return the config object
-        Ok(Self {
-            factory_address,
-            calling_address,
-            init_code_hash,
-            gpu_device,
-            target_start_string,
-        })
-    }
-}
+        // only a single results file, appended to, gets a header
+        if config.write_header && (config.best.is_some() || config.split_by_score) {
+            return Err(ConfigError::Invalid(
+                "--header cannot be combined with --best or --split-by-score.",
+            ));
+        }
 
-/// Given a Config object with a factory address, a caller address, and a
-/// keccak-256 hash of the contract initialization code, search for salts that
-/// will enable the factory contract to deploy a contract to a gas-efficient
-/// address via CREATE2.
-///
-/// The 32-byte salt is constructed as follows:
-///   - the 20-byte calling address (to prevent frontrunning)
-///   - a random 6-byte segment (to prevent collisions with other runs)
-///   - a 6-byte nonce segment (incrementally stepped through during the run)
-///
-/// When a salt that will result in the creation of a gas-efficient contract
-/// address is found, it will be appended to `efficient_addresses.txt` along
-/// with the resultant address and the "value" (i.e. approximate rarity) of the
-/// resultant address.
-pub fn cpu(config: Config) -> Result<(), Box<dyn Error>> {
-    // (create if necessary) and open a file where found salts will be written
-    let file = OpenOptions::new()
-        .append(true)
-        .create(true)
-        .open("efficient_addresses.txt")
-        .expect("Could not create or open `efficient_addresses.txt` file.");
-
-    let start_without_prefix = &config.target_start_string[2..];
-
-    eprintln!(
-        "Searching for addresses starting with 0x{}...",
-        &start_without_prefix
-    );
+        if config.best.is_some()
+            && (config.split_by_score || config.rotate_size.is_some() || config.sort_results)
+        {
+            return Err(ConfigError::Invalid(
+                "--best cannot be combined with --split-by-score, --rotate-size or --sorted.",
+            ));
+        }
 
-    let bytes: Vec<u8> = start_without_prefix
-        .as_bytes()
-        .chunks(2)
-        .map(|chunk| u8::from_str_radix(std::str::from_utf8(chunk).unwrap(), 16).unwrap())
-        .collect();
+        if config.truncation_offset != ADDRESS_OFFSET && config.uses_gpu() {
+            return Err(ConfigError::Invalid(
+                "--truncation-offset is only supported on the CPU.",
+            ));
+        }
 
-    let target_start = &bytes[..];
+        if config.rotation != 0
+            && (config.uses_gpu() || config.closest || config.lex_before.is_some())
+        {
+            return Err(ConfigError::Invalid(
+                "--rotation cannot be combined with --closest, --lex-before or a gpu.",
+            ));
+        }
 
-    // set "footer" of hash message using initialization code hash from config
-    let footer: [u8; 32] = config.init_code_hash;
+        // only record addresses that beat the best one found by previous runs
+        if resume_best {
+            if config.split_by_score || config.closest || config.lex_before.is_some() {
+                return Err(ConfigError::Invalid(
+                    "--resume-best cannot be combined with --split-by-score, --closest or \
+                     --lex-before.",
+                ));
+            }
+
+            config.min_score = best_score_in("efficient_addresses.txt")
+                .map_err(|e| ConfigError::read_file("results file", "efficient_addresses.txt", e))?
+                .map(|best| best + 1);
+        }
 
-    // create a random number generator
-    let mut rng = thread_rng();
+        if config
+            .formats
+            .iter()
+            .filter(|(_, path)| path.is_none())
+            .count()
+            > 1
+        {
+            return Err(ConfigError::Invalid(
+                "only one --format can be written to stdout.",
+            ));
+        }
 
-    // begin searching for addresses
-    loop {
-        // create a random 6-byte salt using the random number generator
-        let salt_random_segment = rng.gen_iter::<u8>().take(6).collect::<Vec<u8>>();
+        if config.max_segments.is_some() && config.uses_gpu() {
+            return Err(ConfigError::Invalid(
+                "--max-segments is only supported on the CPU.",
+            ));
+        }
 
-        // header: 0xff ++ factory ++ caller ++ salt_random_segment (47 bytes)
-        let mut header_vec: Vec<u8> = vec![CONTROL_CHARACTER];
-        header_vec.extend(config.factory_address.iter());
-        header_vec.extend(config.calling_address.iter());
-        header_vec.extend(salt_random_segment);
+        if config.max_results.is_some() && config.uses_gpu() {
+            return Err(ConfigError::Invalid(
+                "--max-results is only supported on the CPU.",
+            ));
+        }
 
-        // convert the header vector to a fixed-length array
-        let header: [u8; 47] = to_fixed_47(&header_vec);
+        if config.timeout.is_some() && config.uses_gpu() {
+            return Err(ConfigError::Invalid(
+                "--timeout is only supported on the CPU.",
+            ));
+        }
 
-        // create new hash object
-        let mut hash_header = Keccak::new_keccak256();
+        if config.batch_size != MAX_INCREMENTER && config.uses_gpu() {
+            return Err(ConfigError::Invalid(
+                "--batch-size is only supported on the CPU.",
+            ));
+        }
 
-        // update hash with header
-        hash_header.update(&header);
+        // every segment would search the same nonces from zero
+        if config.batch_size != MAX_INCREMENTER && config.scheme == AddressScheme::Create {
+            return Err(ConfigError::Invalid(
+                "--batch-size can't be combined with --create.",
+            ));
+        }
 
-        // iterate over a 6-byte nonce and compute each address
-        (0..MAX_INCREMENTER)
-            .into_par_iter() // parallelization
-            .map(|x| u64_to_fixed_6(&x)) // convert int nonces to fixed arrays
-            .for_each(|salt_incremented_segment| {
-                // clone the partially-hashed object
-                let mut hash = hash_header.clone();
-
-                // update with body and footer (total: 38 bytes)
-                hash.update(&salt_incremented_segment);
-                hash.update(&footer);
-
-                // hash the payload and get the result
-                let mut res: [u8; 32] = [0; 32];
-                hash.finalize(&mut res);
-
-                let last_20_bytes = &res[12..32];
-
-                // eprintln!("0x{}", hex::encode(&last_20_bytes));
-                let starts_with_facade = last_20_bytes.starts_with(target_start);
-
-                if starts_with_facade {
-                    // truncate first 12 bytes from the hash to derive address
-                    let mut address_bytes: [u8; 20] = Default::default();
-                    address_bytes.copy_from_slice(&res[12..]);
-
-                    // get the address that results from the hash
-                    let address_hex_string = hex::encode(&address_bytes);
-                    let address = format!("{}", &address_hex_string);
-
-                    // get the full salt used to create the address
-                    let header_hex_string = hex::encode(&header_vec);
-                    let body_hex_string = hex::encode(salt_incremented_segment.to_vec());
-                    let full_salt = format!("0x{}{}", &header_hex_string[42..], &body_hex_string);
-
-                    // encode address and set up a variable for the checksum
-                    let address_encoded = address.as_bytes();
-                    let mut checksum_address = "0x".to_string();
-
-                    // create new hash object for computing the checksum
-                    let mut checksum_hash = Keccak::new_keccak256();
-
-                    // update with utf8-encoded address (total: 20 bytes)
-                    checksum_hash.update(&address_encoded);
-
-                    // hash the payload and get the result
-                    let mut checksum_res: [u8; 32] = [0; 32];
-                    checksum_hash.finalize(&mut checksum_res);
-                    let address_hash = hex::encode(checksum_res);
-
-                    // compute the address checksum using the above hash
-                    for nibble in 0..address.len() {
-                        let hash_character = i64::from_str_radix(
-                            &address_hash.chars().nth(nibble).unwrap().to_string(),
-                            16,
-                        )
-                        .unwrap();
-                        let character = address.chars().nth(nibble).unwrap();
-                        if hash_character > 7 {
-                            checksum_address = format!(
-                                "{}{}",
-                                checksum_address,
-                                character.to_uppercase().to_string()
-                            );
-                        } else {
-                            checksum_address =
-                                format!("{}{}", checksum_address, character.to_string());
-                        }
-                    }
+        if config.threads.is_some() && config.uses_gpu() {
+            return Err(ConfigError::Invalid(
+                "--threads is only supported on the CPU.",
+            ));
+        }
+
+        if config.seed.is_some() && config.uses_gpu() {
+            return Err(ConfigError::Invalid("--seed is only supported on the CPU."));
+        }
+
+        if config.random_bytes != 6 && config.uses_gpu() {
+            return Err(ConfigError::Invalid(
+                "--random-bytes is only supported on the CPU.",
+            ));
+        }
 
-                    eprintln!(
-                        "Found address: {} with salt {}",
-                        checksum_address, full_salt
-                    );
-                    let checksummed_starts_with_facade =
-                        checksum_address.starts_with(&config.target_start_string);
+        if config.deterministic && config.uses_gpu() {
+            return Err(ConfigError::Invalid(
+                "--deterministic is only supported on the CPU.",
+            ));
+        }
 
-                    if checksummed_starts_with_facade {
-                        // display the salt and the address.
-                        let output = format!("{} => {}", full_salt, checksum_address);
-                        println!("{}", &output);
+        // there are no random segments to seed
+        if config.deterministic && config.seed.is_some() {
+            return Err(ConfigError::Invalid(
+                "--deterministic cannot be combined with --seed.",
+            ));
+        }
 
-                        // create a lock on the file before writing
-                        file.lock_exclusive().expect("Couldn't lock file.");
+        if config.scheme == AddressScheme::Create {
+            if config.uses_gpu() {
+                return Err(ConfigError::Invalid(
+                    "--create is only supported on the CPU.",
+                ));
+            }
+
+            // the salt holds the deployer's nonce, which is always big-endian
+            if config.nonce_order != NonceOrder::BigEndian {
+                return Err(ConfigError::Invalid(
+                    "--nonce-order cannot be combined with --create.",
+                ));
+            }
+
+            // every caller would search the same nonces
+            if config.calling_addresses.len() > 1 {
+                return Err(ConfigError::Invalid(
+                    "--create cannot be combined with several calling addresses.",
+                ));
+            }
+
+            if config.salt_scheme != SaltScheme::Raw {
+                return Err(ConfigError::Invalid(
+                    "--safe-initializer-hash cannot be combined with --create.",
+                ));
+            }
+        }
 
-                        // write the result to file
-                        writeln!(&file, "{}", &output)
-                            .expect("Couldn't write to `efficient_addresses.txt` file.");
+        // the kernel only hashes raw salts
+        if config.salt_scheme != SaltScheme::Raw && config.uses_gpu() {
+            return Err(ConfigError::Invalid(
+                "--safe-initializer-hash is only supported on the CPU.",
+            ));
+        }
 
-                        // release the file lock
-                        file.unlock().expect("Couldn't unlock file.")
-                    }
-                }
-            });
+        if config.warmup.is_some() && config.uses_gpu() {
+            return Err(ConfigError::Invalid(
+                "--warmup is only supported on the CPU.",
+            ));
+        }
+
+        if config.metrics_file.is_some() && config.uses_gpu() {
+            return Err(ConfigError::Invalid(
+                "--metrics-file is only supported on the CPU.",
+            ));
+        }
+
+        if config.sync_on_write && config.best.is_some() {
+            return Err(ConfigError::Invalid(
+                "--sync-on-write cannot be combined with --best.",
+            ));
+        }
+
+        if config.show_progress && config.uses_gpu() {
+            return Err(ConfigError::Invalid(
+                "--progress is only supported on the CPU (the GPU search always shows its \
+                 progress).",
+            ));
+        }
+
+        if config.resume && config.uses_gpu() {
+            return Err(ConfigError::Invalid(
+                "--resume is only supported on the CPU.",
+            ));
+        }
+
+        if config.fast_first && config.sort_results {
+            return Err(ConfigError::Invalid(
+                "--fast-first cannot be combined with --sorted.",
+            ));
+        }
+
+        for target in &config.simulate {
+            if !target.starts_with("0x")
+                || !target[2..]
+                    .chars()
+                    .all(|c| c.is_ascii_hexdigit() || c == '?')
+                || target.len() > 42
+            {
+                return Err(ConfigError::Invalid(
+                    "--simulate targets must be 0x-prefixed hex strings of up to 40 characters.",
+                ));
+            }
+        }
+
+        if let Some(ref bound) = config.lex_before {
+            if !bound.starts_with("0x") || !bound[2..].chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(ConfigError::Invalid(
+                    "--lex-before bound must be a 0x-prefixed hex string.",
+                ));
+            }
+
+            if config.closest {
+                return Err(ConfigError::Invalid(
+                    "--lex-before cannot be combined with --closest.",
+                ));
+            }
+
+            if config.uses_gpu() {
+                return Err(ConfigError::Invalid(
+                    "--lex-before is only supported on the CPU.",
+                ));
+            }
+        }
+
+        if let Some(ref suffix) = config.target_end_string {
+            if suffix.len() < 3
+                || suffix.len() > 42
+                || !suffix.starts_with("0x")
+                || !suffix[2..].chars().all(|c| c.is_ascii_hexdigit())
+            {
+                return Err(ConfigError::Invalid(
+                    "--suffix must be a 0x-prefixed hex string of 1 to 40 characters.",
+                ));
+            }
+
+            if config.closest || config.lex_before.is_some() {
+                return Err(ConfigError::Invalid(
+                    "--suffix cannot be combined with --closest or --lex-before.",
+                ));
+            }
+
+            if config.uses_gpu() {
+                return Err(ConfigError::Invalid(
+                    "--suffix is only supported on the CPU.",
+                ));
+            }
+        }
+
+        if let Some(ref characters) = config.forbidden {
+            if characters.is_empty() || !characters.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(ConfigError::Invalid(
+                    "--forbid requires a set of hex characters.",
+                ));
+            }
+
+            let suffix = config.target_end_string.as_deref().unwrap_or("0x");
+            if config
+                .targets()
+                .any(|target| target[2..].contains(|c| characters.contains(c)))
+                || suffix[2..].contains(|c| characters.contains(c))
+            {
+                return Err(ConfigError::Invalid(
+                    "the target contains a character forbidden by --forbid.",
+                ));
+            }
+
+            if config.closest {
+                return Err(ConfigError::Invalid(
+                    "--forbid cannot be combined with --closest.",
+                ));
+            }
+        }
+
+        if config.encoded_target.is_some() && (config.closest || config.lex_before.is_some()) {
+            return Err(ConfigError::Invalid(
+                "--base32 and --base58 cannot be combined with --closest or --lex-before.",
+            ));
+        }
+
+        if config.monotone.is_some() && (config.closest || config.lex_before.is_some()) {
+            return Err(ConfigError::Invalid(
+                "--increasing and --decreasing cannot be combined with --closest or --lex-before.",
+            ));
+        }
+
+        if (config.require_palindrome || config.min_repeat_run.is_some())
+            && (config.closest || config.lex_before.is_some())
+        {
+            return Err(ConfigError::Invalid(
+                "--palindrome and --min-repeat-run cannot be combined with --closest or \
+                 --lex-before.",
+            ));
+        }
+
+        if !config.extra_targets.is_empty() {
+            if config.targets().any(|target| target == "0x") {
+                return Err(ConfigError::Invalid(
+                    "an empty target can't be one of several targets.",
+                ));
+            }
+
+            if config.uses_gpu() {
+                return Err(ConfigError::Invalid(
+                    "several targets are only supported on the CPU.",
+                ));
+            }
+
+            if config.closest || config.lex_before.is_some() {
+                return Err(ConfigError::Invalid(
+                    "several targets cannot be combined with --closest or --lex-before.",
+                ));
+            }
+        }
+
+        if config.targets().any(|target| target.contains('?')) {
+            if config.uses_gpu() {
+                return Err(ConfigError::Invalid(
+                    "targets with ? wildcards are only supported on the CPU.",
+                ));
+            }
+
+            // the distance to a target is only defined for a full address
+            if config.closest {
+                return Err(ConfigError::Invalid(
+                    "--closest requires a target without ? wildcards.",
+                ));
+            }
+        }
+
+        if config.closest && config.closest_address().is_none() {
+            return Err(ConfigError::Invalid(
+                "--closest requires the target to be a full 20-byte address.",
+            ));
+        }
+
+        // a bare `0x` target with nothing else to narrow it down would record
+        // every address it comes across, so (as on the GPU) the zero
+        // thresholds apply instead, unless every address is wanted
+        let unconstrained = config.target_start_string == "0x"
+            && config.target_end_string.is_none()
+            && config.simulate.is_empty()
+            && config.lex_before.is_none()
+            && config.forbidden.is_none()
+            && config.min_leading_zero_nibbles.is_none()
+            && config.monotone.is_none()
+            && !config.require_palindrome
+            && config.min_repeat_run.is_none()
+            && config.encoded_target.is_none();
+        if config.match_all && config.uses_gpu() {
+            return Err(ConfigError::Invalid(
+                "--match-all is only supported on the CPU.",
+            ));
+        }
+        config.zero_thresholds = unconstrained && !config.match_all && !config.uses_gpu();
+
+        // a target that matches nearly every address only records improvements
+        config.improvements_only = !config.closest
+            && !config.match_all
+            && !config.zero_thresholds
+            && config.lex_before.is_none()
+            && config.target_selectivity() < LOOSE_TARGET_BITS;
+
+        if let Some(warning) = config
+            .warnings()
+            .into_iter()
+            .next()
+            .filter(|_| config.strict)
+        {
+            return Err(ConfigError::Invalid(warning));
+        }
+
+        Ok(config)
     }
-}
 
-/// Given a Config object with a factory address, a caller address, a keccak-256
-/// hash of the contract initialization code, and a device ID, search for salts
-/// using OpenCL that will enable the factory contract to deploy a contract to a
-/// gas-efficient address via CREATE2. This method also takes threshold values
-/// for both leading zero bytes and total zero bytes - any address that does not
-/// meet or exceed the threshold will not be returned. Default threshold values
-/// are three leading zeroes or five total zeroes.
-///
-/// The 32-byte salt is constructed as follows:
-///   - the 20-byte calling address (to prevent frontrunning)
-///   - a random 4-byte segment (to prevent collisions with other runs)
-///   - a 4-byte segment unique to each work group running in parallel
-///   - a 4-byte nonce segment (incrementally stepped through during the run)
-///
-/// When a salt that will result in the creation of a gas-efficient contract
-/// address is found, it will be appended to `efficient_addresses.txt` along
-/// with the resultant address and the "value" (i.e. approximate rarity) of the
-/// resultant address.
-///
-/// This method is still highly experimental and could almost certainly use
-/// further optimization - contributions are more than welcome!
-// pub fn gpu(config: Config) -> ocl::Result<()> {
-//     println!(
-//         "Setting up experimental OpenCL miner using device {}...",
-//         config.gpu_device
-//     );
-
-//     // (create if necessary) and open a file where found salts will be written
-//     let file = OpenOptions::new()
-//         .append(true)
-//         .create(true)
-//         .open("efficient_addresses.txt")
-//         .expect("Could not create or open `efficient_addresses.txt` file.");
-
-//     // track how many addresses have been found and information about them
-//     let mut found: u64 = 0;
-//     let mut found_list: Vec<String> = vec![];
-
-//     // set up a controller for terminal output
-//     let term = Term::stdout();
-
-//     // set up a platform to use
-//     let platform = Platform::default();
-
-//     // set up the device to use
-//     let device = Device::by_idx_wrap(platform, config.gpu_device as usize)?;
-
-//     // set up the context to use
-//     let context = Context::builder()
-//         .platform(platform)
-//         .devices(device.clone())
-//         .build()?;
-
-//     // get factory, caller, and initialization code hash from config object
-//     let factory: [u8; 20] = config.factory_address;
-//     let caller: [u8; 20] = config.calling_address;
-//     let init_hash: [u8; 32] = config.init_code_hash;
-
-//     // generate the kernel source code with the define macros
-//     let kernel_src = &format!(
-//         "{}\n{}\n{}\n#define LEADING_ZEROES {}\n#define TOTAL_ZEROES {}\n{}",
-//         factory
-//             .iter()
-//             .enumerate()
-//             .map(|(i, x)| format!("#define S_{} {}u\n", i + 1, x))
-//             .collect::<String>(),
-//         caller
-//             .iter()
-//             .enumerate()
-//             .map(|(i, x)| format!("#define S_{} {}u\n", i + 21, x))
-//             .collect::<String>(),
-//         init_hash
-//             .iter()
-//             .enumerate()
-//             .map(|(i, x)| format!("#define S_{} {}u\n", i + 53, x))
-//             .collect::<String>(),
-//         config.leading_zeroes_threshold,
-//         config.total_zeroes_threshold,
-//         KERNEL_SRC
-//     );
-
-//     // set up the program to use
-//     let program = Program::builder()
-//         .devices(device)
-//         .src(kernel_src)
-//         .build(&context)?;
-
-//     // set up the queue to use
-//     let queue = Queue::new(&context, device, None)?;
-
-//     // set up the "proqueue" (or amalgamation of various elements) to use
-//     let ocl_pq = ProQue::new(context, queue, program, Some(WORK_SIZE));
-
-//     // create a random number generator
-//     let mut rng = thread_rng();
-
-//     // determine the start time
-//     let start_time: f64 = SystemTime::now()
-//         .duration_since(UNIX_EPOCH)
-//         .unwrap()
-//         .as_secs() as f64;
-
-//     // set up variables for tracking performance
-//     let mut rate: f64 = 0.0;
-//     let mut cumulative_nonce: u64 = 0;
-
-//     // the previous timestamp of printing to the terminal
-//     let mut previous_time: f64 = 0.0;
-
-//     // the last work duration in milliseconds
-//     let mut work_duration_millis: u64 = 0;
-
-//     // begin searching for addresses
-//     loop {
-//         // create a random 4-byte salt using the random number generator
-//         let salt = rng.gen_iter::<u8>().take(4).collect::<Vec<u8>>();
-
-//         // construct the 4-byte message to hash, leaving last 8 of salt empty
-//         let message: [u8; 4] = to_fixed_4(&salt);
-
-//         // build a corresponding buffer for passing the message to the kernel
-//         let message_buffer = Buffer::builder()
-//             .queue(ocl_pq.queue().clone())
-//             .flags(MemFlags::new().read_only())
-//             .len(4)
-//             .copy_host_slice(&message)
-//             .build()?;
-
-//         // reset nonce & create a buffer to view it in little-endian
-//         // for more uniformly distributed nonces, we shall initialize it to a random value
-//         let mut nonce: [u32; 1] = [rng.next_u32()];
-//         let mut view_buf = [0; 8];
-
-//         // build a corresponding buffer for passing the nonce to the kernel
-//         let mut nonce_buffer = Buffer::builder()
-//             .queue(ocl_pq.queue().clone())
-//             .flags(MemFlags::new().read_only())
-//             .len(1)
-//             .copy_host_slice(&nonce)
-//             .build()?;
-
-//         // establish a buffer for nonces that result in desired addresses
-//         let mut solutions: Vec<u64> = vec![0; 1];
-//         let solutions_buffer: Buffer<u64> = Buffer::builder()
-//             .queue(ocl_pq.queue().clone())
-//             .flags(MemFlags::new().write_only())
-//             .len(1)
-//             .copy_host_slice(&solutions)
-//             .build()?;
-
-//         // repeatedly enqueue kernel to search for new addresses
-//         loop {
-//             // build the kernel and define the type of each buffer
-//             let kern = ocl_pq
-//                 .kernel_builder("hashMessage")
-//                 .arg_named("message", None::<&Buffer<u8>>)
-//                 .arg_named("nonce", None::<&Buffer<u32>>)
-//                 .arg_named("solutions", None::<&Buffer<u64>>)
-//                 .build()?;
-
-//             // set each buffer
-//             kern.set_arg("message", Some(&message_buffer))?;
-//             kern.set_arg("nonce", Some(&nonce_buffer))?;
-//             kern.set_arg("solutions", &solutions_buffer)?;
-
-//             // enqueue the kernel
-//             unsafe {
-//                 kern.enq()?;
-//             }
-
-//             // calculate the current time
-//             let mut now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-//             let current_time: f64 = now.as_secs() as f64;
-
-//             // we don't want to print too fast
-//             let print_output: bool = current_time - previous_time > 0.99;
-//             previous_time = current_time;
-
-//             // clear the terminal screen
-//             if print_output {
-//                 term.clear_screen()?;
-
-//                 // get the total runtime and parse into hours : minutes : seconds
-//                 let total_runtime = current_time - start_time;
-//                 let total_runtime_hrs = *&total_runtime as u64 / (3600);
-//                 let total_runtime_mins = (*&total_runtime as u64 - &total_runtime_hrs * 3600) / 60;
-//                 let total_runtime_secs = &total_runtime
-//                     - (&total_runtime_hrs * 3600) as f64
-//                     - (&total_runtime_mins * 60) as f64;
-
-//                 // determine the number of attempts being made per second
-//                 let work_rate: u128 = WORK_FACTOR * cumulative_nonce as u128;
-//                 if total_runtime > 0.0 {
-//                     rate = 1.0 / total_runtime;
-//                 }
-
-//                 // fill the buffer for viewing the properly-formatted nonce
-//                 LittleEndian::write_u64(&mut view_buf, (nonce[0] as u64) << 32);
-
-//                 // calculate the terminal height, defaulting to a height of ten rows
-//                 let size = terminal_size();
-//                 let height: u16;
-//                 if let Some((Width(_w), Height(h))) = size {
-//                     height = h;
-//                 } else {
-//                     height = 10;
-//                 }
-
-//                 // display information about the total runtime and work size
-//                 term.write_line(&format!(
-//                     "total runtime: {}:{:02}:{:02} ({} cycles)\t\t\t\
-//                   work size per cycle: {}",
-//                     total_runtime_hrs,
-//                     total_runtime_mins,
-//                     total_runtime_secs,
-//                     cumulative_nonce,
-//                     WORK_SIZE.separated_string()
-//                 ))?;
-
-//                 // display information about the attempt rate and found solutions
-//                 term.write_line(&format!(
-//                     "rate: {:.2} million attempts per second\t\t\t\
-//                   total found this run: {}",
-//                     work_rate as f64 * rate,
-//                     &found
-//                 ))?;
-//                 // display information about the current search criteria
-//                 term.write_line(&format!(
-//                     "current search space: {}xxxxxxxx{:08x}\t\t\
-//                   threshold: {} leading or {} total zeroes",
-//                     hex::encode(&salt),
-//                     BigEndian::read_u64(&view_buf),
-//                     config.leading_zeroes_threshold,
-//                     config.total_zeroes_threshold
-//                 ))?;
-
-//                 // display recently found solutions based on terminal height
-//                 let rows: usize = if height < 5 { 1 } else { (height - 4) as usize };
-//                 let last_rows: Vec<String> = found_list.iter().cloned().rev().take(rows).collect();
-//                 let ordered: Vec<String> = last_rows.iter().cloned().rev().collect();
-//                 let recently_found = &ordered.join("\n");
-//                 term.write_line(&recently_found)?;
-//             }
-
-//             // increment the cumulative nonce (does not reset after a match)
-//             cumulative_nonce += 1;
-
-//             // record the start time of the work
-//             let work_start_time_millis = now.as_secs() * 1000 + now.subsec_nanos() as u64 / 1000000;
-
-//             // sleep for 98% of the previous work duration to conserve CPU
-//             if work_duration_millis != 0 {
-//                 std::thread::sleep(std::time::Duration::from_millis(
-//                     work_duration_millis * 980 / 1000,
-//                 ));
-//             }
-
-//             // read the solutions from the device
-//             solutions_buffer.read(&mut solutions).enq()?;
-
-//             // record the end time of the work and compute how long the work took
-//             now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-//             work_duration_millis = (now.as_secs() * 1000 + now.subsec_nanos() as u64 / 1000000)
-//                 - work_start_time_millis;
-
-//             // if at least one solution is found, end the loop
-//             if solutions[0] != 0 {
-//                 break;
-//             }
-
-//             // if no solution has yet been found, increment the nonce
-//             nonce[0] += 1;
-
-//             // update the nonce buffer with the incremented nonce value
-//             nonce_buffer = Buffer::builder()
-//                 .queue(ocl_pq.queue().clone())
-//                 .flags(MemFlags::new().read_write())
-//                 .len(1)
-//                 .copy_host_slice(&nonce)
-//                 .build()?;
-//         }
-
-//         // iterate over each solution, first converting to a fixed array
-//         solutions
-//             .iter()
-//             .filter(|&i| *i != 0)
-//             .map(|i| u64_to_le_fixed_8(i))
-//             .for_each(|solution| {
-//                 // proceed if a solution is found at the given location
-//                 if &solution != &EIGHT_ZERO_BYTES {
-//                     let mut solution_message: Vec<u8> = vec![CONTROL_CHARACTER];
-
-//                     solution_message.extend(factory.iter());
-//                     solution_message.extend(caller.iter());
-//                     solution_message.extend(salt.iter());
-//                     solution_message.extend(solution.iter());
-//                     solution_message.extend(init_hash.iter());
-
-//                     // create new hash object
-//                     let mut hash = Keccak::new_keccak256();
-
-//                     // update with header
-//                     hash.update(&solution_message);
-
-//                     // hash the payload and get the result
-//                     let mut res: [u8; 32] = [0; 32];
-//                     hash.finalize(&mut res);
-
-//                     let last_20_bytes = &res[12..32];
-//                     let starts_with_facade = last_20_bytes.starts_with(&[0xFA, 0xCA, 0xDE]);
-
-//                     if starts_with_facade {
-//                         let mut address_bytes: [u8; 20] = Default::default();
-//                         address_bytes.copy_from_slice(&res[12..]);
-
-//                         // get the address that results from the hash
-//                         let address_hex_string = hex::encode(&address_bytes);
-//                         let address = format!("{}", &address_hex_string);
-
-//                         // encode address and set up a variable for the checksum
-//                         let address_encoded = address.as_bytes();
-//                         let mut checksum_address = "0x".to_string();
-
-//                         // create new hash object for computing the checksum
-//                         let mut checksum_hash = Keccak::new_keccak256();
-
-//                         // update with utf8-encoded address (total: 20 bytes)
-//                         checksum_hash.update(&address_encoded);
-
-//                         // hash the payload and get the result
-//                         let mut checksum_res: [u8; 32] = [0; 32];
-//                         checksum_hash.finalize(&mut checksum_res);
-//                         let address_hash = hex::encode(checksum_res);
-
-//                         // compute the checksum using the above hash
-//                         for nibble in 0..address.len() {
-//                             let hash_character = i64::from_str_radix(
-//                                 &address_hash.chars().nth(nibble).unwrap().to_string(),
-//                                 16,
-//                             )
-//                             .unwrap();
-//                             let character = address.chars().nth(nibble).unwrap();
-//                             if hash_character > 7 {
-//                                 checksum_address = format!(
-//                                     "{}{}",
-//                                     checksum_address,
-//                                     character.to_uppercase().to_string()
-//                                 );
-//                             } else {
-//                                 checksum_address =
-//                                     format!("{}{}", checksum_address, character.to_string());
-//                             }
-//                         }
-
-//                         let output = format!(
-//                             "0x{}{}{} => {}",
-//                             hex::encode(&caller),
-//                             hex::encode(&salt),
-//                             hex::encode(&solution),
-//                             checksum_address,
-//                         );
-
-//                         let show = format!("{}", &output);
-//                         let next_found = vec![show.to_string()];
-//                         found_list.extend(next_found);
-
-//                         file.lock_exclusive().expect("Couldn't lock file.");
-
-//                         writeln!(&file, "{}", &output)
-//                             .expect("Couldn't write to `efficient_addresses.txt` file.");
-
-//                         file.unlock().expect("Couldn't unlock file.");
-//                         found = found + 1;
-//                     }
-//                 }
-//             });
-//     }
-// }
-
-/// Remove the `0x` prefix from a hex string.
-fn without_prefix(string: String) -> String {
-    string
-        .char_indices()
-        .nth(2)
-        .and_then(|(i, _)| string.get(i..))
-        .unwrap()
-        .to_string()
-}
+    /// Start building a configuration from already-parsed values rather than
+    /// command line arguments (see `ConfigBuilder`).
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
 
-/// Convert a properly-sized vector to a fixed array of 20 bytes.
-fn to_fixed_20(bytes: std::vec::Vec<u8>) -> [u8; 20] {
-    let mut array = [0; 20];
-    let bytes = &bytes[..array.len()];
-    array.copy_from_slice(bytes);
-    array
-}
+    /// Require addresses to also pass a custom test in order to match. The
+    /// test is only applied to addresses that already match the target, so
+    /// it's called once per candidate that passes the built-in checks rather
+    /// than once per attempt; a target of `0x` applies it to every address.
+    pub fn with_predicate<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&[u8; 20]) -> bool + Send + Sync + 'static,
+    {
+        self.predicate = Some(CustomPredicate(Arc::new(predicate)));
+        self
+    }
 
-/// Convert a properly-sized vector to a fixed array of 32 bytes.
-fn to_fixed_32(bytes: std::vec::Vec<u8>) -> [u8; 32] {
-    let mut array = [0; 32];
-    let bytes = &bytes[..array.len()];
-    array.copy_from_slice(bytes);
-    array
-}
+    /// Call the given function with the progress of a CPU search about once
+    /// per second while it runs, e.g. to show the rate in an application's
+    /// own interface. This works with or without `--progress`, which draws
+    /// the built-in display from the same figures.
+    pub fn on_progress<F>(mut self, on_progress: F) -> Self
+    where
+        F: Fn(ProgressStats) + Send + Sync + 'static,
+    {
+        self.on_progress = Some(OnProgress(Arc::new(on_progress)));
+        self
+    }
 
-/// Convert a properly-sized vector to a fixed array of 47 bytes.
-fn to_fixed_47(bytes: &std::vec::Vec<u8>) -> [u8; 47] {
-    let mut array = [0; 47];
-    let bytes = &bytes[..array.len()];
-    array.copy_from_slice(bytes);
-    array
-}
+    /// The number of nonces searched between checks of the flags that stop a
+    /// search: `CHECK_INTERVAL`, or the smaller `FAST_CHECK_INTERVAL` with
+    /// `--fast-first`. Smaller chunks are handed out to threads more often,
+    /// which gets every thread searching sooner and makes the search respond
+    /// sooner to being stopped, at the cost of a little throughput.
+    pub fn check_interval(&self) -> u64 {
+        if self.fast_first {
+            FAST_CHECK_INTERVAL
+        } else {
+            CHECK_INTERVAL
+        }
+    }
 
-/// Convert a properly-sized vector to a fixed array of 4 bytes.
-fn to_fixed_4(bytes: &std::vec::Vec<u8>) -> [u8; 4] {
-    let mut array = [0; 4];
-    let bytes = &bytes[..array.len()];
-    array.copy_from_slice(bytes);
-    array
-}
+    /// The generator that random salt segments are drawn from: seeded with
+    /// `seed` if one was given, so that a run can be reproduced exactly, or
+    /// else the thread-local generator.
+    pub fn segment_rng(&self) -> Box<dyn Rng> {
+        match self.seed {
+            Some(seed) => Box::new(Isaac64Rng::from_seed(&[seed][..])),
+            None => Box::new(thread_rng()),
+        }
+    }
+
+    /// The factory, callers, init code hash and targets of the search, one
+    /// to a labeled line, for checking before a long run that the arguments
+    /// went where they were meant to.
+    pub fn describe(&self) -> String {
+        let mut description = String::new();
+        self.write_inputs(&mut description, false)
+            .expect("Writing to a String never fails.");
+        description
+    }
+
+    /// Write the lines of `describe`, which also open the `Display` of the
+    /// config; with `decoded`, each target is followed by the bytes it
+    /// decodes to (and the first by the bits it fixes).
+    fn write_inputs(&self, f: &mut dyn fmt::Write, decoded: bool) -> fmt::Result {
+        writeln!(
+            f,
+            "factory:        {}",
+            to_checksum_address(&self.factory_address)
+        )?;
+        for caller in &self.calling_addresses {
+            writeln!(f, "caller:         {}", to_checksum_address(caller))?;
+        }
+        writeln!(f, "init code hash: 0x{}", hex::encode(self.init_code_hash))?;
+        write!(f, "target:         {}", self.target_start_string)?;
+        if decoded {
+            write!(
+                f,
+                " (bytes 0x{}, {} bits)",
+                hex::encode(target_start_bytes(self).bytes()),
+                self.target_selectivity()
+            )?;
+        }
+        writeln!(f)?;
+        for target in &self.extra_targets {
+            write!(f, "or:             {}", target)?;
+            if decoded {
+                write!(
+                    f,
+                    " (bytes 0x{})",
+                    hex::encode(prefix_bytes(target).bytes())
+                )?;
+            }
+            writeln!(f)?;
+        }
+        if let Some(ref suffix) = self.target_end_string {
+            writeln!(f, "target end:     {}", suffix)?;
+        }
+        Ok(())
+    }
+
+    /// Signs that the positional arguments were given in the wrong order or
+    /// are not what was meant, which would leave a search running for hours
+    /// on addresses that can't be deployed to. The two addresses can't be
+    /// told apart by length, so these are heuristics: a search they flag may
+    /// still be intended, unless `--strict` is given.
+    pub fn warnings(&self) -> Vec<&'static str> {
+        let mut warnings = vec![];
+        if self.factory_address == [0; 20] {
+            warnings.push("the factory is the zero address; were the factory and caller swapped?");
+        }
+        if self
+            .calling_addresses
+            .iter()
+            .any(|caller| KNOWN_FACTORIES.contains(caller))
+        {
+            warnings.push(
+                "a caller is a well-known CREATE2 factory; were the factory and caller swapped?",
+            );
+        }
+        if self.calling_addresses.contains(&self.factory_address) && self.factory_address != [0; 20]
+        {
+            warnings.push("the factory is also given as a caller.");
+        }
+        if self.init_code_hash[..12] == [0; 12] {
+            warnings.push(
+                "the init code hash looks like a padded address; was an address given in its \
+                 place?",
+            );
+        }
+        if self.init_code_hash == EMPTY_INIT_CODE_HASH {
+            warnings
+                .push("the init code hash is the hash of empty init code, which deploys no code.");
+        }
+        warnings
+    }
+
+    /// Whether the search runs on one or more GPUs rather than the CPU.
+    pub fn uses_gpu(&self) -> bool {
+        // a device of 255 indicates that the CPU is used
+        self.all_gpus || self.gpu_device != 255
+    }
+
+    /// The target address when mining the closest address to it, provided
+    /// that the target is a full 20-byte address.
+    pub fn closest_address(&self) -> Option<[u8; 20]> {
+        let target_start = target_start_bytes(self);
+        if self.closest && target_start.nibbles == 40 {
+            Some(to_fixed_20(target_start.bytes()))
+        } else {
+            None
+        }
+    }
+
+    /// The number of bits of the address constrained by a `0x`-prefixed
+    /// target: four for each nibble, plus (with `--case-sensitive`) one for
+    /// each letter, since the checksummed address must then also match that
+    /// letter's case.
+    pub fn bits_of(&self, target: &str) -> u32 {
+        if self.case_sensitive {
+            selectivity(target)
+        } else {
+            fixed_nibbles(target) * 4
+        }
+    }
+
+    /// The number of bits of the address constrained by the search criteria:
+    /// those of the target and suffix (see `bits_of`) along with any other
+    /// criteria. A random address matches with a probability of `2^-bits`.
+    pub fn target_selectivity(&self) -> u32 {
+        let bits = |target: &str| self.bits_of(target);
+        // an address may match any one of several targets, so their odds add up
+        let target_odds: f64 = self
+            .targets()
+            .map(|target| 2f64.powi(-(bits(target) as i32)))
+            .sum();
+        let target_bits = (-target_odds.log2()).round() as u32
+            + self.target_end_string.as_deref().map_or(0, bits);
+
+        // a monotone run constrains the same leading bytes as the target,
+        // so this overstates a combination of the two
+        let monotone_bits = self
+            .monotone
+            .map_or(0.0, |monotone| monotone.selectivity_bits());
+
+        // each character of an encoded target narrows the address by a factor
+        // of the base (though not exactly, as the first is skewed)
+        let encoded_bits = self.encoded_target.as_ref().map_or(0.0, |(base, prefix)| {
+            prefix.len() as f64 * (base.alphabet().len() as f64).log2()
+        });
+
+        // the leading zero nibbles required on top of any target (this
+        // overlaps the target and thresholds, so overstates a combination)
+        let nibble_bits = self.min_leading_zero_nibbles.map_or(0, |n| n * 4);
+
+        // the leading zero bytes required by the thresholds (this ignores the
+        // alternative of enough total zero bytes, so it slightly overstates
+        // how rare a match is)
+        let threshold_bits = if self.zero_thresholds {
+            u32::from(self.leading_zeroes_threshold) * 8
+        } else {
+            0
+        };
+
+        // a palindrome fixes the last 20 nibbles by the first 20
+        let palindrome_bits = if self.require_palindrome { 80 } else { 0 };
+
+        // a run of `n` identical nibbles can start at any of `41 - n` places
+        // (this counts overlapping runs more than once, so understates it)
+        let repeat_bits = self.min_repeat_run.map_or(0.0, |n| {
+            let n = f64::from(n);
+            ((n - 1.0) * 4.0 - (41.0 - n).log2()).max(0.0)
+        });
+
+        target_bits
+            + nibble_bits
+            + threshold_bits
+            + palindrome_bits
+            + (monotone_bits + encoded_bits + repeat_bits).round() as u32
+    }
+
+    /// Whether an address has enough leading or total zero bytes to meet the
+    /// thresholds, if they apply (see `zero_thresholds`), and enough leading
+    /// zero nibbles for `--min-leading-zero-nibbles`. As on the GPU, a total
+    /// threshold above 20 is never met.
+    pub fn meets_thresholds(&self, address: &[u8; 20]) -> bool {
+        if self
+            .min_leading_zero_nibbles
+            .is_some_and(|min| leading_zero_nibbles(address) < min)
+        {
+            return false;
+        }
+        if !self.zero_thresholds {
+            return true;
+        }
+
+        let leading = address.iter().take_while(|&&b| b == 0).count();
+        let total = address.iter().filter(|&&b| b == 0).count();
+        leading >= usize::from(self.leading_zeroes_threshold)
+            || total >= usize::from(self.total_zeroes_threshold)
+    }
+
+    /// Whether a found address should be announced on the terminal, i.e. it
+    /// meets `--announce-min-score`. Every found address is written either
+    /// way.
+    pub fn announces(&self, found: &FoundAddress) -> bool {
+        self.announce_min_score
+            .is_none_or(|min_score| found.score() >= min_score)
+    }
 
-/// Convert a 64-bit unsigned integer to a fixed array of six bytes.
-fn u64_to_fixed_6(x: &u64) -> [u8; 6] {
-    let mask: u64 = 0xff;
-    let b1: u8 = ((x >> 40) & mask) as u8;
-    let b2: u8 = ((x >> 32) & mask) as u8;
-    let b3: u8 = ((x >> 24) & mask) as u8;
-    let b4: u8 = ((x >> 16) & mask) as u8;
-    let b5: u8 = ((x >> 8) & mask) as u8;
-    let b6: u8 = (x & mask) as u8;
-    [b1, b2, b3, b4, b5, b6]
-}
-
-/// Convert 64-bit unsigned integer to little-endian fixed array of eight bytes.
-fn u64_to_le_fixed_8(x: &u64) -> [u8; 8] {
-    let mask: u64 = 0xff;
-    let b1: u8 = ((x >> 56) & mask) as u8;
-    let b2: u8 = ((x >> 48) & mask) as u8;
-    let b3: u8 = ((x >> 40) & mask) as u8;
-    let b4: u8 = ((x >> 32) & mask) as u8;
-    let b5: u8 = ((x >> 24) & mask) as u8;
-    let b6: u8 = ((x >> 16) & mask) as u8;
-    let b7: u8 = ((x >> 8) & mask) as u8;
-    let b8: u8 = (x & mask) as u8;
-    [b8, b7, b6, b5, b4, b3, b2, b1]
+    /// Whether a checksummed address avoids every character forbidden by
+    /// `--forbid`. Letters are compared in their checksummed case, so
+    /// forbidding `b` still allows `B`.
+    pub fn allows_characters(&self, checksum_address: &str) -> bool {
+        match self.forbidden {
+            Some(ref characters) => !checksum_address[2..].contains(|c| characters.contains(c)),
+            None => true,
+        }
+    }
+
+    /// The address as the target is matched against: rotated left by
+    /// `rotation` bytes, so that byte `rotation` of the address comes first.
+    pub fn rotated(&self, address: &[u8; 20]) -> [u8; 20] {
+        let mut rotated = *address;
+        rotated.rotate_left(usize::from(self.rotation));
+        rotated
+    }
+
+    /// Every target an address may match: `target_start_string`, followed by
+    /// `extra_targets`.
+    pub fn targets(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.target_start_string).chain(&self.extra_targets)
+    }
+
+    /// Whether a checksummed address starts with one of the targets once
+    /// rotated by `rotation` bytes (and ends with the suffix, if any), taking
+    /// the case of their letters into account with `--case-sensitive`.
+    pub fn matches_target_case(&self, checksum_address: &str) -> bool {
+        if let Some(ref suffix) = self.target_end_string {
+            let end = &checksum_address[checksum_address.len() - (suffix.len() - 2)..];
+            if self.case_sensitive && end != &suffix[2..] || !end.eq_ignore_ascii_case(&suffix[2..])
+            {
+                return false;
+            }
+        }
+
+        self.matched_target(checksum_address).is_some()
+    }
+
+    /// The first of the targets that a checksummed address starts with once
+    /// rotated by `rotation` bytes, with the same casing rules as
+    /// `matches_target_case` (but ignoring the suffix).
+    pub fn matched_target(&self, checksum_address: &str) -> Option<&str> {
+        let rotated;
+        let checksum_address = if self.rotation == 0 {
+            checksum_address
+        } else {
+            let (head, tail) = checksum_address[2..].split_at(usize::from(self.rotation) * 2);
+            rotated = format!("0x{}{}", tail, head);
+            &rotated
+        };
+
+        self.targets()
+            .find(|target| self.matches_case(checksum_address, target))
+            .map(String::as_str)
+    }
+
+    /// Whether a checksummed address starts with the given target, taking
+    /// the case of its letters into account with `--case-sensitive`. A `?` in
+    /// the target matches any character.
+    pub fn matches_case(&self, checksum_address: &str, target: &str) -> bool {
+        let start = match checksum_address.get(..target.len()) {
+            Some(start) => start,
+            None => return false,
+        };
+        start
+            .bytes()
+            .zip(target.bytes())
+            .all(|(c, t)| t == b'?' || c == t || !self.case_sensitive && c.eq_ignore_ascii_case(&t))
+    }
+}
+
+/// Formats the configuration as parsed, one setting per line, with addresses
+/// checksummed. Options that are off are omitted.
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_inputs(f, true)?;
+
+        if self.all_gpus {
+            write!(f, "device:         all gpus")?;
+        } else if self.uses_gpu() {
+            write!(f, "device:         gpu {}", self.gpu_device)?;
+        } else {
+            write!(f, "device:         cpu")?;
+        }
+        if self.hybrid {
+            write!(f, " and cpu")?;
+        }
+        if self.uses_gpu() || self.zero_thresholds {
+            write!(
+                f,
+                " (thresholds: {} leading or {} total zero bytes)",
+                self.leading_zeroes_threshold, self.total_zeroes_threshold
+            )?;
+        }
+        writeln!(f)?;
+
+        let options = [
+            ("split by score", self.split_by_score),
+            ("preview", self.preview),
+            ("salt decimal", self.salt_decimal),
+            ("run meta", self.run_meta),
+            ("header", self.write_header),
+            ("strict", self.strict),
+            ("closest", self.closest),
+            ("case sensitive", self.case_sensitive),
+            ("dedupe addresses", self.dedupe_addresses),
+            ("sorted", self.sort_results),
+            ("bench", self.bench),
+            ("fast first", self.fast_first),
+            ("quiet", self.quiet),
+            ("progress", self.show_progress),
+            ("resume", self.resume),
+            ("attempt number", self.attempt_number),
+            ("match all", self.match_all),
+            ("create", self.scheme == AddressScheme::Create),
+            ("safe salt nonces", self.salt_scheme != SaltScheme::Raw),
+            (
+                "little-endian nonces",
+                self.nonce_order == NonceOrder::LittleEndian,
+            ),
+            ("sync on write", self.sync_on_write),
+            ("improvements only", self.improvements_only),
+        ];
+        for &(name, _) in options.iter().filter(|&&(_, enabled)| enabled) {
+            writeln!(f, "option:         {}", name)?;
+        }
+        if self.hmac_key.is_some() {
+            writeln!(f, "option:         hmac")?;
+        }
+        if self.predicate.is_some() {
+            writeln!(f, "option:         custom predicate")?;
+        }
+        if let Some(ref bound) = self.lex_before {
+            writeln!(f, "option:         lex before {}", bound)?;
+        }
+        if let Some(ref characters) = self.forbidden {
+            writeln!(f, "option:         forbid {}", characters)?;
+        }
+        if let Some(nibbles) = self.min_leading_zero_nibbles {
+            writeln!(f, "option:         {} leading zero nibbles", nibbles)?;
+        }
+        if let Some((base, ref prefix)) = self.encoded_target {
+            writeln!(f, "option:         {:?} prefix {}", base, prefix)?;
+        }
+        if self.require_palindrome {
+            writeln!(f, "option:         palindrome")?;
+        }
+        if let Some(n) = self.min_repeat_run {
+            writeln!(f, "option:         repeat run of {}", n)?;
+        }
+        if let Some(monotone) = self.monotone {
+            let direction = match monotone.direction {
+                Direction::Increasing => "increasing",
+                Direction::Decreasing => "decreasing",
+            };
+            writeln!(
+                f,
+                "option:         {} for {} bytes",
+                direction, monotone.len
+            )?;
+        }
+        if let Some(ref chain) = self.chain {
+            writeln!(f, "option:         chain {}", chain)?;
+        }
+        for (format, path) in &self.formats {
+            let path = path.as_deref().unwrap_or("stdout");
+            writeln!(f, "option:         format {:?} to {}", format, path)?;
+        }
+        if self.truncation_offset != ADDRESS_OFFSET {
+            writeln!(
+                f,
+                "option:         truncation offset {} (non-standard)",
+                self.truncation_offset
+            )?;
+        }
+        if self.rotation != 0 {
+            writeln!(f, "option:         rotation {} bytes", self.rotation)?;
+        }
+        if let Some(secs) = self.warmup {
+            writeln!(f, "option:         warmup {}s", secs)?;
+        }
+        if let Some(ref path) = self.metrics_file {
+            writeln!(
+                f,
+                "option:         metrics to {} every {}s",
+                path, self.metrics_interval
+            )?;
+        }
+        if let Some(n) = self.best {
+            writeln!(f, "option:         best {}", n)?;
+        }
+        if let Some(max_bytes) = self.rotate_size {
+            writeln!(f, "option:         rotate at {} bytes", max_bytes)?;
+        }
+        if let Some(n) = self.max_segments {
+            writeln!(f, "option:         max segments {}", n)?;
+        }
+        if self.batch_size != MAX_INCREMENTER {
+            writeln!(f, "option:         batch size {}", self.batch_size)?;
+        }
+        if let Some(n) = self.max_results {
+            writeln!(f, "option:         max results {}", n)?;
+        }
+        if let Some(timeout) = self.timeout {
+            writeln!(f, "option:         timeout {}s", timeout.as_secs())?;
+        }
+        if let Some(seed) = self.seed {
+            writeln!(f, "option:         seed {}", seed)?;
+        }
+        if self.random_bytes != 6 {
+            writeln!(f, "option:         {} random bytes", self.random_bytes)?;
+        }
+        if self.deterministic {
+            writeln!(
+                f,
+                "option:         deterministic (segment 0x{})",
+                hex::encode(self.segment)
+            )?;
+        }
+        if let Some(threads) = self.threads {
+            writeln!(f, "option:         {} threads", threads)?;
+        }
+        if let Some(min_score) = self.min_score {
+            writeln!(f, "option:         min score {}", min_score)?;
+        }
+        if let Some(min_score) = self.announce_min_score {
+            writeln!(f, "option:         announce min score {}", min_score)?;
+        }
+        if !self.simulate.is_empty() {
+            writeln!(f, "option:         simulate {}", self.simulate.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The number of bits of the address constrained by a `0x`-prefixed target
+/// when matched case-sensitively (see `Config::bits_of`). A `?` wildcard
+/// constrains nothing.
+pub fn selectivity(target: &str) -> u32 {
+    let nibbles = &target[2..];
+    let case_sensitive_letters = nibbles.chars().filter(|c| c.is_ascii_alphabetic()).count() as u32;
+
+    fixed_nibbles(target) * 4 + case_sensitive_letters
+}
+
+/// The number of nibbles a `0x`-prefixed target fixes, i.e. those that
+/// aren't `?` wildcards.
+fn fixed_nibbles(target: &str) -> u32 {
+    target[2..].chars().filter(|&c| c != '?').count() as u32
+}
+
+/// The expected number of attempts it takes to find a match for the given
+/// configuration: `2^bits` for the bits constrained by its criteria (see
+/// `Config::target_selectivity`), which combines independent criteria by
+/// multiplying their odds. Where the zero thresholds apply, their odds are
+/// worked out exactly (see `threshold_odds`) rather than by the leading zero
+/// bytes alone.
+pub fn estimate_attempts(config: &Config) -> f64 {
+    let mut bits = config.target_selectivity();
+    let mut odds = 1.0;
+    if config.zero_thresholds {
+        bits -= u32::from(config.leading_zeroes_threshold) * 8;
+        odds = threshold_odds(
+            config.leading_zeroes_threshold,
+            config.total_zeroes_threshold,
+        );
+    } else if config.uses_gpu() && config.target_start_string == "0x" {
+        odds = threshold_odds(
+            config.leading_zeroes_threshold,
+            config.total_zeroes_threshold,
+        );
+    }
+    2f64.powi(bits as i32) / odds
+}
+
+/// The probability that a random address has at least `leading` leading zero
+/// bytes, or at least `total` zero bytes in all.
+fn threshold_odds(leading: u8, total: u8) -> f64 {
+    let p: f64 = 1.0 / 256.0;
+
+    // the probability of at least `k` zero bytes among `n` random bytes
+    let at_least = |k: u8, n: u8| -> f64 {
+        (k..=n)
+            .map(|i| {
+                let ways = (0..i).fold(1.0, |ways, j| ways * f64::from(n - j) / f64::from(j + 1));
+                ways * p.powi(i32::from(i)) * (1.0 - p).powi(i32::from(n - i))
+            })
+            .sum()
+    };
+
+    let leading_odds = p.powi(i32::from(leading));
+    let total_odds = at_least(total, 20);
+    // addresses meeting both, i.e. with enough zero bytes after the leading ones
+    let both_odds = leading_odds * at_least(total.saturating_sub(leading), 20 - leading);
+    leading_odds + total_odds - both_odds
+}
+
+/// Render a number of attempts compactly, e.g. `16.7M`.
+fn format_attempts(attempts: f64) -> String {
+    if attempts >= 1e15 {
+        return format!("{:.1e}", attempts);
+    }
+    let units = [(1e12, "T"), (1e9, "B"), (1e6, "M"), (1e3, "K")];
+    match units.iter().find(|&&(size, _)| attempts >= size) {
+        Some(&(size, unit)) => format!("{:.1}{}", attempts / size, unit),
+        None => format!("{:.0}", attempts),
+    }
+}
+
+/// Render a number of seconds compactly in its two largest units, e.g.
+/// `3m12s` or `2d04h`.
+fn format_duration(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m{:02}s", secs / 60, secs % 60),
+        3600..=86399 => format!("{}h{:02}m", secs / 3600, secs / 60 % 60),
+        86400..=31_535_999 => format!("{}d{:02}h", secs / 86400, secs / 3600 % 24),
+        _ => format!("{:.1} years", secs as f64 / 31_536_000.0),
+    }
+}
+
+/// A custom test that an address must pass to match (see
+/// `Config::with_predicate`).
+pub type Predicate = dyn Fn(&[u8; 20]) -> bool + Send + Sync;
+
+/// A `Predicate` as held by a `Config`.
+#[derive(Clone)]
+pub struct CustomPredicate(pub Arc<Predicate>);
+
+impl fmt::Debug for CustomPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CustomPredicate(..)")
+    }
+}
+
+/// A callback for the progress of a search (see `Config::on_progress`).
+pub type ProgressCallback = dyn Fn(ProgressStats) + Send + Sync;
+
+/// A `ProgressCallback` as held by a `Config`.
+#[derive(Clone)]
+pub struct OnProgress(pub Arc<ProgressCallback>);
+
+impl fmt::Debug for OnProgress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "OnProgress(..)")
+    }
+}
+
+/// The byte order of the nonce in the last six bytes of each salt the CPU
+/// searches. Nonce `n` is the `n`th salt searched under a random segment, so
+/// either order covers the same salts, just in a different sequence.
+///
+/// The GPU lays out its own (eight-byte) nonce little-endian, with the work
+/// item in the low four bytes. `LittleEndian` puts the CPU's nonce in the same
+/// byte order, so that the low bytes of either nonce come first in the salt
+/// and salts found by both can be compared byte for byte.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum NonceOrder {
+    /// The most significant byte first, as the salts written by earlier
+    /// versions (and seeded runs) have it.
+    #[default]
+    BigEndian,
+    /// The least significant byte first, as on the GPU.
+    LittleEndian,
+}
+
+impl NonceOrder {
+    /// The six bytes of the salt holding the given nonce.
+    pub fn nonce_bytes(self, nonce: u64) -> [u8; 6] {
+        let mut bytes = [0; 6];
+        match self {
+            NonceOrder::BigEndian => bytes.copy_from_slice(&nonce.to_be_bytes()[2..]),
+            NonceOrder::LittleEndian => bytes.copy_from_slice(&nonce.to_le_bytes()[..6]),
+        }
+        bytes
+    }
+}
+
+/// The direction in which the bytes of a `MonotoneBytes` address run.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Direction {
+    /// Each byte is greater than the one before it.
+    Increasing,
+    /// Each byte is less than the one before it.
+    Decreasing,
+}
+
+/// Matches addresses whose first `len` bytes are strictly increasing (or
+/// decreasing), e.g. `0x0112233f...`. Only `C(256, len)` of the `256^len`
+/// possible prefixes qualify, so each match takes roughly `len!` attempts
+/// (see `selectivity_bits`): 24 for four bytes, 40,320 for eight.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MonotoneBytes {
+    pub len: usize,
+    pub direction: Direction,
+}
+
+impl MonotoneBytes {
+    /// Whether the first `len` bytes of the address run in `direction`.
+    pub fn matches(&self, address: &[u8; 20]) -> bool {
+        self.run_length(address) >= self.len
+    }
+
+    /// The number of leading bytes of the address that run in `direction`
+    /// (at least one, since a single byte is trivially monotone).
+    pub fn run_length(&self, address: &[u8; 20]) -> usize {
+        1 + address
+            .windows(2)
+            .take_while(|pair| match self.direction {
+                Direction::Increasing => pair[0] < pair[1],
+                Direction::Decreasing => pair[0] > pair[1],
+            })
+            .count()
+    }
+
+    /// The number of bits of the address this constrains: a random address
+    /// matches with a probability of `C(256, len) / 256^len`, i.e. `2^-bits`.
+    pub fn selectivity_bits(&self) -> f64 {
+        (0..self.len)
+            .map(|i| (256.0 / (256 - i) as f64 * (i + 1) as f64).log2())
+            .sum()
+    }
+}
+
+/// A salt that results in an address matching the search criteria, along with
+/// the resultant address in both raw and checksummed form.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FoundAddress {
+    pub salt: [u8; 32],
+    pub address: [u8; 20],
+    pub checksum_address: String,
+    /// The number of attempts made before this address was found, where
+    /// known. Attempts run in parallel, so this is the index of the attempt
+    /// within the search rather than a count of the attempts that had
+    /// completed at the time; matches found close together may be reported
+    /// out of order.
+    pub attempt: Option<u64>,
+    /// The target the address matched, when searching for several.
+    pub target: Option<String>,
+}
+
+impl FoundAddress {
+    /// Compute the checksummed form of an address derived from the given salt.
+    pub fn new(salt: [u8; 32], address_bytes: [u8; 20]) -> Self {
+        Self {
+            salt,
+            address: address_bytes,
+            checksum_address: to_checksum_address(&address_bytes),
+            attempt: None,
+            target: None,
+        }
+    }
+
+    /// The number of leading zero bytes in the address.
+    pub fn leading_zero_bytes(&self) -> u8 {
+        self.address.iter().take_while(|&&b| b == 0).count() as u8
+    }
+
+    /// The number of leading zero nibbles in the address (see
+    /// `leading_zero_nibbles`).
+    pub fn leading_zero_nibbles(&self) -> u32 {
+        leading_zero_nibbles(&self.address)
+    }
+
+    /// The total number of zero bytes in the address.
+    pub fn total_zero_bytes(&self) -> u8 {
+        self.address.iter().filter(|&&b| b == 0).count() as u8
+    }
+
+    /// The score of the address, i.e. its approximate rarity. Every found
+    /// address has already matched the target, so the score only ranks how
+    /// gas-efficient it is: `leading_zero_bytes * LEADING_ZERO_WEIGHT +
+    /// total_zero_bytes`. An address with more leading zero bytes therefore
+    /// always outranks one with fewer, with ties broken by total zero bytes,
+    /// so the same address is scored identically across runs.
+    pub fn score(&self) -> u32 {
+        u32::from(self.leading_zero_bytes()) * LEADING_ZERO_WEIGHT
+            + u32::from(self.total_zero_bytes())
+    }
+
+    /// The calling address that the salt was mined for (its first 20 bytes).
+    pub fn caller(&self) -> [u8; 20] {
+        let mut caller = [0; 20];
+        caller.copy_from_slice(&self.salt[..20]);
+        caller
+    }
+}
+
+/// Formats the result as written to the output file:
+/// `<salt> => <address> => <score>`.
+impl fmt::Display for FoundAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "0x{} => {} => {}",
+            hex::encode(self.salt),
+            self.checksum_address,
+            self.score()
+        )
+    }
+}
+
+/// Given a Config object with a factory address, a caller address, and a
+/// keccak-256 hash of the contract initialization code, search for salts that
+/// will enable the factory contract to deploy a contract to a gas-efficient
+/// address via CREATE2.
+///
+/// The 32-byte salt is constructed as follows:
+///   - the 20-byte calling address (to prevent frontrunning)
+///   - a random 6-byte segment (to prevent collisions with other runs)
+///   - a 6-byte nonce segment (incrementally stepped through during the run)
+///
+/// When a salt that will result in the creation of a gas-efficient contract
+/// address is found, it will be appended to `efficient_addresses.txt` along
+/// with the resultant address and the "value" (i.e. approximate rarity) of the
+/// resultant address.
+///
+/// The search runs until it's bounded by `--max-results`, `--timeout`,
+/// `--max-segments` or `--deterministic`, at which point it flushes its
+/// results and returns a summary of the run. No signal handlers are
+/// installed; to stop it early, use `cpu_with_stop`.
+pub fn cpu(config: Config) -> Result<SearchSummary, Box<dyn Error>> {
+    cpu_with_stop(config, Arc::new(AtomicBool::new(false)))
+}
+
+/// Search on the CPU as `cpu` does, but also stop once `should_stop` is set,
+/// finishing the chunk of work in hand first, so that an application
+/// embedding the search can wire up its own shutdown trigger (or, as the
+/// command line does, use `stop_on_termination`). The flag is checked
+/// between chunks of `Config::check_interval` nonces.
+pub fn cpu_with_stop(
+    config: Config,
+    should_stop: Arc<AtomicBool>,
+) -> Result<SearchSummary, Box<dyn Error>> {
+    let format = line_format(&config);
+
+    // (create if necessary) and open the destination where found salts go
+    let sink = open_sink(&config, format);
+
+    // record the configuration of the run alongside its results
+    if config.run_meta {
+        RunMeta::new(&config).write(RUN_META_FILE)?;
+    }
+
+    // a --format written to stdout takes the place of the usual lines
+    let formatted_stdout = config.formats.iter().any(|(_, path)| path.is_none());
+
+    // with --progress on a terminal, found addresses are shown as part of the
+    // progress display rather than printed as they're found
+    let recent = if config.show_progress && !formatted_stdout && Term::stdout().is_term() {
+        Some(Mutex::new(Vec::new()))
+    } else {
+        None
+    };
+
+    run_cpu(&config, &*sink, &should_stop, recent.as_ref(), &|found| {
+        // display the salt and the address.
+        if formatted_stdout || !config.announces(found) {
+            return;
+        }
+        match recent {
+            Some(ref recent) => recent.lock().unwrap().push(format.line(found)),
+            None => println!("{}", format.line(found)),
+        }
+    })
+}
+
+/// Run the CPU search alongside `other` (the GPU search, with `--hybrid`),
+/// handing each address the CPU finds to `report` before writing it to the
+/// same `sink` that `other` writes to. The CPU search runs until `stop` is set
+/// or `other` returns, whichever comes first, and the result of `other` is
+/// returned once both have finished.
+pub fn search_alongside<T>(
+    config: &Config,
+    sink: &dyn ResultSink,
+    stop: &AtomicBool,
+    report: &(dyn Fn(&FoundAddress) + Sync),
+    other: impl FnOnce() -> T,
+) -> T {
+    thread::scope(|scope| {
+        let cpu =
+            scope.spawn(|| cpu_search(config, sink, stop, &Progress::default(), None, report));
+
+        // once the other search stops (e.g. on an error), stop the CPU as well
+        let result = other();
+        stop.store(true, Ordering::Relaxed);
+        cpu.join().unwrap();
+        result
+    })
+}
+
+/// Search for addresses on the CPU as `cpu` does, but send each address found
+/// down a channel rather than writing it out, so that an application
+/// embedding the search can decide what to do with it. Results files,
+/// `--format` and `--run-meta` don't apply. The search stops once
+/// `should_stop` is set (as with `cpu_with_stop`, no signal handlers are
+/// installed), or once the next address is found after the receiving end has
+/// been dropped.
+pub fn cpu_with_sink(
+    config: Config,
+    sink: Sender<FoundAddress>,
+    should_stop: Arc<AtomicBool>,
+) -> Result<SearchSummary, Box<dyn Error>> {
+    run_cpu(&config, &ChannelSink(sink), &should_stop, None, &|_| {})
+}
+
+/// Search for addresses on the CPU as `cpu` does, yielding each address as
+/// it's found rather than writing it out. The search runs in the background,
+/// holding at most `SEARCH_BUFFER` addresses that haven't been taken yet, and
+/// ends once it finishes by itself (e.g. after `--max-results`). Dropping the
+/// iterator stops it, so e.g. `search(config).take(3)` searches only until
+/// the third address is found. As with `cpu_with_sink`, results files,
+/// `--format` and `--run-meta` don't apply.
+pub fn search(config: Config) -> impl Iterator<Item = FoundAddress> {
+    let (sender, receiver) = mpsc::sync_channel(SEARCH_BUFFER);
+    let stop = Arc::new(AtomicBool::new(false));
+    let worker = {
+        let stop = Arc::clone(&stop);
+        thread::spawn(move || {
+            if let Err(e) = run_cpu(&config, &SyncChannelSink(sender), &stop, None, &|_| {}) {
+                error!("CPU application error: {}", e);
+            }
+        })
+    };
+
+    Search {
+        receiver: Some(receiver),
+        stop,
+        worker: Some(worker),
+    }
+}
+
+/// The addresses found by a search running in the background (see `search`).
+struct Search {
+    receiver: Option<Receiver<FoundAddress>>,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Iterator for Search {
+    type Item = FoundAddress;
+
+    fn next(&mut self) -> Option<FoundAddress> {
+        self.receiver.as_ref()?.recv().ok()
+    }
+}
+
+impl Drop for Search {
+    fn drop(&mut self) {
+        // stop the search, waking it if it's waiting to hand over an address,
+        // and wait for it to finish the chunk of work in hand
+        self.stop.store(true, Ordering::Relaxed);
+        self.receiver.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Run the CPU search until it's stopped (see `cpu_search`), recording found
+/// addresses to the sink and reporting each one as it's found. With
+/// `--progress`, the progress display lists the lines in `recent` (if given)
+/// beneath the progress of the search. Returns a summary of the run once it
+/// has finished.
+fn run_cpu(
+    config: &Config,
+    sink: &dyn ResultSink,
+    stop: &AtomicBool,
+    recent: Option<&Mutex<Vec<String>>>,
+    report: &(dyn Fn(&FoundAddress) + Sync),
+) -> Result<SearchSummary, Box<dyn Error>> {
+    // a dedicated pool caps the number of threads searching
+    let pool = match config.threads {
+        Some(threads) => Some(ThreadPoolBuilder::new().num_threads(threads).build()?),
+        None => None,
+    };
+
+    if let Some(secs) = config.warmup {
+        info!("Warming up for {} seconds...", secs);
+        let rate = in_pool(pool.as_ref(), || {
+            measure_rate(config, Duration::from_secs(secs))
+        });
+        let expected = estimate_attempts(config);
+        info!(
+            "Hashing {:.2} million addresses per second; expected ~{} attempts per match \
+             (~{} at this rate).",
+            rate / 1_000_000.0,
+            format_attempts(expected),
+            format_duration(expected / rate)
+        );
+    }
+
+    // pick up where an earlier run of the same search left off; without
+    // --resume, any state file is left alone
+    let resume_from = if config.resume {
+        match SearchState::read(STATE_FILE) {
+            Ok(Some(state)) => {
+                if !state.matches(config) {
+                    return Err(format!(
+                        "{} is for a different factory, callers or init code hash; remove it to \
+                         start a new search.",
+                        STATE_FILE
+                    )
+                    .into());
+                }
+                info!(
+                    "Resuming from {} after {} attempts.",
+                    STATE_FILE,
+                    state.attempts_before + state.next_nonce
+                );
+                Some(state)
+            }
+            Ok(None) => None,
+            Err(e) => return Err(format!("Couldn't read {}: {}", STATE_FILE, e).into()),
+        }
+    } else {
+        None
+    };
+
+    let progress = Progress::default();
+    let finished = AtomicBool::new(false);
+    let started = Instant::now();
+
+    thread::scope(|scope| {
+        // periodically write the progress of the search for node_exporter
+        if let Some(ref path) = config.metrics_file {
+            let (progress, finished) = (&progress, &finished);
+            let interval = Duration::from_secs(config.metrics_interval);
+            scope.spawn(move || {
+                while !stop.load(Ordering::Relaxed) && !finished.load(Ordering::Relaxed) {
+                    if let Err(e) = progress.write_metrics(path, started.elapsed()) {
+                        warn!("Couldn't write metrics to {}: {}", path, e);
+                    }
+                    thread::sleep(interval);
+                }
+            });
+        }
+
+        // report the progress of the search to the callback and show it,
+        // refreshing about once per second
+        if config.show_progress || config.on_progress.is_some() {
+            let (progress, finished) = (&progress, &finished);
+            scope.spawn(move || {
+                let mut last_shown: Option<Instant> = None;
+                let mut ticks: u64 = 0;
+                while !stop.load(Ordering::Relaxed) && !finished.load(Ordering::Relaxed) {
+                    if last_shown.is_none_or(|shown| shown.elapsed() >= Duration::from_secs(1)) {
+                        last_shown = Some(Instant::now());
+                        let stats = progress.stats(started.elapsed());
+                        if let Some(OnProgress(ref on_progress)) = config.on_progress {
+                            on_progress(stats);
+                        }
+                        let shown = match recent {
+                            _ if !config.show_progress => Ok(()),
+                            Some(recent) => draw_progress(config, progress, stats, recent),
+                            None if ticks.is_multiple_of(PLAIN_PROGRESS_INTERVAL) => {
+                                info!("{}", progress_line(config, stats));
+                                Ok(())
+                            }
+                            None => Ok(()),
+                        };
+                        if let Err(e) = shown {
+                            warn!("Couldn't show progress: {}", e);
+                        }
+                        ticks += 1;
+                    }
+                    thread::sleep(Duration::from_millis(100));
+                }
+            });
+        }
+
+        announce_search(config);
+        in_pool(pool.as_ref(), || {
+            cpu_search(config, sink, stop, &progress, resume_from, report)
+        });
+        finished.store(true, Ordering::Relaxed);
+    });
+
+    // the search finishes after `--max-segments` (or a deterministic search's
+    // only segment), or once asked to terminate
+    let summary = progress.summary(started.elapsed());
+    info!("Search finished after {}.", summary);
+
+    sink.flush()?;
+    Ok(summary)
+}
+
+/// Render a span of time as hours, minutes and seconds (e.g. `1:02:03`).
+fn format_runtime(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// A one-line summary of the progress of a search, logged periodically by
+/// `--progress` when not writing to a terminal.
+fn progress_line(config: &Config, stats: ProgressStats) -> String {
+    format!(
+        "runtime {}, {} attempts, {:.2} million attempts per second, {} found{}",
+        format_runtime(stats.elapsed),
+        stats.attempts,
+        stats.rate / 1_000_000.0,
+        stats.found,
+        expected_time(config, stats.rate)
+    )
+}
+
+/// The expected time to find each match at the given rate, as noted by the
+/// progress output (e.g. `, ~3m12s per match`), or nothing if there's no
+/// meaningful expectation: the rate isn't known yet, or the search records
+/// improvements rather than matches.
+fn expected_time(config: &Config, rate: f64) -> String {
+    if rate == 0.0 || config.closest || config.lex_before.is_some() || config.improvements_only {
+        return String::new();
+    }
+    format!(
+        ", ~{} per match",
+        format_duration(estimate_attempts(config) / rate)
+    )
+}
+
+/// Redraw the terminal with the progress of a search, the salt segments
+/// being searched and as many of the most recently found addresses as fit.
+fn draw_progress(
+    config: &Config,
+    progress: &Progress,
+    stats: ProgressStats,
+    recent: &Mutex<Vec<String>>,
+) -> io::Result<()> {
+    let term = Term::stdout();
+    term.clear_screen()?;
+
+    term.write_line(&format!(
+        "total runtime: {} ({} attempts)",
+        format_runtime(stats.elapsed),
+        stats.attempts
+    ))?;
+    term.write_line(&format!(
+        "rate: {:.2} million attempts per second{}\t\t\ttotal found this run: {}",
+        stats.rate / 1_000_000.0,
+        expected_time(config, stats.rate),
+        stats.found
+    ))?;
+    let segments = progress.segments.lock().unwrap();
+    for segment in segments.iter() {
+        term.write_line(&format!("current search space: {}", segment))?;
+    }
+
+    // display recently found addresses based on terminal height, defaulting
+    // to a height of ten rows
+    let height = match terminal_size() {
+        Some((_, Height(height))) => usize::from(height),
+        None => 10,
+    };
+    let rows = height.saturating_sub(3 + segments.len()).max(1);
+    drop(segments);
+    let recent = recent.lock().unwrap();
+    for line in &recent[recent.len().saturating_sub(rows)..] {
+        term.write_line(line)?;
+    }
+    Ok(())
+}
+
+/// Run `op` on the given thread pool, or on rayon's global pool (with a
+/// thread per core) if there isn't one.
+fn in_pool<R: Send>(pool: Option<&ThreadPool>, op: impl FnOnce() -> R + Send) -> R {
+    match pool {
+        Some(pool) => pool.install(op),
+        None => op(),
+    }
+}
+
+/// A flag that is set once the process is asked to terminate (by SIGTERM, as
+/// sent by e.g. `docker stop` or Kubernetes) or interrupted (by SIGINT, as
+/// sent by Ctrl-C), so that the search can stop and flush its results instead
+/// of being killed mid-write. A second signal, received while the search is
+/// still stopping, exits immediately.
+///
+/// The handlers are process-wide and stay installed, so this is meant to be
+/// called once, by the application that owns the process.
+pub fn stop_on_termination() -> io::Result<Arc<AtomicBool>> {
+    let stop = Arc::new(AtomicBool::new(false));
+    for &signal in [signal_hook::consts::SIGTERM, signal_hook::consts::SIGINT].iter() {
+        // registered first, so that it only sees the flag set by an earlier signal
+        signal_hook::flag::register_conditional_shutdown(signal, 1, Arc::clone(&stop))?;
+        signal_hook::flag::register(signal, Arc::clone(&stop))?;
+    }
+    Ok(stop)
+}
+
+/// Describe the CPU search about to start (and warn about any settings that
+/// change what's written) in the log.
+fn announce_search(config: &Config) {
+    if config.closest {
+        info!(
+            "Searching for addresses closest to {}...",
+            &config.target_start_string
+        );
+    } else if let Some(ref bound) = config.lex_before {
+        info!(
+            "Searching for addresses starting with {} that sort before {}...",
+            &config.target_start_string, bound
+        );
+    } else {
+        info!(
+            "Searching for addresses starting with {}...",
+            config.targets().cloned().collect::<Vec<_>>().join(" or ")
+        );
+        if !config.improvements_only && !config.match_all {
+            info!(
+                "Expected ~{} attempts per match.",
+                format_attempts(estimate_attempts(config))
+            );
+        }
+    }
+
+    // each letter also has to come out in the right case in the checksum
+    let letters_of = |target: &String| {
+        target[2..]
+            .chars()
+            .filter(char::is_ascii_alphabetic)
+            .count()
+    };
+    let letters = config.targets().map(letters_of).max().unwrap_or(0)
+        + config.target_end_string.as_ref().map_or(0, letters_of);
+    if config.case_sensitive && letters > 0 {
+        warn!(
+            "Matching the checksummed case of {} letters makes a match about {} times \
+             rarer; each letter roughly doubles the time to find one.",
+            letters,
+            format_attempts(2f64.powi(letters as i32))
+        );
+    }
+
+    if config.truncation_offset != ADDRESS_OFFSET {
+        warn!(
+            "Taking addresses from byte {} of the hash rather than byte {}. The \
+             addresses found are NOT the addresses CREATE2 will deploy to.",
+            config.truncation_offset, ADDRESS_OFFSET
+        );
+    }
+
+    if config.improvements_only {
+        warn!(
+            "{} matches nearly every address, so only addresses with more \
+             zero bytes than any previous match will be written. Use a longer target \
+             to record every match.",
+            &config.target_start_string
+        );
+    }
+}
+
+/// Run the CPU search, handing each address found to `report` before writing
+/// it to `sink` and counting attempts and matches in `progress`, until `stop`
+/// is set. The search starts from `resume_from` if given, and with `--resume`
+/// checkpoints its progress to `STATE_FILE` as it goes.
+fn cpu_search(
+    config: &Config,
+    sink: &dyn ResultSink,
+    stop: &AtomicBool,
+    progress: &Progress,
+    mut resume_from: Option<SearchState>,
+    report: &(dyn Fn(&FoundAddress) + Sync),
+) {
+    let targets = target_prefixes(config);
+    let closest = config.closest_address();
+    let check_interval = config.check_interval();
+
+    // the best score written so far (only used when recording improvements)
+    let best_score = AtomicI64::new(-1);
+
+    // the smallest distance written so far (only used when mining the closest
+    // address to the target)
+    let best_distance = AtomicU32::new(u32::MAX);
+
+    // the checksummed address that sorts first of those written so far (only
+    // used when searching for addresses that sort before a bound)
+    let lowest = Mutex::new(config.lex_before.clone().unwrap_or_default());
+
+    // the number of results claimed so far (only used with `max_results`);
+    // the search stops once every result has been claimed, or once it has
+    // run for `timeout`
+    let claimed = AtomicU64::new(0);
+    let deadline = config.timeout.map(|timeout| Instant::now() + timeout);
+    let done = || {
+        stop.load(Ordering::Relaxed)
+            || config
+                .max_results
+                .is_some_and(|max| claimed.load(Ordering::Relaxed) >= max)
+            || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    };
+
+    // create a random number generator
+    let mut rng = config.segment_rng();
+
+    // the number of attempts covered by the segments searched so far
+    let mut attempts_before: u64 = 0;
+
+    // begin searching for addresses, through at most `max_segments` segments
+    // (or only the one segment of a deterministic search)
+    let max_segments = if config.deterministic {
+        Some(1)
+    } else {
+        config.max_segments
+    };
+    let mut segments_searched: u64 = 0;
+    if let Some(ref state) = resume_from {
+        attempts_before = state.attempts_before;
+        segments_searched = state.segments_searched;
+
+        // a seeded generator continues with the segments it would have drawn
+        // next, rather than drawing the searched ones again
+        if config.seed.is_some() {
+            for _ in 0..=segments_searched {
+                for caller in &config.calling_addresses {
+                    Segment::random(config, caller, &mut rng);
+                }
+            }
+        }
+    }
+    while !done() && max_segments.is_none_or(|max| segments_searched < max) {
+        // set up a random (or, if deterministic, the given) salt segment for
+        // each calling address, or continue with the ones from an earlier run
+        let (segments, mut next_nonce): (Vec<Segment>, u64) = match resume_from.take() {
+            Some(state) => (
+                config
+                    .calling_addresses
+                    .iter()
+                    .zip(&state.segments)
+                    .map(|(caller, random)| Segment::new(config, caller, random))
+                    .collect(),
+                state.next_nonce,
+            ),
+            None => (
+                config
+                    .calling_addresses
+                    .iter()
+                    .map(|caller| {
+                        if config.deterministic {
+                            Segment::new(config, caller, &config.segment)
+                        } else {
+                            Segment::random(config, caller, &mut rng)
+                        }
+                    })
+                    .collect(),
+                0,
+            ),
+        };
+        let callers = segments.len() as u64;
+        if config.scheme == AddressScheme::Create2 {
+            *progress.segments.lock().unwrap() =
+                segments.iter().map(Segment::search_space).collect();
+        }
+
+        // compute the address for the given (interleaved) nonce and return it
+        // if it should be recorded
+        let check = |i: u64| -> Option<FoundAddress> {
+            let segment = &segments[(i % callers) as usize];
+            let nonce = i / callers;
+
+            if config.lex_before.is_some() {
+                // --lex-before only takes a single target
+                return segment.check_lex_before(config, &targets[0], &lowest, nonce);
+            }
+
+            let closest = match closest {
+                Some(ref closest) => closest,
+                None => return segment.check(config, &targets, nonce),
+            };
+
+            // only record addresses closer than any recorded so far
+            let address = segment.derive(config, nonce);
+            let distance = nibble_distance(&address, closest);
+            if distance < best_distance.load(Ordering::Relaxed)
+                && best_distance.fetch_min(distance, Ordering::Relaxed) > distance
+            {
+                Some(segment.found(nonce, address))
+            } else {
+                None
+            }
+        };
+
+        // with --resume, the batch is searched in rounds, checkpointing the
+        // search after each one
+        while next_nonce < config.batch_size && !done() {
+            let end = if config.resume {
+                (next_nonce + CHECKPOINT_NONCES).min(config.batch_size)
+            } else {
+                config.batch_size
+            };
+
+            // iterate over a batch of 6-byte nonces and compute each address,
+            // interleaving the nonces of each calling address (the stop flag is
+            // only checked once per chunk, keeping it out of the per-nonce path)
+            (next_nonce / check_interval..end.div_ceil(check_interval))
+                .into_par_iter() // parallelization
+                .take_any_while(|_| !done())
+                .flat_map_iter(|chunk| {
+                    let nonces = (chunk * check_interval).max(next_nonce)
+                        ..((chunk + 1) * check_interval).min(end);
+                    progress.count_attempts(nonces.end - nonces.start);
+                    nonces
+                })
+                .filter_map(|i| {
+                    check(i).map(|mut found| {
+                        found.attempt = Some(attempts_before + i);
+                        found
+                    })
+                })
+                .filter(|found| {
+                    if !config.improvements_only {
+                        return true;
+                    }
+                    let score = i64::from(found.score());
+                    best_score.fetch_max(score, Ordering::Relaxed) < score
+                })
+                .for_each(|found| {
+                    // threads finishing their chunks may still find a few more
+                    // results, which are dropped rather than written
+                    if config
+                        .max_results
+                        .is_some_and(|max| claimed.fetch_add(1, Ordering::Relaxed) >= max)
+                    {
+                        return;
+                    }
+
+                    progress.found.fetch_add(1, Ordering::Relaxed);
+                    report(&found);
+
+                    // write the result to the sink, stopping if whatever reads the
+                    // results has gone away
+                    match sink.record(&found) {
+                        Err(ref e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                            stop.store(true, Ordering::Relaxed)
+                        }
+                        result => result.expect("Couldn't write found address to output file."),
+                    }
+                });
+
+            // a round cut short isn't checkpointed, so it's searched again
+            if done() {
+                break;
+            }
+            next_nonce = end;
+
+            if config.resume {
+                let state = SearchState {
+                    factory_address: config.factory_address,
+                    calling_addresses: config.calling_addresses.clone(),
+                    init_code_hash: config.init_code_hash,
+                    segments: segments.iter().map(Segment::random_part).collect(),
+                    next_nonce,
+                    segments_searched,
+                    attempts_before,
+                };
+                if let Err(e) = state.write(STATE_FILE) {
+                    warn!("Couldn't checkpoint the search to {}: {}", STATE_FILE, e);
+                }
+            }
+        }
+
+        attempts_before += config.batch_size;
+        segments_searched += 1;
+    }
+
+    if config.deterministic && !done() {
+        info!(
+            "Searched every salt in segment 0x{}.",
+            hex::encode(config.segment)
+        );
+    }
+}
+
+/// Measure the number of addresses the CPU search derives per second by
+/// deriving addresses (without checking them against the target) for the
+/// given duration.
+pub fn measure_rate(config: &Config, duration: Duration) -> f64 {
+    let segment = Segment::random(config, &config.calling_addresses[0], &mut thread_rng());
+    let check_interval = config.check_interval();
+    let start = Instant::now();
+
+    let attempts: u64 = (0..MAX_INCREMENTER.div_ceil(check_interval))
+        .into_par_iter()
+        .take_any_while(|_| start.elapsed() < duration)
+        .map(|chunk| {
+            let nonces =
+                chunk * check_interval..((chunk + 1) * check_interval).min(MAX_INCREMENTER);
+            let attempts = nonces.end - nonces.start;
+            for nonce in nonces {
+                black_box(segment.derive(config, nonce));
+            }
+            attempts
+        })
+        .sum();
+
+    attempts as f64 / start.elapsed().as_secs_f64()
+}
+
+/// Measure the rate of the CPU search over `attempts` nonces twice: once only
+/// deriving each address, with no matching, checksumming or output (the most
+/// the hashing engine can do on this machine), and once also matching each
+/// address against the target as the search does (but without writing
+/// anything). Returns both rates in attempts per second, in that order; the
+/// difference is the cost of matching.
+pub fn benchmark(config: &Config, attempts: u64) -> (f64, f64) {
+    let targets = target_prefixes(config);
+    let segment = Segment::random(config, &config.calling_addresses[0], &mut thread_rng());
+    let nonces = 0..attempts.min(MAX_INCREMENTER);
+
+    let start = Instant::now();
+    nonces.clone().into_par_iter().for_each(|nonce| {
+        black_box(segment.derive(config, nonce));
+    });
+    let counting_rate = nonces.end as f64 / start.elapsed().as_secs_f64();
+
+    let start = Instant::now();
+    let matches = nonces
+        .clone()
+        .into_par_iter()
+        .filter(|&nonce| {
+            let address = segment.derive(config, nonce);
+            matches_any(&targets, &config.rotated(&address))
+                && config.matches_target_case(&segment.found(nonce, address).checksum_address)
+        })
+        .count();
+    black_box(matches);
+    let search_rate = nonces.end as f64 / start.elapsed().as_secs_f64();
+
+    (counting_rate, search_rate)
+}
+
+/// Measure how many addresses per second can be checksummed, as each match
+/// is before its casing is checked and it's written, by checksumming
+/// `attempts` distinct addresses. Loose targets match often enough for this
+/// to bound the rate of the search, so it's reported by `--bench` alongside
+/// the hash rate.
+pub fn benchmark_checksum(attempts: u64) -> f64 {
+    let start = Instant::now();
+    (0..attempts).into_par_iter().for_each_init(
+        || (Checksummer::default(), String::with_capacity(42)),
+        |(checksummer, out), i| {
+            let mut address = [0; 20];
+            address[12..].copy_from_slice(&i.to_be_bytes());
+            checksummer.checksum_into(&address, out);
+            black_box(&out);
+        },
+    );
+    attempts as f64 / start.elapsed().as_secs_f64()
+}
+
+/// Measure how many CREATE2 preimages per second the given keccak engine
+/// hashes, by hashing `attempts` distinct preimages. `--bench` reports this
+/// for the engine selected on this machine, so that a slow engine can be told
+/// apart from slow matching.
+pub fn benchmark_engine(engine: KeccakEngine, attempts: u64) -> f64 {
+    let start = Instant::now();
+    (0..attempts).into_par_iter().for_each_init(
+        || [0xff; PREIMAGE_LENGTH],
+        |preimage, i| {
+            preimage[45..53].copy_from_slice(&i.to_be_bytes());
+            black_box(engine.hash_preimage(preimage));
+        },
+    );
+    attempts as f64 / start.elapsed().as_secs_f64()
+}
+
+/// Run the CPU search itself (as `cpu` does, with the same threads, batch size
+/// and check interval) for the given duration against a target that never
+/// matches, so that nothing is found or written. Returns the number of
+/// attempts each thread made; their sum over the duration is the rate the
+/// search actually achieves on this machine.
+pub fn benchmark_search(config: &Config, duration: Duration) -> Result<Vec<u64>, Box<dyn Error>> {
+    // a full address as the target matches with odds of 2^-160, and without
+    // the other criteria every address is only checked against it
+    let config = Config {
+        target_start_string: format!("0x{}", "f".repeat(40)),
+        extra_targets: vec![],
+        closest: false,
+        lex_before: None,
+        improvements_only: false,
+        match_all: false,
+        resume: false,
+        deterministic: false,
+        max_segments: None,
+        max_results: None,
+        timeout: None,
+        ..config.clone()
+    };
+
+    // (zero threads is rayon's default of one per core)
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(config.threads.unwrap_or(0))
+        .build()?;
+    let progress = Progress::per_thread(pool.current_num_threads());
+    let stop = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            thread::sleep(duration);
+            stop.store(true, Ordering::Relaxed);
+        });
+        pool.install(|| cpu_search(&config, &TeeSink(vec![]), &stop, &progress, None, &|_| {}));
+    });
+
+    Ok(progress
+        .thread_attempts
+        .iter()
+        .map(|attempts| attempts.load(Ordering::Relaxed))
+        .collect())
+}
+
+/// Search up to `max_attempts` nonces under a single fresh random salt segment
+/// and return the first matching address found, if any (or, when mining the
+/// closest address to the target, the closest one found). Unlike `cpu`,
+/// nothing is written to the output file, which makes this suitable for
+/// previewing the result of a configuration before committing to a long run.
+pub fn search_once(config: &Config, max_attempts: u64) -> Option<FoundAddress> {
+    let targets = target_prefixes(config);
+    let segment = Segment::random(
+        config,
+        &config.calling_addresses[0],
+        &mut config.segment_rng(),
+    );
+    let nonces = 0..max_attempts.min(MAX_INCREMENTER);
+
+    if let Some(closest) = config.closest_address() {
+        return nonces
+            .into_par_iter()
+            .map(|nonce| {
+                let address = segment.derive(config, nonce);
+                (nibble_distance(&address, &closest), nonce, address)
+            })
+            .min()
+            .map(|(_, nonce, address)| segment.found(nonce, address));
+    }
+
+    nonces
+        .into_par_iter()
+        .find_map_any(|nonce| segment.check(config, &targets, nonce))
+}
+
+/// Given the full salt of a candidate solution, derive the resultant address
+/// and return it (along with its checksummed form) if it matches the target.
+/// Both the CPU and GPU searches hand their candidates to this function, so a
+/// candidate is judged the same way whichever device found it.
+pub fn handle_solution(config: &Config, salt: &[u8; 32]) -> Option<FoundAddress> {
+    // hash the full preimage
+    let res = match config.scheme {
+        AddressScheme::Create2 => KeccakEngine::Scalar.hash_preimage(&preimage_for(config, salt)),
+        AddressScheme::Create => create_hash(&config.factory_address, nonce_in_salt(salt)),
+    };
+
+    // truncate the hash (normally its first 12 bytes) to derive address
+    let mut address_bytes: [u8; 20] = Default::default();
+    address_bytes.copy_from_slice(&res[config.truncation_offset..config.truncation_offset + 20]);
+
+    if !matches_any(&target_prefixes(config), &config.rotated(&address_bytes)) {
+        return None;
+    }
+
+    if !ends_with_suffix(config, &address_bytes) || !config.meets_thresholds(&address_bytes) {
+        return None;
+    }
+
+    if let Some(CustomPredicate(ref predicate)) = config.predicate {
+        if !predicate(&address_bytes) {
+            return None;
+        }
+    }
+
+    if let Some(ref monotone) = config.monotone {
+        if !monotone.matches(&address_bytes) {
+            return None;
+        }
+    }
+
+    if config.require_palindrome && !is_palindrome(&address_bytes) {
+        return None;
+    }
+
+    if config
+        .min_repeat_run
+        .is_some_and(|min| longest_repeat_run(&address_bytes) < u32::from(min))
+    {
+        return None;
+    }
+
+    if let Some((base, ref prefix)) = config.encoded_target {
+        if !base.encode(&address_bytes).starts_with(prefix.as_str()) {
+            return None;
+        }
+    }
+
+    let found = FoundAddress::new(*salt, address_bytes);
+    if config
+        .min_score
+        .is_some_and(|min_score| found.score() < min_score)
+    {
+        return None;
+    }
+
+    if !config.matches_target_case(&found.checksum_address)
+        || !config.allows_characters(&found.checksum_address)
+    {
+        return None;
+    }
+
+    // only log matches, once they're confirmed in their checksummed form
+    // (announcing every candidate of a loose target would flood the output)
+    if !config.quiet && !config.improvements_only && config.announces(&found) {
+        info!(
+            "Found address: {} with salt 0x{}",
+            found.checksum_address,
+            hex::encode(found.salt)
+        );
+    }
+
+    // note which of several targets was matched
+    let mut found = found;
+    if !config.extra_targets.is_empty() {
+        found.target = config
+            .matched_target(&found.checksum_address)
+            .map(String::from);
+    }
+
+    Some(found)
+}
+
+/// The exact bytes hashed to derive the address for the given salt:
+/// `0xff ++ factory ++ salt ++ init_code_hash`. Feeding these into any other
+/// keccak-256 implementation should yield the same address (as the last 20
+/// bytes of the hash), which makes this useful for cross-checking a result.
+pub fn preimage_for(config: &Config, salt: &[u8; 32]) -> [u8; PREIMAGE_LENGTH] {
+    let salt = config.salt_scheme.create2_salt(salt);
+    create2_preimage(&config.factory_address, &salt, &config.init_code_hash)
+}
+
+/// The `uint256 saltNonce` (big-endian) to give a factory that takes one in
+/// place of a raw salt for the given found salt, or `None` if the factory
+/// takes the salt itself (see `SaltScheme`).
+pub fn salt_nonce_for(config: &Config, salt: &[u8; 32]) -> Option<[u8; 32]> {
+    match config.salt_scheme {
+        SaltScheme::Raw => None,
+        SaltScheme::Safe { .. } => Some(*salt),
+    }
+}
+
+/// The address that a contract with the given init code hash is deployed to
+/// when `factory` creates it via CREATE2 with the given salt, i.e. the last 20
+/// bytes of `keccak256(0xff ++ factory ++ salt ++ init_code_hash)`. Nothing is
+/// matched or mined here, so this is cheap enough to call in a loop of your
+/// own (e.g. to check the salts a factory will use ahead of time).
+#[inline]
+pub fn create2_address(
+    factory: &[u8; 20],
+    caller_salt: &[u8; 32],
+    init_code_hash: &[u8; 32],
+) -> [u8; 20] {
+    let res =
+        KeccakEngine::Scalar.hash_preimage(&create2_preimage(factory, caller_salt, init_code_hash));
+
+    let mut address_bytes = [0; 20];
+    address_bytes.copy_from_slice(&res[ADDRESS_OFFSET..]);
+    address_bytes
+}
+
+/// The keccak-256 hash of a contract's init code (its creation bytecode,
+/// including any constructor arguments), as CREATE2 takes it.
+pub fn init_code_hash(init_code: &[u8]) -> [u8; 32] {
+    keccak256(init_code)
+}
+
+/// The checksummed address that the given full salt deploys to (see
+/// `create2_address`), for confirming a salt from a results file against the
+/// address recorded alongside it.
+pub fn verify(factory: &[u8; 20], salt: &[u8; 32], init_code_hash: &[u8; 32]) -> String {
+    to_checksum_address(&create2_address(factory, salt, init_code_hash))
+}
+
+/// The calldata for a call to the immutable create2 factory with the given
+/// salt, along with the signature of the function it calls: with the init
+/// code at hand (from `--init-code`), `safeCreate2` to deploy the contract,
+/// and otherwise `findCreate2AddressViaHash` to look up the address it would
+/// be deployed to.
+pub fn factory_calldata(config: &Config, salt: &[u8; 32]) -> (&'static str, Vec<u8>) {
+    let signature = match config.init_code {
+        Some(_) => "safeCreate2(bytes32,bytes)",
+        None => "findCreate2AddressViaHash(bytes32,bytes32)",
+    };
+    let mut calldata = keccak256(signature.as_bytes())[..4].to_vec();
+    calldata.extend_from_slice(salt);
+    match config.init_code {
+        Some(ref code) => {
+            // the offset and length of the code, then the code itself padded
+            // to a whole number of words
+            let mut word = [0; 32];
+            word[24..].copy_from_slice(&0x40u64.to_be_bytes());
+            calldata.extend_from_slice(&word);
+            word[24..].copy_from_slice(&(code.len() as u64).to_be_bytes());
+            calldata.extend_from_slice(&word);
+            calldata.extend_from_slice(code);
+            calldata.resize(calldata.len() + (32 - code.len() % 32) % 32, 0);
+        }
+        None => calldata.extend_from_slice(&config.init_code_hash),
+    }
+    (signature, calldata)
+}
+
+/// The CREATE nonce held in a salt, as a big-endian integer in its last eight
+/// bytes (see `AddressScheme::Create`).
+fn nonce_in_salt(salt: &[u8; 32]) -> u64 {
+    let mut nonce = [0; 8];
+    nonce.copy_from_slice(&salt[24..]);
+    u64::from_be_bytes(nonce)
+}
+
+/// Assemble `0xff ++ factory ++ salt ++ init_code_hash`.
+#[inline]
+fn create2_preimage(
+    factory: &[u8; 20],
+    salt: &[u8; 32],
+    init_code_hash: &[u8; 32],
+) -> [u8; PREIMAGE_LENGTH] {
+    let mut preimage = [0u8; PREIMAGE_LENGTH];
+    preimage[0] = CONTROL_CHARACTER;
+    preimage[1..21].copy_from_slice(factory);
+    preimage[21..53].copy_from_slice(salt);
+    preimage[53..].copy_from_slice(init_code_hash);
+    preimage
+}
+
+/// Derive the address for each of the given salts, in parallel and in the
+/// same order. This is for checking salts generated elsewhere (e.g. by
+/// another process) in bulk: the preimage is only assembled once, with each
+/// salt then copied into it and hashed with the fastest engine available.
+pub fn derive_batch(config: &Config, salts: &[[u8; 32]]) -> Vec<[u8; 20]> {
+    let engine = KeccakEngine::detect();
+    let template = preimage_for(config, &[0; 32]);
+
+    salts
+        .par_iter()
+        .map(|salt| {
+            let res = match config.scheme {
+                AddressScheme::Create2 => {
+                    let mut preimage = template;
+                    preimage[21..53].copy_from_slice(&config.salt_scheme.create2_salt(salt));
+                    engine.hash_preimage(&preimage)
+                }
+                AddressScheme::Create => create_hash(&config.factory_address, nonce_in_salt(salt)),
+            };
+
+            let mut address_bytes = [0; 20];
+            address_bytes
+                .copy_from_slice(&res[config.truncation_offset..config.truncation_offset + 20]);
+            address_bytes
+        })
+        .collect()
+}
+
+/// The header that `--header` starts a results file with: the factory,
+/// callers, init code hash and targets of the search, and when it started (in
+/// seconds since the unix epoch), each on a line commented with `#`.
+pub fn results_header(config: &Config) -> String {
+    let mut header: String = config
+        .describe()
+        .lines()
+        .map(|line| format!("# {}\n", line))
+        .collect();
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    header += &format!("# started:        {}\n", started);
+    header
+}
+
+/// The format of each line written for the given configuration.
+fn line_format(config: &Config) -> LineFormat {
+    LineFormat {
+        salt_decimal: config.salt_decimal,
+        caller: config.calling_addresses.len() > 1,
+        distance_to: config.closest_address(),
+        attempt: config.attempt_number,
+        hmac: config.hmac_key,
+        monotone: config.monotone,
+        chain: CHAIN_SHORT_NAMES
+            .iter()
+            .find(|&&name| Some(name) == config.chain.as_deref())
+            .cloned(),
+    }
+}
+
+/// (Create if necessary and) open the destination where found salts go.
+fn open_sink(config: &Config, format: LineFormat) -> Box<dyn ResultSink> {
+    let (sink, paths): (Box<dyn ResultSink>, Vec<PathBuf>) = if config.split_by_score {
+        let mut sink = ScoreRoutedSink::new(".", format);
+        if config.sync_on_write {
+            sink = sink.sync_on_write();
+        }
+        let paths = (0..=20).map(|score| sink.path_for(score)).collect();
+        (Box::new(sink), paths)
+    } else if let Some(n) = config.best {
+        let path = PathBuf::from("efficient_addresses.txt");
+        let sink = BestSink::open(&path, format, n)
+            .expect("Could not read or create `efficient_addresses.txt` file.");
+        (Box::new(sink), vec![path])
+    } else {
+        let path = PathBuf::from("efficient_addresses.txt");
+        let mut sink = FileSink::open(&path, format)
+            .expect("Could not create or open `efficient_addresses.txt` file.");
+        if let Some(max_bytes) = config.rotate_size {
+            sink = sink.rotate_at(max_bytes);
+        }
+        if config.sync_on_write {
+            sink = sink.sync_on_write();
+        }
+        if config.write_header {
+            sink = sink
+                .header(results_header(config))
+                .expect("Could not write the header of `efficient_addresses.txt`.");
+        }
+        (Box::new(sink), vec![path])
+    };
+
+    // also write every result in each of the additional formats
+    let sink: Box<dyn ResultSink> = if config.formats.is_empty() {
+        sink
+    } else {
+        let mut sinks = vec![sink];
+        for (output_format, path) in &config.formats {
+            let mut format_sink = FormatSink::open(*output_format, path.as_ref(), format)
+                .expect("Could not create or open a --format output file.");
+            if config.sync_on_write {
+                format_sink = format_sink.sync_on_write();
+            }
+            sinks.push(Box::new(format_sink));
+        }
+        Box::new(TeeSink(sinks))
+    };
+
+    // hold results back to write them sorted by score
+    let sink: Box<dyn ResultSink> = if config.sort_results {
+        warn!(
+            "Results are held in memory until the search stops (or {} have been \
+             found), so they will be lost if the process is killed.",
+            SORTED_BUFFER_CAPACITY
+        );
+        Box::new(SortedSink::new(sink, SORTED_BUFFER_CAPACITY))
+    } else {
+        sink
+    };
+
+    // overlapping random segments can come up with the same salt (and so the
+    // same address) twice, which is only written once; every address is kept
+    // in memory for that, so runs recording every address are left alone
+    if config.match_all && !config.dedupe_addresses {
+        return sink;
+    }
+    let mut sink = DedupSink::new(sink);
+
+    // skip the addresses already written by previous runs
+    if config.dedupe_addresses {
+        for path in paths {
+            sink = sink
+                .load(path)
+                .expect("Could not read previously found addresses.");
+        }
+    }
+    Box::new(sink)
+}
+
+/// Count how many of `attempts` addresses, searched under a single fresh
+/// random salt segment as with `search_once`, match each of the given targets.
+/// Every target is checked against the same addresses in a single pass and
+/// nothing is written, which makes this suitable for comparing the difficulty
+/// of candidate targets before committing to one.
+pub fn simulate(config: &Config, targets: &[String], attempts: u64) -> Vec<u64> {
+    let prefixes: Vec<TargetPrefix> = targets.iter().map(|target| prefix_bytes(target)).collect();
+    let segment = Segment::random(config, &config.calling_addresses[0], &mut thread_rng());
+
+    (0..attempts.min(MAX_INCREMENTER))
+        .into_par_iter()
+        .fold(
+            || vec![0u64; targets.len()],
+            |mut counts, nonce| {
+                let address = segment.derive(config, nonce);
+
+                // the checksum is only computed once some prefix matches
+                let mut checksum_address = None;
+                for (i, (target, prefix)) in targets.iter().zip(&prefixes).enumerate() {
+                    if !prefix.matches(&address) {
+                        continue;
+                    }
+
+                    let checksum_address = checksum_address
+                        .get_or_insert_with(|| segment.found(nonce, address).checksum_address);
+                    if config.matches_case(checksum_address, target) {
+                        counts[i] += 1;
+                    }
+                }
+                counts
+            },
+        )
+        .reduce(
+            || vec![0u64; targets.len()],
+            |a, b| a.iter().zip(b).map(|(x, y)| x + y).collect(),
+        )
+}
+
+/// Decode the target prefix (sans `0x`) into the nibbles it should match.
+fn target_start_bytes(config: &Config) -> TargetPrefix {
+    prefix_bytes(&config.target_start_string)
+}
+
+/// Decode every target (see `Config::targets`) into the nibbles it should
+/// match.
+fn target_prefixes(config: &Config) -> Vec<TargetPrefix> {
+    config
+        .targets()
+        .map(|target| prefix_bytes(target))
+        .collect()
+}
+
+/// Whether the given bytes start with the nibbles of any of the targets.
+#[inline]
+fn matches_any(targets: &[TargetPrefix], address: &[u8]) -> bool {
+    targets.iter().any(|target| target.matches(address))
+}
+
+/// Whether an address ends with the nibbles of the suffix (if any), ignoring
+/// case: the case of any letters is checked against the checksummed address
+/// later, in `Config::matches_target_case`.
+fn ends_with_suffix(config: &Config, address: &[u8; 20]) -> bool {
+    let suffix = match config.target_end_string {
+        Some(ref suffix) => &suffix.as_bytes()[2..],
+        None => return true,
+    };
+
+    // compare nibbles from the last one backwards
+    suffix.iter().rev().enumerate().all(|(i, &c)| {
+        let byte = address[19 - i / 2];
+        let nibble = if i % 2 == 0 { byte & 0xf } else { byte >> 4 };
+        (c as char).to_digit(16) == Some(u32::from(nibble))
+    })
+}
+
+/// The leading nibbles that an address must have to match a target. A `?` in
+/// the target matches any nibble, and a target with an odd number of nibbles
+/// ends partway through a byte, so only the high nibble of its last byte is
+/// compared (e.g. `0xabc` matches `0xabc0` through `0xabcf`).
+struct TargetPrefix {
+    /// The value and mask of each byte of the target: an address byte
+    /// matches if it equals the value once masked. The mask leaves out the
+    /// wildcard nibbles and the low nibble of a partial last byte, which are
+    /// zero in the value.
+    pattern: Vec<(u8, u8)>,
+    nibbles: usize,
+}
+
+impl TargetPrefix {
+    /// Whether the given bytes start with the target's nibbles.
+    #[inline]
+    fn matches(&self, address: &[u8]) -> bool {
+        address.len() >= self.pattern.len()
+            && self
+                .pattern
+                .iter()
+                .zip(address)
+                .all(|(&(value, mask), &byte)| byte & mask == value)
+    }
+
+    /// The bytes of the target, with any nibble it doesn't fix set to zero.
+    fn bytes(&self) -> Vec<u8> {
+        self.pattern.iter().map(|&(value, _)| value).collect()
+    }
+}
+
+/// Decode a `0x`-prefixed target, which may contain `?` wildcards, into the
+/// nibbles it should match.
+fn prefix_bytes(target: &str) -> TargetPrefix {
+    let nibbles = &target.as_bytes()[2..];
+    let pattern = nibbles
+        .chunks(2)
+        .map(|chunk| {
+            // a lone final nibble is the high nibble of its byte
+            let (high, low) = (chunk[0], chunk.get(1).copied().unwrap_or(b'?'));
+            let (high_value, high_mask) = nibble_pattern(high);
+            let (low_value, low_mask) = nibble_pattern(low);
+            (high_value << 4 | low_value, high_mask << 4 | low_mask)
+        })
+        .collect();
+
+    TargetPrefix {
+        pattern,
+        nibbles: nibbles.len(),
+    }
+}
+
+/// The value and mask of a single hex character of a target, where `?`
+/// matches anything.
+fn nibble_pattern(c: u8) -> (u8, u8) {
+    match (c as char).to_digit(16) {
+        Some(nibble) => (nibble as u8, 0xf),
+        None => (0, 0),
+    }
+}
+
+/// Where the salt starts in a segment's hash header, after the control
+/// character and the factory address. The header holds the first 26 bytes of
+/// the salt (the caller and the random segment); the nonce completes it.
+const SALT_START: usize = 21;
+
+/// The state shared by every nonce searched under a single random salt
+/// segment: the hash header and a keccak state that has already absorbed it,
+/// along with the keccak engine used to derive each address.
+struct Segment {
+    header: [u8; 47],
+    hash_header: Keccak256,
+    engine: KeccakEngine,
+    scheme: AddressScheme,
+    nonce_order: NonceOrder,
+}
+
+impl Segment {
+    /// Set up a segment for the given caller using a random 6-byte salt segment
+    /// (of which only the last `Config::random_bytes` are random).
+    fn random<R: Rng>(config: &Config, caller: &[u8; 20], rng: &mut R) -> Self {
+        // create a random 6-byte salt using the random number generator
+        let mut salt_random_segment = [0; 6];
+        let random = &mut salt_random_segment[6 - config.random_bytes as usize..];
+        for (byte, random) in random.iter_mut().zip(rng.gen_iter::<u8>()) {
+            *byte = random;
+        }
+        Self::new(config, caller, &salt_random_segment)
+    }
+
+    /// Set up a segment for the given caller using the given 6-byte salt
+    /// segment.
+    fn new(config: &Config, caller: &[u8; 20], salt_random_segment: &[u8; 6]) -> Self {
+        // header: 0xff ++ factory ++ caller ++ salt_random_segment (47 bytes)
+        let mut header_vec: Vec<u8> = vec![CONTROL_CHARACTER];
+        header_vec.extend(config.factory_address.iter());
+        header_vec.extend(caller.iter());
+        header_vec.extend(salt_random_segment.iter());
+
+        // convert the header vector to a fixed-length array
+        let header: [u8; 47] = to_fixed_47(&header_vec);
+
+        // create new hash object and update it with the header
+        let mut hash_header = Keccak256::new();
+        hash_header.update(&header);
+
+        Self {
+            header,
+            hash_header,
+            engine: KeccakEngine::detect(),
+            scheme: config.scheme,
+            nonce_order: config.nonce_order,
+        }
+    }
+
+    /// The random part of the salts searched under this segment.
+    fn random_part(&self) -> [u8; 6] {
+        let mut random = [0; 6];
+        random.copy_from_slice(&self.header[SALT_START + 20..]);
+        random
+    }
+
+    /// The salts searched under this segment, with the bytes of the nonce
+    /// shown as `x`s.
+    fn search_space(&self) -> String {
+        format!(
+            "0x{}{}",
+            hex::encode(&self.header[SALT_START..]),
+            "x".repeat(12)
+        )
+    }
+
+    /// Compute the address for the given nonce.
+    fn derive(&self, config: &Config, nonce: u64) -> [u8; 20] {
+        let res: [u8; 32] = if self.scheme == AddressScheme::Create {
+            // the nonce is the deployer's, so the salt segment plays no part
+            create_hash(&config.factory_address, nonce)
+        } else if config.salt_scheme != SaltScheme::Raw {
+            // the salt searched is hashed into the one CREATE2 uses
+            self.engine
+                .hash_preimage(&preimage_for(config, &self.salt(nonce)))
+        } else if self.engine == KeccakEngine::Scalar {
+            // clone the partially-hashed object
+            let mut hash = self.hash_header.clone();
+
+            // update with body and footer (total: 38 bytes)
+            hash.update(&self.nonce_order.nonce_bytes(nonce));
+            hash.update(&config.init_code_hash);
+
+            // hash the payload and get the result
+            hash.finalize()
+        } else {
+            // assemble the full preimage and hash it in a single block
+            let mut preimage = [0u8; PREIMAGE_LENGTH];
+            preimage[..47].copy_from_slice(&self.header);
+            preimage[47..53].copy_from_slice(&self.nonce_order.nonce_bytes(nonce));
+            preimage[53..].copy_from_slice(&config.init_code_hash);
+            self.engine.hash_preimage(&preimage)
+        };
+
+        // truncate the hash (normally its first 12 bytes) to derive address
+        let mut address_bytes: [u8; 20] = Default::default();
+        address_bytes
+            .copy_from_slice(&res[config.truncation_offset..config.truncation_offset + 20]);
+        address_bytes
+    }
+
+    /// Compute the address for the given nonce and return it if it matches
+    /// the target.
+    fn check(&self, config: &Config, targets: &[TargetPrefix], nonce: u64) -> Option<FoundAddress> {
+        // most candidates are rejected here, without assembling the salt
+        let address_bytes = self.derive(config, nonce);
+        if !matches_any(targets, &config.rotated(&address_bytes))
+            || !ends_with_suffix(config, &address_bytes)
+            || !config.meets_thresholds(&address_bytes)
+        {
+            return None;
+        }
+
+        handle_solution(config, &self.salt(nonce))
+    }
+
+    /// Compute the address for the given nonce and return it if it matches the
+    /// target and its checksummed form sorts before `lowest`, which it then
+    /// replaces.
+    fn check_lex_before(
+        &self,
+        config: &Config,
+        target_start: &TargetPrefix,
+        lowest: &Mutex<String>,
+        nonce: u64,
+    ) -> Option<FoundAddress> {
+        let address_bytes = self.derive(config, nonce);
+
+        // rule out most candidates before computing the checksum
+        let bound = config.lex_before.as_ref()?;
+        if !target_start.matches(&address_bytes) || !could_sort_before(&address_bytes, bound) {
+            return None;
+        }
+
+        let found = self.found(nonce, address_bytes);
+        if !config.matches_target_case(&found.checksum_address)
+            || !config.allows_characters(&found.checksum_address)
+        {
+            return None;
+        }
+
+        let mut lowest = lowest.lock().unwrap();
+        if found.checksum_address >= *lowest {
+            return None;
+        }
+        *lowest = found.checksum_address.clone();
+
+        Some(found)
+    }
+
+    /// Assemble the full salt and checksummed address for an address derived
+    /// from the given nonce.
+    fn found(&self, nonce: u64, address_bytes: [u8; 20]) -> FoundAddress {
+        FoundAddress::new(self.salt(nonce), address_bytes)
+    }
+
+    /// The full salt used to create the address for the given nonce (or, for
+    /// CREATE, just the nonce).
+    fn salt(&self, nonce: u64) -> [u8; 32] {
+        let mut salt: [u8; 32] = [0; 32];
+        if self.scheme == AddressScheme::Create {
+            salt[24..].copy_from_slice(&nonce.to_be_bytes());
+            return salt;
+        }
+
+        // caller ++ random segment ++ nonce
+        salt[..26].copy_from_slice(&self.header[SALT_START..]);
+        salt[26..].copy_from_slice(&self.nonce_order.nonce_bytes(nonce));
+        salt
+    }
+}
+
+/// Whether some checksum casing of the address could sort before `bound`. The
+/// casing with every letter uppercase sorts first (`0-9` < `A-F` < `a-f`), so
+/// an address can only sort before the bound if it does so in that casing.
+fn could_sort_before(address: &[u8; 20], bound: &str) -> bool {
+    const UPPER_HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut lowest = [0u8; 42];
+    lowest[..2].copy_from_slice(b"0x");
+    for (i, byte) in address.iter().enumerate() {
+        lowest[2 + 2 * i] = UPPER_HEX[(byte >> 4) as usize];
+        lowest[3 + 2 * i] = UPPER_HEX[(byte & 0x0f) as usize];
+    }
+
+    lowest[..] < *bound.as_bytes()
+}
+
+/// The EIP-55 checksummed form of an address, `0x`-prefixed: the same form
+/// that found addresses are written in. To checksum many addresses, reuse a
+/// `Checksummer` instead.
+pub fn to_checksum_address(address_bytes: &[u8; 20]) -> String {
+    Checksummer::default().checksum(address_bytes)
+}
+
+/// The number of nibbles (hex characters) that differ between two addresses.
+pub fn nibble_distance(a: &[u8; 20], b: &[u8; 20]) -> u32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| {
+            let diff = x ^ y;
+            u32::from(diff & 0xf0 != 0) + u32::from(diff & 0x0f != 0)
+        })
+        .sum()
+}
+
+/// The number of leading zero nibbles (hex characters) in an address, so that
+/// e.g. `0x0a...` has one even though it has no leading zero bytes.
+pub fn leading_zero_nibbles(address: &[u8; 20]) -> u32 {
+    let zero_bytes = address.iter().take_while(|&&b| b == 0).count();
+    match address.get(zero_bytes) {
+        Some(&b) if b < 0x10 => zero_bytes as u32 * 2 + 1,
+        _ => zero_bytes as u32 * 2,
+    }
+}
+
+/// The 40 nibbles (hex characters) of an address, from first to last.
+fn nibbles_of(address: &[u8; 20]) -> [u8; 40] {
+    let mut nibbles = [0; 40];
+    for (i, &byte) in address.iter().enumerate() {
+        nibbles[i * 2] = byte >> 4;
+        nibbles[i * 2 + 1] = byte & 0xf;
+    }
+    nibbles
+}
+
+/// Whether an address reads the same backwards, nibble by nibble (e.g.
+/// `0x1234...4321`), regardless of the case of its checksummed form.
+pub fn is_palindrome(address: &[u8; 20]) -> bool {
+    let nibbles = nibbles_of(address);
+    nibbles.iter().eq(nibbles.iter().rev())
+}
+
+/// The length of the longest run of identical nibbles anywhere in an address,
+/// so that e.g. `0x12aaaaaa3...` has a run of at least six.
+pub fn longest_repeat_run(address: &[u8; 20]) -> u32 {
+    nibbles_of(address)
+        .chunk_by(|a, b| a == b)
+        .map(|run| run.len() as u32)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Decode a hex argument (with or without its `0x` prefix) that must be
+/// `expected` bytes long, naming `field` if it isn't.
+fn decode_hex_arg(arg: &str, field: &'static str, expected: usize) -> Result<Vec<u8>, ConfigError> {
+    let bytes = match Vec::from_hex(arg.strip_prefix("0x").unwrap_or(arg)) {
+        Ok(bytes) => bytes,
+        Err(_) => return Err(ConfigError::NotHex { field }),
+    };
+
+    if bytes.len() != expected {
+        return Err(ConfigError::InvalidHexLength {
+            field,
+            expected,
+            got: bytes.len(),
+        });
+    }
+    Ok(bytes)
+}
+
+/// Write a file by writing to a temporary file alongside it and renaming that
+/// into place, so that the file is never observed partially written.
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path)
+}
+
+/// Convert a properly-sized vector to a fixed array of 20 bytes.
+fn to_fixed_20(bytes: std::vec::Vec<u8>) -> [u8; 20] {
+    let mut array = [0; 20];
+    let bytes = &bytes[..array.len()];
+    array.copy_from_slice(bytes);
+    array
+}
+
+/// Convert a properly-sized vector to a fixed array of 32 bytes.
+fn to_fixed_32(bytes: std::vec::Vec<u8>) -> [u8; 32] {
+    let mut array = [0; 32];
+    let bytes = &bytes[..array.len()];
+    array.copy_from_slice(bytes);
+    array
+}
+
+/// Convert a properly-sized vector to a fixed array of 47 bytes.
+fn to_fixed_47(bytes: &[u8]) -> [u8; 47] {
+    let mut array = [0; 47];
+    let bytes = &bytes[..array.len()];
+    array.copy_from_slice(bytes);
+    array
 }
@@ -1,25 +1,288 @@
 extern crate create2crunch;
+extern crate env_logger;
+extern crate hex;
+#[macro_use]
+extern crate log;
 
 use std::env;
+use std::fs;
 use std::process;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
 
-use create2crunch::Config;
+use create2crunch::{AddressScheme, Config, HmacKey, HMAC_KEY_VAR};
 
 fn main() {
+    // log at the info level unless RUST_LOG says otherwise
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    if env::args().nth(1).as_deref() == Some("--list-devices") {
+        list_devices();
+        return;
+    }
+
+    if env::args().nth(1).as_deref() == Some("--verify") {
+        verify(env::args().skip(2).collect());
+        return;
+    }
+
+    if env::args().nth(1).as_deref() == Some("--verify-hmac") {
+        verify_hmac(env::args().nth(2));
+        return;
+    }
+
     let config = Config::new(env::args()).unwrap_or_else(|err| {
         eprintln!("Problem parsing arguments: {}", err);
         process::exit(1);
     });
 
-    if config.gpu_device == 255 {
-        if let Err(e) = create2crunch::cpu(config) {
-            eprintln!("CPU application error: {}", e);
-            process::exit(1);
+    // show what is about to be searched before a long run starts
+    if !config.dry_parse && !config.bench && config.simulate.is_empty() {
+        for line in config.describe().lines() {
+            info!("{}", line);
         }
+    }
+    for warning in config.warnings() {
+        warn!("{}", warning);
+    }
+
+    if config.dry_parse {
+        print!("{}", config);
+    } else if config.bench {
+        let (counting_rate, search_rate) =
+            create2crunch::benchmark(&config, create2crunch::BENCH_ATTEMPTS);
+        println!(
+            "counting only: {:.2} million attempts per second",
+            counting_rate / 1_000_000.0
+        );
+        println!(
+            "full search:   {:.2} million attempts per second ({:.1}% spent matching)",
+            search_rate / 1_000_000.0,
+            (1.0 - search_rate / counting_rate) * 100.0
+        );
+        let engine = create2crunch::KeccakEngine::detect();
+        println!(
+            "hashing:       {:.2} million preimages per second ({:?} engine)",
+            create2crunch::benchmark_engine(engine, create2crunch::BENCH_ATTEMPTS) / 1_000_000.0,
+            engine
+        );
+        println!(
+            "checksumming:  {:.2} million addresses per second",
+            create2crunch::benchmark_checksum(create2crunch::BENCH_ATTEMPTS) / 1_000_000.0
+        );
+        bench_search(&config);
+    } else if !config.simulate.is_empty() {
+        let attempts = create2crunch::SIMULATE_ATTEMPTS;
+        let counts = create2crunch::simulate(&config, &config.simulate, attempts);
+        for (target, count) in config.simulate.iter().zip(counts) {
+            let expected = attempts as f64 / 2f64.powi(config.bits_of(target) as i32);
+            println!(
+                "{}: {} matches in {} attempts (expected {:.1})",
+                target, count, attempts, expected
+            );
+        }
+    } else if config.preview {
+        match create2crunch::search_once(&config, create2crunch::PREVIEW_MAX_ATTEMPTS) {
+            Some(found) => {
+                println!("salt:     0x{}", hex::encode(found.salt));
+                if config.salt_decimal {
+                    println!("          {}", create2crunch::salt_to_decimal(&found.salt));
+                }
+                println!("address:  {}", found.checksum_address);
+                println!(
+                    "score:    {} ({} leading, {} total zero bytes; {} leading zero nibbles)",
+                    found.score(),
+                    found.leading_zero_bytes(),
+                    found.total_zero_bytes(),
+                    found.leading_zero_nibbles()
+                );
+                if let Some(salt_nonce) = create2crunch::salt_nonce_for(&config, &found.salt) {
+                    println!("nonce:    {}", create2crunch::salt_to_decimal(&salt_nonce));
+                } else if config.scheme == AddressScheme::Create2 {
+                    let (signature, calldata) =
+                        create2crunch::factory_calldata(&config, &found.salt);
+                    println!("calldata: 0x{} ({})", hex::encode(calldata), signature);
+                }
+            }
+            None => {
+                eprintln!(
+                    "No match found within {} attempts; the target may be too strict to preview.",
+                    create2crunch::PREVIEW_MAX_ATTEMPTS
+                );
+                process::exit(1);
+            }
+        }
+    } else if config.uses_gpu() {
+        gpu(config, stop_on_termination());
+    } else if let Err(e) = create2crunch::cpu_with_stop(config, stop_on_termination()) {
+        eprintln!("CPU application error: {}", e);
+        process::exit(1);
+    }
+}
+
+/// Stop the search once the process is asked to terminate or interrupted.
+fn stop_on_termination() -> Arc<AtomicBool> {
+    create2crunch::stop_on_termination().unwrap_or_else(|err| {
+        eprintln!("Problem handling termination signals: {}", err);
+        process::exit(1);
+    })
+}
+
+#[cfg(feature = "gpu")]
+fn gpu(config: Config, stop: Arc<AtomicBool>) {
+    let result = if config.hybrid {
+        create2crunch::hybrid(config, &stop)
     } else {
-        // if let Err(e) = create2crunch::gpu(config) {
-        //     eprintln!("GPU application error: {}", e);
-        //     process::exit(1);
-        // }
+        create2crunch::gpu(config, &stop)
+    };
+
+    if let Err(e) = result {
+        eprintln!("GPU application error: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(not(feature = "gpu"))]
+fn gpu(_config: Config, _stop: Arc<AtomicBool>) {
+    eprintln!("Searching on a GPU requires building with `--features gpu`.");
+    process::exit(1);
+}
+
+/// Run the real search for `BENCH_SEARCH_SECONDS` and print its rate, in total
+/// and on each thread.
+fn bench_search(config: &Config) {
+    let secs = create2crunch::BENCH_SEARCH_SECONDS;
+    let thread_attempts = create2crunch::benchmark_search(config, Duration::from_secs(secs))
+        .unwrap_or_else(|err| {
+            eprintln!("Problem running the search: {}", err);
+            process::exit(1);
+        });
+
+    let rate = |attempts: u64| attempts as f64 / secs as f64 / 1_000_000.0;
+    println!(
+        "real search:   {:.2} million attempts per second over {}s on {} threads",
+        rate(thread_attempts.iter().sum()),
+        secs,
+        thread_attempts.len()
+    );
+    for (i, &attempts) in thread_attempts.iter().enumerate() {
+        println!(
+            "  thread {:<3}   {:.2} million attempts per second",
+            i,
+            rate(attempts)
+        );
+    }
+}
+
+/// Print the checksummed address that a factory deploys to with a full salt
+/// and init code hash, as given on the command line.
+fn verify(args: Vec<String>) {
+    if args.len() != 3 {
+        eprintln!(
+            "Problem parsing arguments: --verify requires a factory address, a salt and an \
+             init code hash."
+        );
+        process::exit(1);
     }
+
+    let mut factory = [0; 20];
+    let mut salt = [0; 32];
+    let mut init_code_hash = [0; 32];
+    decode_arg("factory address", &args[0], &mut factory);
+    decode_arg("salt", &args[1], &mut salt);
+    decode_arg("init code hash", &args[2], &mut init_code_hash);
+
+    println!(
+        "{}",
+        create2crunch::verify(&factory, &salt, &init_code_hash)
+    );
+}
+
+/// Decode a hex argument (with or without `0x`) of exactly the length of
+/// `out` into it, exiting with an error if it can't be.
+fn decode_arg(name: &str, arg: &str, out: &mut [u8]) {
+    match hex::decode(arg.trim_start_matches("0x")) {
+        Ok(ref bytes) if bytes.len() == out.len() => out.copy_from_slice(bytes),
+        _ => {
+            eprintln!(
+                "Problem parsing arguments: the {} must be {} hex-encoded bytes.",
+                name,
+                out.len()
+            );
+            process::exit(1);
+        }
+    }
+}
+
+/// Check the HMAC of every line of a results file written with `--hmac`,
+/// printing each line that fails and exiting with an error if any do.
+fn verify_hmac(path: Option<String>) {
+    let path = path.unwrap_or_else(|| {
+        eprintln!("Problem parsing arguments: --verify-hmac requires a path.");
+        process::exit(1);
+    });
+    let key = match env::var(HMAC_KEY_VAR) {
+        Ok(ref key) if !key.is_empty() => HmacKey::new(key.as_bytes()),
+        _ => {
+            eprintln!(
+                "Verifying requires the key in the {} variable.",
+                HMAC_KEY_VAR
+            );
+            process::exit(1);
+        }
+    };
+    let contents = fs::read_to_string(&path).unwrap_or_else(|err| {
+        eprintln!("Problem reading {}: {}", path, err);
+        process::exit(1);
+    });
+
+    // skip the blank lines and any header written with --header
+    let lines: Vec<&str> = contents
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    let mut failed = 0;
+    for line in &lines {
+        if !key.verify_line(line) {
+            println!("FAILED: {}", line);
+            failed += 1;
+        }
+    }
+
+    println!("{} of {} lines verified", lines.len() - failed, lines.len());
+    if failed > 0 {
+        process::exit(1);
+    }
+}
+
+/// Print each available OpenCL device along with the index that selects it.
+#[cfg(feature = "gpu")]
+fn list_devices() {
+    let devices = create2crunch::list_devices().unwrap_or_else(|err| {
+        eprintln!("Problem listing OpenCL devices: {}", err);
+        process::exit(1);
+    });
+
+    if devices.is_empty() {
+        eprintln!("No OpenCL devices found. Is an OpenCL driver installed?");
+        process::exit(1);
+    }
+
+    for device in devices {
+        if device.default_platform {
+            println!("{}: {} ({})", device.index, device.name, device.platform);
+        } else {
+            println!(
+                "-: {} ({}, not the default platform)",
+                device.name, device.platform
+            );
+        }
+    }
+}
+
+#[cfg(not(feature = "gpu"))]
+fn list_devices() {
+    eprintln!("Listing OpenCL devices requires building with `--features gpu`.");
+    process::exit(1);
 }
@@ -0,0 +1,57 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{write_atomically, Config};
+
+/// The name of the sidecar file written alongside the results by `--run-meta`.
+pub const RUN_META_FILE: &str = "run_meta.json";
+
+/// A description of a search, written alongside its results so that a results
+/// file is self-describing: anyone can re-verify the salts it contains or
+/// reproduce the run from the recorded configuration.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunMeta {
+    /// The full configuration of the run.
+    pub config: Config,
+    /// The search engine in use (`cpu`, `gpu`, or `hybrid`).
+    pub engine: String,
+    /// When the run started, in seconds since the unix epoch.
+    pub started_at: u64,
+}
+
+impl RunMeta {
+    /// Describe a run of the given configuration that is starting now.
+    pub fn new(config: &Config) -> Self {
+        let engine = if config.hybrid {
+            "hybrid"
+        } else if config.uses_gpu() {
+            "gpu"
+        } else {
+            "cpu"
+        };
+
+        Self {
+            config: config.clone(),
+            engine: engine.to_string(),
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        }
+    }
+
+    /// Write the description to the file at `path`. The file is replaced
+    /// atomically, so readers never observe a partially written file.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        write_atomically(path.as_ref(), json.as_bytes())
+    }
+
+    /// Read a description previously written with `write`.
+    pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt::{self, Write};
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::write_atomically;
+
+/// The default number of seconds between writes of the metrics file.
+pub const METRICS_INTERVAL: u64 = 15;
+
+/// What a finished search got through: the number of addresses derived and
+/// found (across every thread), and how long it ran for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchSummary {
+    pub attempts: u64,
+    pub found: u64,
+    pub elapsed: Duration,
+}
+
+impl SearchSummary {
+    /// The average number of addresses derived per second.
+    pub fn rate(&self) -> f64 {
+        self.attempts as f64 / self.elapsed.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+}
+
+impl fmt::Display for SearchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} attempts in {:.1} seconds; {} addresses found",
+            self.attempts,
+            self.elapsed.as_secs_f64(),
+            self.found
+        )
+    }
+}
+
+/// A snapshot of the progress of a running search, as handed to the callback
+/// set with `Config::on_progress` about once per second.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProgressStats {
+    /// The number of addresses derived so far.
+    pub attempts: u64,
+    /// The average number of addresses derived per second so far.
+    pub rate: f64,
+    /// The number of matching addresses found so far.
+    pub found: u64,
+    /// The time spent searching so far.
+    pub elapsed: Duration,
+}
+
+/// Counters tracking the progress of a search. The search updates them as it
+/// goes, so they can be read from another thread at any time.
+#[derive(Debug, Default)]
+pub struct Progress {
+    /// The number of addresses derived so far.
+    pub attempts: AtomicU64,
+    /// The number of matching addresses found so far.
+    pub found: AtomicU64,
+    /// The salts of the random segments being searched, one per caller, with
+    /// the bytes of the nonce shown as `x`s.
+    pub segments: Mutex<Vec<String>>,
+    /// The number of addresses derived by each thread of the search, by its
+    /// index in the thread pool (if counted; see `per_thread`).
+    pub thread_attempts: Vec<AtomicU64>,
+}
+
+impl Progress {
+    /// Counters that also count the addresses derived by each of the given
+    /// number of threads.
+    pub fn per_thread(threads: usize) -> Self {
+        Progress {
+            thread_attempts: (0..threads).map(|_| AtomicU64::new(0)).collect(),
+            ..Progress::default()
+        }
+    }
+
+    /// Count a number of addresses derived by the current thread.
+    pub fn count_attempts(&self, attempts: u64) {
+        self.attempts.fetch_add(attempts, Ordering::Relaxed);
+        let thread = ::rayon::current_thread_index().and_then(|i| self.thread_attempts.get(i));
+        if let Some(thread) = thread {
+            thread.fetch_add(attempts, Ordering::Relaxed);
+        }
+    }
+
+    /// The counters as they stand, along with the average rate over the
+    /// given time spent searching.
+    pub fn stats(&self, elapsed: Duration) -> ProgressStats {
+        let attempts = self.attempts.load(Ordering::Relaxed);
+        ProgressStats {
+            attempts,
+            rate: attempts as f64 / elapsed.as_secs_f64().max(f64::MIN_POSITIVE),
+            found: self.found.load(Ordering::Relaxed),
+            elapsed,
+        }
+    }
+
+    /// The counters as they stand, for a search that has run for the given
+    /// time.
+    pub fn summary(&self, elapsed: Duration) -> SearchSummary {
+        SearchSummary {
+            attempts: self.attempts.load(Ordering::Relaxed),
+            found: self.found.load(Ordering::Relaxed),
+            elapsed,
+        }
+    }
+
+    /// Render the counters, along with the average rate over the given time
+    /// spent searching, in the Prometheus text exposition format.
+    pub fn exposition(&self, elapsed: Duration) -> String {
+        let attempts = self.attempts.load(Ordering::Relaxed);
+        let found = self.found.load(Ordering::Relaxed);
+        let rate = attempts as f64 / elapsed.as_secs_f64().max(f64::MIN_POSITIVE);
+
+        let metrics = [
+            (
+                "create2crunch_attempts_total",
+                "counter",
+                "Addresses derived since the search started.",
+                attempts as f64,
+            ),
+            (
+                "create2crunch_found_total",
+                "counter",
+                "Matching addresses found since the search started.",
+                found as f64,
+            ),
+            (
+                "create2crunch_attempts_per_second",
+                "gauge",
+                "Average rate at which addresses have been derived.",
+                rate,
+            ),
+        ];
+
+        let mut text = String::new();
+        for &(name, kind, help, value) in metrics.iter() {
+            writeln!(text, "# HELP {} {}", name, help).unwrap();
+            writeln!(text, "# TYPE {} {}", name, kind).unwrap();
+            writeln!(text, "{} {}", name, value).unwrap();
+        }
+        text
+    }
+
+    /// Write the counters to the file at `path` (see `exposition`). The file
+    /// is replaced atomically, so node_exporter's textfile collector never
+    /// reads a partially written file.
+    pub fn write_metrics<P: AsRef<Path>>(&self, path: P, elapsed: Duration) -> io::Result<()> {
+        write_atomically(path.as_ref(), self.exposition(elapsed).as_bytes())
+    }
+}
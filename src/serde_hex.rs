@@ -0,0 +1,67 @@
+//! Serialize fixed-size byte arrays as `0x`-prefixed hex strings, the way
+//! addresses and hashes are written everywhere else, rather than as arrays of
+//! numbers.
+
+use hex::FromHex;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// Serialize a byte array as a `0x`-prefixed hex string.
+pub fn serialize<S: Serializer, const N: usize>(
+    bytes: &[u8; N],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("0x{}", hex::encode(bytes)))
+}
+
+/// Deserialize a byte array from a (optionally `0x`-prefixed) hex string.
+pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<[u8; N], D::Error> {
+    let string = String::deserialize(deserializer)?;
+    decode(&string).map_err(D::Error::custom)
+}
+
+/// Decode a hex string into a byte array of exactly `N` bytes.
+fn decode<const N: usize>(string: &str) -> Result<[u8; N], String> {
+    let digits = string.trim_start_matches("0x");
+    let bytes: Vec<u8> =
+        Vec::from_hex(digits).map_err(|_| format!("could not decode hex string {}", string))?;
+
+    if bytes.len() != N {
+        return Err(format!("expected {} bytes, got {}", N, bytes.len()));
+    }
+
+    let mut array = [0; N];
+    array.copy_from_slice(&bytes);
+    Ok(array)
+}
+
+/// The same encoding applied to each element of a list of byte arrays.
+pub mod list {
+    use serde::de::Error;
+    use serde::ser::SerializeSeq;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// Serialize a list of byte arrays as a list of `0x`-prefixed hex strings.
+    pub fn serialize<S: Serializer, const N: usize>(
+        list: &[[u8; N]],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(list.len()))?;
+        for bytes in list {
+            seq.serialize_element(&format!("0x{}", hex::encode(bytes)))?;
+        }
+        seq.end()
+    }
+
+    /// Deserialize a list of byte arrays from a list of hex strings.
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<Vec<[u8; N]>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|string| super::decode(string).map_err(D::Error::custom))
+            .collect()
+    }
+}
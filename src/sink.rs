@@ -0,0 +1,711 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Sender, SyncSender};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use fs2::FileExt;
+use hex::FromHex;
+
+use super::{nibble_distance, write_atomically, FoundAddress, HmacKey, MonotoneBytes};
+
+/// A destination for found addresses. Sinks are shared across all of the
+/// worker threads performing the search, so recording must be thread-safe.
+pub trait ResultSink: Sync {
+    /// Record a single found address.
+    fn record(&self, found: &FoundAddress) -> io::Result<()>;
+
+    /// Write out any addresses that have been recorded but not yet written.
+    /// Called once the search stops.
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<S: ResultSink + ?Sized> ResultSink for Box<S> {
+    fn record(&self, found: &FoundAddress) -> io::Result<()> {
+        (**self).record(found)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        (**self).flush()
+    }
+}
+
+/// Optional columns to include when writing a found address as a line of
+/// output. The default is the plain `<salt> => <address> => <score>` format.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LineFormat {
+    /// Also write the salt as a `uint256` decimal literal.
+    pub salt_decimal: bool,
+    /// Also write the calling address the salt was mined for, which is useful
+    /// when rotating between several callers.
+    pub caller: bool,
+    /// Also write the number of nibbles by which the address differs from
+    /// this target address.
+    pub distance_to: Option<[u8; 20]>,
+    /// Also write the number of attempts made before the address was found.
+    pub attempt: bool,
+    /// Authenticate each line by appending its HMAC under this key.
+    pub hmac: Option<HmacKey>,
+    /// Also write the length of the run of increasing (or decreasing) bytes
+    /// at the start of the address.
+    pub monotone: Option<MonotoneBytes>,
+    /// Write the address as an EIP-3770 chain-specific address for the chain
+    /// with this short name (e.g. `eth:0x...`).
+    pub chain: Option<&'static str>,
+}
+
+impl LineFormat {
+    /// Format a found address as a single line (without the trailing newline).
+    pub fn line(&self, found: &FoundAddress) -> String {
+        let mut line = match self.chain {
+            Some(chain) => format!(
+                "0x{} => {}:{} => {}",
+                hex::encode(found.salt),
+                chain,
+                found.checksum_address,
+                found.score()
+            ),
+            None => found.to_string(),
+        };
+        if self.caller {
+            line.push_str(&format!(" (caller 0x{})", hex::encode(found.caller())));
+        }
+        if let Some(ref target) = found.target {
+            line.push_str(&format!(" (target {})", target));
+        }
+        if let Some(ref target) = self.distance_to {
+            line.push_str(&format!(
+                " (distance {})",
+                nibble_distance(&found.address, target)
+            ));
+        }
+        if self.salt_decimal {
+            line.push_str(&format!(" (salt {})", salt_to_decimal(&found.salt)));
+        }
+        if let (true, Some(attempt)) = (self.attempt, found.attempt) {
+            line.push_str(&format!(" (attempt {})", attempt));
+        }
+        if let Some(ref monotone) = self.monotone {
+            line.push_str(&format!(" (run {})", monotone.run_length(&found.address)));
+        }
+        if let Some(ref key) = self.hmac {
+            line = key.sign_line(&line);
+        }
+        line
+    }
+}
+
+/// The EIP-3770 short names of the chains that addresses can be written for.
+pub const CHAIN_SHORT_NAMES: [&str; 12] = [
+    "eth", "sep", "oeth", "arb1", "base", "matic", "gno", "bnb", "avax", "linea", "scr", "zksync",
+];
+
+/// Convert a 32-byte salt to its big-endian `uint256` decimal representation.
+pub fn salt_to_decimal(salt: &[u8; 32]) -> String {
+    // repeatedly divide the big-endian number by ten, collecting remainders
+    let mut quotient = *salt;
+    let mut digits: Vec<u8> = vec![];
+    while quotient.iter().any(|&b| b != 0) {
+        let mut remainder: u16 = 0;
+        for byte in quotient.iter_mut() {
+            let value = (remainder << 8) | u16::from(*byte);
+            *byte = (value / 10) as u8;
+            remainder = value % 10;
+        }
+        digits.push(b'0' + remainder as u8);
+    }
+
+    if digits.is_empty() {
+        return "0".to_string();
+    }
+
+    digits.iter().rev().map(|&d| d as char).collect()
+}
+
+/// Append a line to a file while holding an exclusive lock on it, so that
+/// concurrent runs writing to the same file don't interleave their output.
+/// With `sync`, the line is flushed to disk before the lock is released.
+fn append_locked(file: &File, line: &str, sync: bool) -> io::Result<()> {
+    // create a lock on the file before writing
+    file.lock_exclusive()?;
+
+    // write the result to file
+    let written = write_line(file, line, sync);
+
+    // release the file lock, even if the write failed
+    file.unlock()?;
+    written
+}
+
+/// Write a header to a file that's still empty, while holding an exclusive
+/// lock on it so that concurrent runs creating the same file only write it
+/// once.
+fn write_header(file: &File, header: &str) -> io::Result<()> {
+    file.lock_exclusive()?;
+    let written = file.metadata().and_then(|metadata| match metadata.len() {
+        0 => write!(&*file, "{}", header),
+        _ => Ok(()),
+    });
+    file.unlock()?;
+    written
+}
+
+/// Write a line to a file, flushing it to disk with `sync`.
+fn write_line(file: &File, line: &str, sync: bool) -> io::Result<()> {
+    writeln!(&*file, "{}", line)?;
+    if sync {
+        file.sync_data()?;
+    }
+    Ok(())
+}
+
+/// (Create if necessary) and open a file for appending.
+fn open_append<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    OpenOptions::new().append(true).create(true).open(path)
+}
+
+/// Appends every found address to a single file.
+pub struct FileSink {
+    path: PathBuf,
+    file: Mutex<File>,
+    format: LineFormat,
+    rotate_at: Option<u64>,
+    sync: bool,
+    header: Option<String>,
+}
+
+impl FileSink {
+    /// Open (creating if necessary) the file at `path` for appending.
+    pub fn open<P: AsRef<Path>>(path: P, format: LineFormat) -> io::Result<Self> {
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            file: Mutex::new(open_append(path)?),
+            format,
+            rotate_at: None,
+            sync: false,
+            header: None,
+        })
+    }
+
+    /// Start the file with the given header (lines commented with `#`, which
+    /// readers of results files skip) if it's empty, i.e. it was just created,
+    /// and likewise each new file started by a rotation. A file that already
+    /// holds results is appended to as it is.
+    pub fn header(mut self, header: String) -> io::Result<Self> {
+        write_header(&self.file.lock().unwrap(), &header)?;
+        self.header = Some(header);
+        Ok(self)
+    }
+
+    /// Flush each line to disk as soon as it's written, so that no result is
+    /// lost if the machine goes down mid-run. Each sync waits on the disk,
+    /// which is negligible unless results are found many times a second.
+    pub fn sync_on_write(mut self) -> Self {
+        self.sync = true;
+        self
+    }
+
+    /// Rotate the file once it grows beyond `max_bytes`: it's renamed with
+    /// the time of the rotation appended (e.g. `addresses.txt.1700000000`)
+    /// and a new file is started in its place. The rename happens while the
+    /// file is locked, so no line is lost or split across files; a concurrent
+    /// run that still has the old file open simply finishes its current line
+    /// there.
+    pub fn rotate_at(mut self, max_bytes: u64) -> Self {
+        self.rotate_at = Some(max_bytes);
+        self
+    }
+
+    /// The path the file is renamed to when rotated now.
+    fn rotated_path(&self) -> PathBuf {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        // several rotations within a second get numbered suffixes
+        let mut path = self.path.as_os_str().to_owned();
+        path.push(format!(".{}", now));
+        let mut rotated = PathBuf::from(&path);
+        let mut n = 1;
+        while rotated.exists() {
+            let mut numbered = path.clone();
+            numbered.push(format!("-{}", n));
+            rotated = PathBuf::from(numbered);
+            n += 1;
+        }
+        rotated
+    }
+}
+
+impl ResultSink for FileSink {
+    fn record(&self, found: &FoundAddress) -> io::Result<()> {
+        let mut file = self.file.lock().unwrap();
+        let max_bytes = match self.rotate_at {
+            Some(max_bytes) => max_bytes,
+            None => return append_locked(&file, &self.format.line(found), self.sync),
+        };
+
+        file.lock_exclusive()?;
+        let rotated = write_line(&file, &self.format.line(found), self.sync).and_then(|_| {
+            if file.metadata()?.len() <= max_bytes {
+                return Ok(None);
+            }
+
+            // move the full file aside and start a new one in its place
+            fs::rename(&self.path, self.rotated_path())?;
+            let new_file = open_append(&self.path)?;
+            if let Some(ref header) = self.header {
+                write_header(&new_file, header)?;
+            }
+            Ok(Some(new_file))
+        });
+        file.unlock()?;
+
+        if let Some(new_file) = rotated? {
+            *file = new_file;
+        }
+        Ok(())
+    }
+}
+
+/// Routes found addresses into one file per leading-zero-byte count, e.g.
+/// `addresses_4lz.txt` and `addresses_5lz.txt`, so the best results of a broad
+/// search are easy to pick out. Each file is only created once an address with
+/// the corresponding score is actually found.
+pub struct ScoreRoutedSink {
+    directory: PathBuf,
+    format: LineFormat,
+    files: Mutex<HashMap<u8, File>>,
+    sync: bool,
+}
+
+impl ScoreRoutedSink {
+    /// Route results into files within the given directory.
+    pub fn new<P: AsRef<Path>>(directory: P, format: LineFormat) -> Self {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+            format,
+            files: Mutex::new(HashMap::new()),
+            sync: false,
+        }
+    }
+
+    /// Flush each line to disk as soon as it's written (see
+    /// `FileSink::sync_on_write`).
+    pub fn sync_on_write(mut self) -> Self {
+        self.sync = true;
+        self
+    }
+
+    /// The path of the file that addresses with the given number of leading
+    /// zero bytes are written to.
+    pub fn path_for(&self, leading_zero_bytes: u8) -> PathBuf {
+        self.directory
+            .join(format!("addresses_{}lz.txt", leading_zero_bytes))
+    }
+}
+
+impl ResultSink for ScoreRoutedSink {
+    fn record(&self, found: &FoundAddress) -> io::Result<()> {
+        let score = found.leading_zero_bytes();
+
+        // the mutex guards the lazily-populated handles within this process
+        // while the file lock guards against other processes
+        let mut files = self.files.lock().unwrap();
+        let file = match files.entry(score) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(open_append(self.path_for(score))?),
+        };
+
+        append_locked(file, &self.format.line(found), self.sync)
+    }
+}
+
+/// A representation that found addresses can be written in (see
+/// `FormatSink`).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum OutputFormat {
+    /// The usual `<salt> => <address> => <score>` lines (see `LineFormat`).
+    Text,
+    /// One JSON object per line, with `salt`, `address`, `score`,
+    /// `leading_zeros` and `total_zeros` (zero bytes) fields, and `attempt`
+    /// when known.
+    Json,
+    /// Comma-separated `salt,address,score` rows, under a header row.
+    Csv,
+}
+
+impl OutputFormat {
+    /// The format with the given name (`text`, `json` or `csv`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(OutputFormat::Text),
+            "json" => Some(OutputFormat::Json),
+            "csv" => Some(OutputFormat::Csv),
+            _ => None,
+        }
+    }
+
+    /// Format a found address as a single line (without the trailing
+    /// newline). Text lines include the optional columns of `format`.
+    pub fn line(self, found: &FoundAddress, format: &LineFormat) -> String {
+        match self {
+            OutputFormat::Text => format.line(found),
+            OutputFormat::Json => serde_json::to_string(&JsonLine {
+                salt: format!("0x{}", hex::encode(found.salt)),
+                address: &found.checksum_address,
+                score: found.score(),
+                leading_zeros: found.leading_zero_bytes(),
+                total_zeros: found.total_zero_bytes(),
+                attempt: found.attempt,
+                target: found.target.as_deref(),
+            })
+            .unwrap(),
+            OutputFormat::Csv => format!(
+                "0x{},{},{}",
+                hex::encode(found.salt),
+                found.checksum_address,
+                found.score()
+            ),
+        }
+    }
+
+    /// The line written at the top of a new output, if any.
+    fn header(self) -> Option<&'static str> {
+        match self {
+            OutputFormat::Csv => Some("salt,address,score"),
+            _ => None,
+        }
+    }
+}
+
+/// A found address as written by `OutputFormat::Json`.
+#[derive(Serialize)]
+struct JsonLine<'a> {
+    salt: String,
+    address: &'a str,
+    score: u32,
+    leading_zeros: u8,
+    total_zeros: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attempt: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<&'a str>,
+}
+
+/// Writes every found address in one `OutputFormat`, either appending to a
+/// file or printing to stdout.
+pub struct FormatSink {
+    output_format: OutputFormat,
+    format: LineFormat,
+    file: Option<File>,
+    sync: bool,
+}
+
+impl FormatSink {
+    /// Append to the file at `path` (creating it if necessary, along with
+    /// any header), or print to stdout if there's no path.
+    pub fn open<P: AsRef<Path>>(
+        output_format: OutputFormat,
+        path: Option<P>,
+        format: LineFormat,
+    ) -> io::Result<Self> {
+        let file = match path {
+            Some(path) => Some(open_append(path)?),
+            None => None,
+        };
+
+        if let Some(header) = output_format.header() {
+            match file {
+                Some(ref file) if file.metadata()?.len() == 0 => {
+                    append_locked(file, header, false)?
+                }
+                Some(_) => {}
+                None => println!("{}", header),
+            }
+        }
+
+        Ok(Self {
+            output_format,
+            format,
+            file,
+            sync: false,
+        })
+    }
+
+    /// Flush each line written to the file to disk as soon as it's written
+    /// (see `FileSink::sync_on_write`). Lines printed to stdout aren't synced.
+    pub fn sync_on_write(mut self) -> Self {
+        self.sync = true;
+        self
+    }
+}
+
+impl ResultSink for FormatSink {
+    fn record(&self, found: &FoundAddress) -> io::Result<()> {
+        let line = self.output_format.line(found, &self.format);
+        match self.file {
+            Some(ref file) => append_locked(file, &line, self.sync),
+            None => {
+                println!("{}", line);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Sends each found address down a channel, for applications that embed the
+/// search and handle results themselves (see `cpu_with_sink`). Recording
+/// fails with `BrokenPipe` once the receiving end has been dropped.
+pub struct ChannelSink(pub Sender<FoundAddress>);
+
+impl ResultSink for ChannelSink {
+    fn record(&self, found: &FoundAddress) -> io::Result<()> {
+        self.0
+            .send(found.clone())
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "receiver dropped"))
+    }
+}
+
+/// Sends each found address down a bounded channel, as `ChannelSink` does, so
+/// that the search blocks once the receiving end falls that far behind (see
+/// `search`). Recording fails with `BrokenPipe` once the receiving end has
+/// been dropped.
+pub struct SyncChannelSink(pub SyncSender<FoundAddress>);
+
+impl ResultSink for SyncChannelSink {
+    fn record(&self, found: &FoundAddress) -> io::Result<()> {
+        self.0
+            .send(found.clone())
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "receiver dropped"))
+    }
+}
+
+/// Passes each found address on to several sinks in turn.
+pub struct TeeSink(pub Vec<Box<dyn ResultSink>>);
+
+impl ResultSink for TeeSink {
+    fn record(&self, found: &FoundAddress) -> io::Result<()> {
+        self.0.iter().try_for_each(|sink| sink.record(found))
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.0.iter().try_for_each(|sink| sink.flush())
+    }
+}
+
+/// Passes each found address on to another sink unless the same address has
+/// already been recorded, whether earlier in this run or (via `load`) in a
+/// previous run. Distinct salts can lead to the same address, so this is for
+/// collecting distinct addresses rather than distinct salts.
+pub struct DedupSink<S> {
+    inner: S,
+    seen: Mutex<HashSet<[u8; 20]>>,
+}
+
+impl<S: ResultSink> DedupSink<S> {
+    /// Deduplicate the addresses passed on to `inner`.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            seen: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Treat every address already written to the file at `path` as seen. A
+    /// file that doesn't exist yet is skipped, as are lines without an address.
+    pub fn load<P: AsRef<Path>>(self, path: P) -> io::Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(self),
+            Err(e) => return Err(e),
+        };
+
+        {
+            let mut seen = self.seen.lock().unwrap();
+            seen.extend(contents.lines().filter_map(address_in_line));
+        }
+        Ok(self)
+    }
+}
+
+impl<S: ResultSink> ResultSink for DedupSink<S> {
+    fn record(&self, found: &FoundAddress) -> io::Result<()> {
+        if !self.seen.lock().unwrap().insert(found.address) {
+            return Ok(());
+        }
+        self.inner.record(found)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Holds found addresses in memory and passes them on to another sink sorted
+/// by score (best first) once the search stops (or the sink is dropped), so
+/// the results need no separate ranking pass. At most `capacity` addresses
+/// are held at once; when the buffer fills up its contents are written out as
+/// a sorted batch. Anything still buffered is lost if the process is killed
+/// outright.
+pub struct SortedSink<S: ResultSink> {
+    inner: S,
+    capacity: usize,
+    buffer: Mutex<Vec<FoundAddress>>,
+}
+
+impl<S: ResultSink> SortedSink<S> {
+    /// Buffer up to `capacity` addresses at a time before passing them on to
+    /// `inner`.
+    pub fn new(inner: S, capacity: usize) -> Self {
+        Self {
+            inner,
+            capacity,
+            buffer: Mutex::new(vec![]),
+        }
+    }
+
+    /// Pass the buffered addresses on to the inner sink, best first.
+    fn write_sorted(&self, buffer: &mut Vec<FoundAddress>) -> io::Result<()> {
+        buffer.sort_by_key(|found| Reverse(found.score()));
+        for found in buffer.drain(..) {
+            self.inner.record(&found)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: ResultSink> ResultSink for SortedSink<S> {
+    fn record(&self, found: &FoundAddress) -> io::Result<()> {
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.push(found.clone());
+        if buffer.len() < self.capacity {
+            return Ok(());
+        }
+        self.write_sorted(&mut buffer)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.write_sorted(&mut self.buffer.lock().unwrap())?;
+        self.inner.flush()
+    }
+}
+
+/// Writes out anything still buffered if the sink is dropped without having
+/// been flushed.
+impl<S: ResultSink> Drop for SortedSink<S> {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            error!("Couldn't write sorted results: {}", e);
+        }
+    }
+}
+
+/// Keeps only the best `capacity` addresses (by score) in a file, evicting the
+/// worst address in the file whenever a better one is found. The whole file is
+/// rewritten (atomically) each time it changes, which gets expensive for large
+/// capacities but is rare once the file holds good results, since only better
+/// addresses change it. Runs shouldn't share a file this way, as each rewrite
+/// replaces the file with the contents known to the run making it.
+pub struct BestSink {
+    path: PathBuf,
+    format: LineFormat,
+    capacity: usize,
+    best: Mutex<BinaryHeap<Reverse<(u32, String)>>>,
+}
+
+impl BestSink {
+    /// Keep the best `capacity` addresses in the file at `path`, starting
+    /// from those already in the file (if it exists).
+    pub fn open<P: AsRef<Path>>(path: P, format: LineFormat, capacity: usize) -> io::Result<Self> {
+        let mut best = BinaryHeap::new();
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                for line in contents.lines() {
+                    if let Some(score) = score_in_line(line) {
+                        best.push(Reverse((score, line.to_string())));
+                    }
+                }
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        while best.len() > capacity {
+            best.pop();
+        }
+
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            format,
+            capacity,
+            best: Mutex::new(best),
+        })
+    }
+}
+
+impl ResultSink for BestSink {
+    fn record(&self, found: &FoundAddress) -> io::Result<()> {
+        let score = found.score();
+
+        let mut best = self.best.lock().unwrap();
+        if best.len() >= self.capacity {
+            // the heap is ordered so that the worst address is at the top
+            match best.peek() {
+                Some(&Reverse((worst, _))) if worst < score => best.pop(),
+                _ => return Ok(()),
+            };
+        }
+        best.push(Reverse((score, self.format.line(found))));
+
+        // rewrite the file with the best addresses first
+        let mut lines: Vec<&(u32, String)> = best.iter().map(|Reverse(entry)| entry).collect();
+        lines.sort_by_key(|&&(score, _)| Reverse(score));
+        let contents: String = lines
+            .iter()
+            .map(|&(_, line)| format!("{}\n", line))
+            .collect();
+        write_atomically(&self.path, contents.as_bytes())
+    }
+}
+
+/// The best score of the addresses in a results file, or `None` if the file
+/// is missing or holds no results.
+pub fn best_score_in<P: AsRef<Path>>(path: P) -> io::Result<Option<u32>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(contents.lines().filter_map(score_in_line).max()),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// The score within a line of output (the third field), if any.
+fn score_in_line(line: &str) -> Option<u32> {
+    line.split(" => ")
+        .nth(2)?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// The address within a line of output (the second field), if any.
+fn address_in_line(line: &str) -> Option<[u8; 20]> {
+    // skip the EIP-3770 chain short name, if any
+    let address = line.split(" => ").nth(1)?.rsplit(':').next()?;
+    let bytes: Vec<u8> = Vec::from_hex(address.trim_start_matches("0x")).ok()?;
+    if bytes.len() != 20 {
+        return None;
+    }
+
+    let mut array = [0; 20];
+    array.copy_from_slice(&bytes);
+    Some(array)
+}
@@ -0,0 +1,61 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::{write_atomically, Config};
+
+/// The name of the file that `--resume` checkpoints the search to.
+pub const STATE_FILE: &str = "search.state";
+
+/// The number of nonces searched between checkpoints with `--resume`. A
+/// restarted search repeats at most this many attempts.
+pub const CHECKPOINT_NONCES: u64 = 0x40000000;
+
+/// How far a CPU search has got, as checkpointed by `--resume`: the random
+/// salt segments being searched and the position reached within them. Every
+/// nonce before `next_nonce` has been searched.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SearchState {
+    #[serde(with = "::serde_hex")]
+    pub factory_address: [u8; 20],
+    #[serde(with = "::serde_hex::list")]
+    pub calling_addresses: Vec<[u8; 20]>,
+    #[serde(with = "::serde_hex")]
+    pub init_code_hash: [u8; 32],
+    /// The random part of the salt for each calling address.
+    #[serde(with = "::serde_hex::list")]
+    pub segments: Vec<[u8; 6]>,
+    /// The next (interleaved) nonce to search within the segments.
+    pub next_nonce: u64,
+    /// The number of segments searched in full before these.
+    pub segments_searched: u64,
+    /// The number of attempts made before these segments.
+    pub attempts_before: u64,
+}
+
+impl SearchState {
+    /// Whether the state was checkpointed by a search for the same factory,
+    /// callers and init code hash as the given configuration.
+    pub fn matches(&self, config: &Config) -> bool {
+        self.factory_address == config.factory_address
+            && self.calling_addresses == config.calling_addresses
+            && self.init_code_hash == config.init_code_hash
+    }
+
+    /// Write the state to the file at `path`, replacing it atomically so
+    /// that a search stopped mid-checkpoint leaves the previous checkpoint
+    /// intact rather than a partially written one.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        write_atomically(path.as_ref(), json.as_bytes())
+    }
+
+    /// Read the state checkpointed to the file at `path`, if there is one.
+    pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Option<Self>> {
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! The rates reported by `--bench`.
+
+extern crate create2crunch;
+
+use create2crunch::{benchmark, Config};
+
+#[test]
+fn counting_alone_is_at_least_as_fast_as_searching() {
+    // an empty target matches (and so checksums) every address, which makes
+    // the cost of matching plain
+    let args = [
+        "create2crunch",
+        "0x0000000000FFe8B47B3e2130213B802212439497",
+        "0x59b7B8Dd9E6e1F934C9c3Def4a1Eb69Bc17Ec9cc",
+        "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392",
+        "0x",
+    ];
+    let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+
+    let (counting_rate, search_rate) = benchmark(&config, 0x8000);
+    assert!(search_rate > 0.0);
+    assert!(
+        counting_rate >= search_rate,
+        "counting at {:.0} per second, searching at {:.0}",
+        counting_rate,
+        search_rate
+    );
+}
@@ -0,0 +1,57 @@
+//! Mining for several callers at once, each result recording the caller it
+//! was mined for.
+
+extern crate create2crunch;
+extern crate hex;
+
+use std::collections::HashSet;
+
+use create2crunch::{create2_address, search, Config, LineFormat};
+
+const CALLERS: [&str; 2] = [
+    "0x59b7b8dd9e6e1f934c9c3def4a1eb69bc17ec9cc",
+    "0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0",
+];
+
+#[test]
+fn results_record_the_caller_they_were_mined_for() {
+    let callers = CALLERS.join(",");
+    let args = [
+        "create2crunch",
+        "0x0000000000FFe8B47B3e2130213B802212439497",
+        &callers,
+        "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392",
+        "0x",
+        "--match-all",
+        "--deterministic",
+        "--batch-size",
+        "64",
+    ];
+    let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+    assert_eq!(config.calling_addresses.len(), 2);
+
+    let found: Vec<_> = search(config.clone()).collect();
+    assert_eq!(found.len(), 64);
+
+    let format = LineFormat {
+        caller: true,
+        ..LineFormat::default()
+    };
+    let mut used = HashSet::new();
+    for found in &found {
+        // the caller heads the salt, and so decides the address
+        let caller = format!("0x{}", hex::encode(found.caller()));
+        assert!(CALLERS.contains(&caller.as_str()));
+        assert_eq!(
+            create2_address(&config.factory_address, &found.salt, &config.init_code_hash),
+            found.address
+        );
+        assert!(format
+            .line(found)
+            .ends_with(&format!(" (caller {})", caller)));
+        used.insert(caller);
+    }
+
+    // attempts are interleaved between the callers
+    assert_eq!(used.len(), 2);
+}
@@ -0,0 +1,221 @@
+//! Embedding the search via `cpu_with_sink` and `search`.
+
+extern crate create2crunch;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use create2crunch::{cpu_with_sink, handle_solution, search, Config};
+
+fn config() -> Config {
+    let args = [
+        "create2crunch",
+        "0x0000000000000000000000000000000000000000",
+        "0x0000000000000000000000000000000000000000",
+        "0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+        "0x00",
+        "--match-all",
+    ];
+    Config::new(args.iter().map(|arg| arg.to_string())).unwrap()
+}
+
+#[test]
+fn found_addresses_are_sent_down_the_channel() {
+    let config = config();
+
+    let (sender, receiver) = channel();
+    let search = {
+        let config = config.clone();
+        thread::spawn(move || {
+            cpu_with_sink(config, sender, Arc::new(AtomicBool::new(false))).unwrap()
+        })
+    };
+
+    for found in receiver.iter().take(3) {
+        assert!(found.checksum_address.starts_with("0x00"));
+        let derived = handle_solution(&config, &found.salt).unwrap();
+        assert_eq!(derived.checksum_address, found.checksum_address);
+    }
+
+    // dropping the receiver stops the search
+    drop(receiver);
+    search.join().unwrap();
+}
+
+#[test]
+fn the_callers_flag_stops_the_search() {
+    let (sender, receiver) = channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let search = {
+        let stop = Arc::clone(&stop);
+        thread::spawn(move || cpu_with_sink(config(), sender, stop).unwrap())
+    };
+
+    // the receiver is kept, so only the flag can stop the search
+    receiver.recv().unwrap();
+    stop.store(true, Ordering::Relaxed);
+    let summary = search.join().unwrap();
+    assert!(summary.found >= 1);
+    assert_eq!(summary.found, 1 + receiver.try_iter().count() as u64);
+}
+
+#[test]
+fn the_flag_takes_effect_within_one_chunk() {
+    for &fast_first in [false, true].iter() {
+        let mut args = vec![
+            "create2crunch",
+            "0x0000000000000000000000000000000000000000",
+            "0x0000000000000000000000000000000000000000",
+            "0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+            "0x",
+            "--match-all",
+            "--threads",
+            "1",
+        ];
+        if fast_first {
+            args.push("--fast-first");
+        }
+
+        // the flag is set by the very first address checked (which, like
+        // every other, is then rejected)
+        let stop = Arc::new(AtomicBool::new(false));
+        let config = {
+            let stop = Arc::clone(&stop);
+            Config::new(args.iter().map(|arg| arg.to_string()))
+                .unwrap()
+                .with_predicate(move |_| {
+                    stop.store(true, Ordering::Relaxed);
+                    false
+                })
+        };
+        let check_interval = config.check_interval();
+
+        // the chunk in hand is finished, and no other is started
+        let (sender, _receiver) = channel();
+        let summary = cpu_with_sink(config, sender, stop).unwrap();
+        assert_eq!(summary.attempts, check_interval);
+        assert_eq!(summary.found, 0);
+    }
+}
+
+#[test]
+fn fast_first_stops_promptly() {
+    let args = [
+        "create2crunch",
+        "0x0000000000000000000000000000000000000000",
+        "0x0000000000000000000000000000000000000000",
+        "0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+        "0xffffffffffffffffffffffffffffffffffffffff",
+        "--fast-first",
+    ];
+    let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+    let stop = Arc::new(AtomicBool::new(false));
+    let (sender, _receiver) = channel();
+    let search = {
+        let stop = Arc::clone(&stop);
+        thread::spawn(move || cpu_with_sink(config, sender, stop).unwrap())
+    };
+
+    // each thread only has a small chunk to finish once the flag is set
+    thread::sleep(Duration::from_millis(200));
+    let stopped = Instant::now();
+    stop.store(true, Ordering::Relaxed);
+    let summary = search.join().unwrap();
+    assert!(stopped.elapsed() < Duration::from_millis(500));
+    assert!(summary.attempts > 0);
+}
+
+#[test]
+fn found_addresses_are_yielded_until_the_iterator_is_dropped() {
+    let config = config();
+    let found: Vec<_> = search(config.clone()).take(3).collect();
+
+    assert_eq!(found.len(), 3);
+    for found in found {
+        let derived = handle_solution(&config, &found.salt).unwrap();
+        assert_eq!(derived.checksum_address, found.checksum_address);
+    }
+}
+
+#[test]
+fn a_finished_search_summarizes_the_run() {
+    let args = [
+        "create2crunch",
+        "0x0000000000000000000000000000000000000000",
+        "0x0000000000000000000000000000000000000000",
+        "0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+        "0x0",
+        "--match-all",
+        "--deterministic",
+        "--batch-size",
+        "4096",
+    ];
+    let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+
+    let (sender, receiver) = channel();
+    let summary = cpu_with_sink(config, sender, Arc::new(AtomicBool::new(false))).unwrap();
+
+    // every salt of the segment is searched, and about one in 16 matches
+    assert_eq!(summary.attempts, 4096);
+    assert_eq!(summary.found, receiver.iter().count() as u64);
+    assert!(summary.found > 0 && summary.found < 4096);
+}
+
+#[test]
+fn a_search_stops_at_its_timeout() {
+    let args = [
+        "create2crunch",
+        "0x0000000000000000000000000000000000000000",
+        "0x0000000000000000000000000000000000000000",
+        "0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+        "0xffffffffffffffffffffffffffffffffffffffff",
+        "--timeout",
+        "1",
+    ];
+    let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+
+    let (sender, _receiver) = channel();
+    let summary = cpu_with_sink(config, sender, Arc::new(AtomicBool::new(false))).unwrap();
+
+    assert_eq!(summary.found, 0);
+    assert!(summary.elapsed >= Duration::from_secs(1));
+    assert!(summary.elapsed < Duration::from_secs(30));
+}
+
+#[test]
+fn progress_is_reported_to_the_callback() {
+    let args = [
+        "create2crunch",
+        "0x0000000000000000000000000000000000000000",
+        "0x0000000000000000000000000000000000000000",
+        "0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+        "0xffffffffffffffffffffffffffffffffffffffff",
+        "--timeout",
+        "2",
+        // stop within a fraction of a second of the timeout, even unoptimized
+        "--fast-first",
+    ];
+    let reports = Arc::new(Mutex::new(vec![]));
+    let config = {
+        let reports = Arc::clone(&reports);
+        Config::new(args.iter().map(|arg| arg.to_string()))
+            .unwrap()
+            .on_progress(move |stats| reports.lock().unwrap().push(stats))
+    };
+
+    let (sender, _receiver) = channel();
+    let summary = cpu_with_sink(config, sender, Arc::new(AtomicBool::new(false))).unwrap();
+
+    // reported once straight away and about once per second after that
+    let reports = reports.lock().unwrap();
+    assert!(!reports.is_empty() && reports.len() <= 3);
+    assert!(reports
+        .windows(2)
+        .all(|pair| pair[0].attempts <= pair[1].attempts));
+    assert!(reports
+        .iter()
+        .all(|stats| stats.attempts <= summary.attempts));
+}
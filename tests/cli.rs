@@ -0,0 +1,586 @@
+//! The binary as run from the command line, in a scratch directory so that
+//! any files it writes can be checked for.
+
+extern crate create2crunch;
+extern crate hex;
+extern crate serde_json;
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::{self, Command, Output, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use create2crunch::nibble_distance;
+
+const FACTORY: &str = "0x0000000000FFe8B47B3e2130213B802212439497";
+const CALLER: &str = "0x59b7B8Dd9E6e1F934C9c3Def4a1Eb69Bc17Ec9cc";
+const INIT_CODE_HASH: &str = "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392";
+
+/// A fresh scratch directory for the named test.
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("create2crunch-cli-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Run the binary in the given directory with the given arguments, logging
+/// at the default level.
+fn run(dir: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_create2crunch"))
+        .current_dir(dir)
+        .env_remove("RUST_LOG")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn preview_prints_a_match_without_writing_results() {
+    let dir = scratch("preview");
+    let output = run(
+        &dir,
+        &[
+            FACTORY,
+            CALLER,
+            "-",
+            "0xab",
+            "--init-code",
+            "0x6001600155",
+            "--seed",
+            "1",
+            "--preview",
+        ],
+    );
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let line = |label: &str| {
+        stdout
+            .lines()
+            .find(|line| line.starts_with(label))
+            .unwrap_or_else(|| panic!("no {} line in {}", label, stdout))
+            .to_string()
+    };
+    let salt = line("salt:")[12..].to_string();
+    assert!(salt.starts_with(&CALLER[2..].to_lowercase()));
+    assert!(line("address:")[12..].to_lowercase().starts_with("ab"));
+    assert!(line("score:").len() > 10);
+
+    // the calldata deploys the init code with the salt found
+    let calldata = line("calldata:");
+    assert!(calldata.starts_with(&format!("calldata: 0x64e03087{}", salt)));
+    assert!(calldata.contains("6001600155"));
+
+    assert!(!dir.join("efficient_addresses.txt").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn closest_records_ever_closer_addresses() {
+    let target = "0x000000000000000000000000000000000000dEaD";
+    let args = [
+        FACTORY,
+        CALLER,
+        INIT_CODE_HASH,
+        target,
+        "--closest",
+        "--seed",
+        "7",
+        "--threads",
+        "1",
+        "--batch-size",
+        "16384",
+        "--max-segments",
+        "2",
+    ];
+    let mut target_bytes = [0; 20];
+    target_bytes.copy_from_slice(&hex::decode(&target[2..]).unwrap());
+
+    let mut runs = Vec::new();
+    for run_number in 0..2 {
+        let dir = scratch(&format!("closest-{}", run_number));
+        assert!(run(&dir, &args).status.success());
+        runs.push(fs::read_to_string(dir.join("efficient_addresses.txt")).unwrap());
+        fs::remove_dir_all(dir).unwrap();
+    }
+    // the same seed searches the same salts, so finds the same addresses
+    assert_eq!(runs[0], runs[1]);
+
+    let distances: Vec<u32> = runs[0]
+        .lines()
+        .map(|line| {
+            let address = line.split(" => ").nth(1).unwrap();
+            let mut bytes = [0; 20];
+            bytes.copy_from_slice(&hex::decode(&address[2..]).unwrap());
+            let distance = nibble_distance(&bytes, &target_bytes);
+            assert!(line.ends_with(&format!(" (distance {})", distance)));
+            distance
+        })
+        .collect();
+    assert!(distances.len() > 1);
+    assert!(distances.windows(2).all(|pair| pair[1] < pair[0]));
+}
+
+#[test]
+fn dry_parse_prints_a_valid_configuration_and_exits() {
+    let dir = scratch("dry-parse");
+    let output = run(
+        &dir,
+        &[
+            FACTORY,
+            &CALLER.to_lowercase(),
+            INIT_CODE_HASH,
+            "0xdead",
+            "--dry-parse",
+        ],
+    );
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(&format!("factory:        {}\n", FACTORY)));
+    assert!(stdout.contains(&format!("caller:         {}\n", CALLER)));
+    assert!(stdout.contains(&format!("init code hash: {}\n", INIT_CODE_HASH)));
+    assert!(stdout.contains("target:         0xdead (bytes 0xdead, 16 bits)\n"));
+
+    // nothing is searched or written
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn dry_parse_rejects_invalid_configurations() {
+    let dir = scratch("dry-parse-invalid");
+    let invalid: &[(&[&str], &str)] = &[
+        (
+            &[FACTORY, "0x59b7", INIT_CODE_HASH, "0xdead"],
+            "calling address",
+        ),
+        (&[FACTORY, CALLER, "0xzz", "0xdead"], "init code hash"),
+        (&[FACTORY, CALLER, INIT_CODE_HASH], "target"),
+        (
+            &[FACTORY, CALLER, INIT_CODE_HASH, "0xdead", "--frobnicate"],
+            "unrecognized flag",
+        ),
+        (
+            &[
+                FACTORY,
+                CALLER,
+                INIT_CODE_HASH,
+                &format!("0x{}", "0".repeat(41)),
+            ],
+            "target",
+        ),
+    ];
+    for (args, problem) in invalid {
+        let mut args = args.to_vec();
+        args.push("--dry-parse");
+        let output = run(&dir, &args);
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        // printed plainly rather than logged, so it shows whatever RUST_LOG says
+        assert!(
+            stderr.starts_with("Problem parsing arguments"),
+            "{}",
+            stderr
+        );
+        assert!(stderr.contains(problem), "{:?}: {}", args, stderr);
+        assert!(output.stdout.is_empty());
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn warmup_reports_a_rate_before_searching() {
+    let dir = scratch("warmup");
+    let output = run(
+        &dir,
+        &[
+            FACTORY,
+            CALLER,
+            INIT_CODE_HASH,
+            "0xab",
+            "--warmup",
+            "1",
+            "--deterministic",
+            "--batch-size",
+            "1024",
+        ],
+    );
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let position = |message: &str| {
+        stderr
+            .find(message)
+            .unwrap_or_else(|| panic!("no {:?} in {}", message, stderr))
+    };
+    assert!(position("Warming up for 1 seconds") < position("Hashing "));
+    assert!(position("Hashing ") < position("Searching for addresses"));
+
+    let rate: f64 = stderr[position("Hashing ") + 8..]
+        .split(' ')
+        .next()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(rate > 0.0);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn sync_on_write_writes_every_result_to_each_file() {
+    let dir = scratch("sync");
+    let output = run(
+        &dir,
+        &[
+            FACTORY,
+            CALLER,
+            INIT_CODE_HASH,
+            "0xa",
+            "--match-all",
+            "--deterministic",
+            "--batch-size",
+            "256",
+            "--sync-on-write",
+            "--format",
+            "json=results.jsonl",
+        ],
+    );
+    assert!(output.status.success());
+
+    // whether or not each line was synced can't be seen from here, but every
+    // result must still be written, once, to both files
+    let text = fs::read_to_string(dir.join("efficient_addresses.txt")).unwrap();
+    let json = fs::read_to_string(dir.join("results.jsonl")).unwrap();
+    assert!(text.lines().count() > 0);
+    assert_eq!(text.lines().count(), json.lines().count());
+    for line in text.lines() {
+        let salt = line.split(" => ").next().unwrap();
+        assert_eq!(json.matches(salt).count(), 1);
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn progress_without_a_terminal_logs_plain_lines() {
+    let dir = scratch("progress");
+    // the output of the binary is captured, so it isn't a terminal
+    let output = run(
+        &dir,
+        &[
+            FACTORY,
+            CALLER,
+            INIT_CODE_HASH,
+            "0xa",
+            "--progress",
+            "--threads",
+            "1",
+            "--timeout",
+            "1",
+        ],
+    );
+    assert!(output.status.success());
+
+    // the progress is logged as a line rather than drawn, and nothing holds
+    // an escape sequence
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr
+            .lines()
+            .any(|line| line.contains("attempts per second")),
+        "{}",
+        stderr
+    );
+    assert!(!stdout.contains('\x1b'), "{:?}", stdout);
+    assert!(!stderr.contains('\x1b'), "{:?}", stderr);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn max_segments_stops_after_that_many_segments_and_reports_stats() {
+    let dir = scratch("max-segments");
+    let output = run(
+        &dir,
+        &[
+            FACTORY,
+            CALLER,
+            INIT_CODE_HASH,
+            "0xab",
+            "--match-all",
+            "--seed",
+            "3",
+            "--batch-size",
+            "4096",
+            "--max-segments",
+            "3",
+        ],
+    );
+    assert!(output.status.success());
+
+    // three segments of 4096 salts each, with every address found written
+    let results = fs::read_to_string(dir.join("efficient_addresses.txt")).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let line = stderr
+        .lines()
+        .find(|line| line.contains("Search finished after 12288 attempts in"))
+        .unwrap_or_else(|| panic!("no summary in {}", stderr));
+    assert!(
+        line.ends_with(&format!("; {} addresses found.", results.lines().count())),
+        "{}",
+        line
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn resume_best_only_records_addresses_beating_the_file() {
+    let dir = scratch("resume-best");
+    let args = [
+        FACTORY,
+        CALLER,
+        INIT_CODE_HASH,
+        "0xdead",
+        "--resume-best",
+        "--dry-parse",
+    ];
+
+    // without results, the usual thresholds apply
+    let output = run(&dir, &args);
+    assert!(output.status.success());
+    assert!(!String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("min score"));
+
+    // with them, the threshold is one above the best score in the file,
+    // wherever it appears
+    fs::write(
+        dir.join("efficient_addresses.txt"),
+        format!(
+            "# factory:        {}\n0x{} => 0x00000000dead1111111111111111111111111111 => 88\n0x{} \
+             => 0x0000000000dead11111111111111111111111111 => 110 (attempt 7)\n0x{} => \
+             0x000000dead1111111111111111111111111111ff => 66\n",
+            FACTORY,
+            "00".repeat(32),
+            "01".repeat(32),
+            "02".repeat(32)
+        ),
+    )
+    .unwrap();
+    let output = run(&dir, &args);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("option:         min score 111\n"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn sigterm_flushes_held_results_and_exits_cleanly() {
+    let dir = scratch("sigterm");
+    let child = Command::new(env!("CARGO_BIN_EXE_create2crunch"))
+        .current_dir(&dir)
+        .env_remove("RUST_LOG")
+        .args([
+            FACTORY,
+            CALLER,
+            INIT_CODE_HASH,
+            "0xab",
+            "--sorted",
+            "--match-all",
+            "--threads",
+            "1",
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // --sorted holds every result until the search stops
+    thread::sleep(Duration::from_secs(1));
+    let killed = Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    // everything held is written, best first, and the stats are reported
+    let results = fs::read_to_string(dir.join("efficient_addresses.txt")).unwrap();
+    let scores: Vec<u32> = results
+        .lines()
+        .map(|line| line.rsplit(" => ").next().unwrap().parse().unwrap())
+        .collect();
+    assert!(!scores.is_empty());
+    assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(&format!("; {} addresses found.", scores.len())),
+        "{}",
+        stderr
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn text_and_json_files_hold_the_same_results() {
+    let dir = scratch("formats");
+    let output = run(
+        &dir,
+        &[
+            FACTORY,
+            CALLER,
+            INIT_CODE_HASH,
+            "0xab",
+            "--match-all",
+            "--deterministic",
+            "--batch-size",
+            "4096",
+            "--format",
+            "text=addresses.txt",
+            "--format",
+            "json=addresses.jsonl",
+        ],
+    );
+    assert!(output.status.success());
+
+    let text: Vec<(String, String)> = fs::read_to_string(dir.join("addresses.txt"))
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let fields: Vec<&str> = line.split(" => ").collect();
+            (fields[0].to_string(), fields[1].to_string())
+        })
+        .collect();
+    let json: Vec<(String, String)> = fs::read_to_string(dir.join("addresses.jsonl"))
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let json: serde_json::Value = serde_json::from_str(line).unwrap();
+            (
+                json["salt"].as_str().unwrap().to_string(),
+                json["address"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert!(!text.is_empty());
+    assert_eq!(text, json);
+
+    // and the same as the usual results file
+    let results = fs::read_to_string(dir.join("efficient_addresses.txt")).unwrap();
+    assert_eq!(
+        results,
+        fs::read_to_string(dir.join("addresses.txt")).unwrap()
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn low_scoring_results_are_written_but_not_announced() {
+    let dir = scratch("announce");
+    let output = run(
+        &dir,
+        &[
+            FACTORY,
+            CALLER,
+            INIT_CODE_HASH,
+            "0xab",
+            "--match-all",
+            "--deterministic",
+            "--batch-size",
+            "4096",
+            "--announce-min-score",
+            "1",
+        ],
+    );
+    assert!(output.status.success());
+
+    // every result is written, but only those scoring at least 1 are printed
+    let results = fs::read_to_string(dir.join("efficient_addresses.txt")).unwrap();
+    let score = |line: &str| line.rsplit(" => ").next().unwrap().parse::<u32>().unwrap();
+    let (announced, quiet): (Vec<&str>, Vec<&str>) =
+        results.lines().partition(|line| score(line) >= 1);
+    assert!(!announced.is_empty() && !quiet.is_empty());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().collect::<Vec<_>>(), announced);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn the_state_file_is_only_read_with_resume() {
+    let dir = scratch("resume");
+    fs::write(dir.join("search.state"), "{ half-written").unwrap();
+    let args = [
+        FACTORY,
+        CALLER,
+        INIT_CODE_HASH,
+        "0xab",
+        "--batch-size",
+        "4096",
+        "--max-segments",
+        "1",
+    ];
+
+    // an ordinary run neither reads nor touches the state
+    let output = run(&dir, &args);
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(dir.join("search.state")).unwrap(),
+        "{ half-written"
+    );
+
+    // while resuming from it fails
+    let mut resuming = args.to_vec();
+    resuming.push("--resume");
+    let output = run(&dir, &resuming);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Couldn't read search.state"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn a_salt_found_twice_in_a_run_is_written_once() {
+    let dir = scratch("repeats");
+    // a thousand segments drawn from 256 are bound to repeat some of them,
+    // and with both thresholds at zero every address is a result
+    let args = [
+        FACTORY,
+        CALLER,
+        INIT_CODE_HASH,
+        "0x",
+        "--random-bytes",
+        "1",
+        "--batch-size",
+        "16",
+        "--max-segments",
+        "1000",
+        "--leading-zeroes",
+        "0",
+        "--total-zeroes",
+        "0",
+    ];
+    assert!(run(&dir, &args).status.success());
+
+    let contents = fs::read_to_string(dir.join("efficient_addresses.txt")).unwrap();
+    let mut lines: Vec<&str> = contents.lines().collect();
+    assert!(lines.len() < 16000);
+    let written = lines.len();
+    lines.sort();
+    lines.dedup();
+    assert_eq!(lines.len(), written);
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,303 @@
+//! Rejection of invalid arguments by `Config::new`.
+
+extern crate create2crunch;
+extern crate hex;
+
+use create2crunch::{Config, ConfigError};
+
+fn parse(args: &[&str]) -> Result<Config, ConfigError> {
+    let mut all = vec!["create2crunch"];
+    all.extend_from_slice(args);
+    Config::new(all.iter().map(|arg| arg.to_string()))
+}
+
+const FACTORY: &str = "0x0000000000000000000000000000000000000000";
+const INIT_CODE_HASH: &str = "0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a";
+
+#[test]
+fn missing_arguments_are_named() {
+    assert_eq!(parse(&[]).unwrap_err(), ConfigError::MissingFactoryAddress);
+    assert_eq!(
+        parse(&[FACTORY, FACTORY, INIT_CODE_HASH]).unwrap_err(),
+        ConfigError::MissingTarget
+    );
+}
+
+#[test]
+fn malformed_arguments_name_the_field() {
+    assert_eq!(
+        parse(&[FACTORY, "0x1234", INIT_CODE_HASH, "0x00"]).unwrap_err(),
+        ConfigError::InvalidHexLength {
+            field: "calling address",
+            expected: 20,
+            got: 2,
+        }
+    );
+    assert_eq!(
+        parse(&[FACTORY, FACTORY, "0xzz", "0x00"]).unwrap_err(),
+        ConfigError::NotHex {
+            field: "init code hash"
+        }
+    );
+    assert_eq!(
+        parse(&[FACTORY, FACTORY, INIT_CODE_HASH, "00"]).unwrap_err(),
+        ConfigError::TargetMissingPrefix
+    );
+}
+
+#[test]
+fn targets_longer_than_an_address_are_rejected() {
+    let target = format!("0x{}", "0".repeat(41));
+    assert_eq!(
+        parse(&[FACTORY, FACTORY, INIT_CODE_HASH, &target]).unwrap_err(),
+        ConfigError::TargetTooLong { nibbles: 41 }
+    );
+    assert!(parse(&[FACTORY, FACTORY, INIT_CODE_HASH, &target[..42]]).is_ok());
+}
+
+#[test]
+fn the_builder_matches_the_command_line() {
+    let mut init_code_hash = [0; 32];
+    init_code_hash.copy_from_slice(&hex::decode(&INIT_CODE_HASH[2..]).unwrap());
+
+    let built = Config::builder()
+        .factory_address([0; 20])
+        .calling_address([0; 20])
+        .init_code_hash(init_code_hash)
+        .target_prefix("0xdead")
+        .configure(|options| options.max_results = Some(1))
+        .build()
+        .unwrap();
+    let parsed = parse(&[
+        FACTORY,
+        FACTORY,
+        INIT_CODE_HASH,
+        "0xdead",
+        "--max-results",
+        "1",
+    ])
+    .unwrap();
+    assert_eq!(built.to_string(), parsed.to_string());
+
+    assert_eq!(
+        Config::builder()
+            .factory_address([0; 20])
+            .build()
+            .unwrap_err(),
+        ConfigError::MissingInitCodeHash
+    );
+
+    // the init code is kept along with its hash
+    let from_code = Config::builder()
+        .factory_address([0; 20])
+        .init_code(&[0])
+        .target_prefix("0xdead")
+        .build()
+        .unwrap();
+    assert_eq!(from_code.init_code_hash, init_code_hash);
+    assert_eq!(from_code.init_code, Some(vec![0]));
+
+    // and options set on the builder are validated as the command line's are
+    assert_eq!(
+        Config::builder()
+            .factory_address([0; 20])
+            .init_code_hash(init_code_hash)
+            .configure(|options| options.hybrid = true)
+            .build()
+            .unwrap_err(),
+        ConfigError::Invalid("--hybrid requires a gpu device.")
+    );
+}
+
+#[test]
+fn init_code_is_hashed_in_place_of_its_hash() {
+    let config = parse(&[FACTORY, FACTORY, "-", "0x00", "--init-code", "0x00"]).unwrap();
+    assert_eq!(hex::encode(config.init_code_hash), &INIT_CODE_HASH[2..]);
+
+    assert!(parse(&[
+        FACTORY,
+        FACTORY,
+        INIT_CODE_HASH,
+        "0x00",
+        "--init-code",
+        "0x00"
+    ])
+    .is_err());
+    assert_eq!(
+        parse(&[FACTORY, FACTORY, "-", "0x00"]).unwrap_err(),
+        ConfigError::MissingInitCodeHash
+    );
+}
+
+#[test]
+fn init_code_hashes_are_read_from_files() {
+    let path = std::env::temp_dir().join(format!("create2crunch-{}.hash", std::process::id()));
+    std::fs::write(&path, format!("  {}\n", INIT_CODE_HASH)).unwrap();
+    let path = path.to_str().unwrap();
+
+    let given = parse(&[FACTORY, FACTORY, INIT_CODE_HASH, "0x00"]).unwrap();
+    let at_path = parse(&[FACTORY, FACTORY, &format!("@{}", path), "0x00"]).unwrap();
+    let flag = parse(&[FACTORY, FACTORY, "-", "0x00", "--init-code-hash-file", path]).unwrap();
+    assert_eq!(at_path.init_code_hash, given.init_code_hash);
+    assert_eq!(flag.init_code_hash, given.init_code_hash);
+
+    // the flag stands in for the positional argument, not alongside it
+    assert!(parse(&[
+        FACTORY,
+        FACTORY,
+        INIT_CODE_HASH,
+        "0x00",
+        "--init-code-hash-file",
+        path
+    ])
+    .is_err());
+    std::fs::remove_file(path).unwrap();
+
+    // a file that can't be read is named, along with why
+    let error = parse(&[FACTORY, FACTORY, &format!("@{}", path), "0x00"]).unwrap_err();
+    match error {
+        ConfigError::ReadFile {
+            what,
+            path: ref missing,
+            ..
+        } => {
+            assert_eq!(what, "init code hash file");
+            assert_eq!(missing, path);
+        }
+        ref error => panic!("{:?}", error),
+    }
+    let message = error.to_string();
+    assert!(message.contains(path), "{}", message);
+    assert!(message.contains("No such file"), "{}", message);
+}
+
+#[test]
+fn config_files_are_overridden_by_the_command_line() {
+    let path = std::env::temp_dir().join(format!("create2crunch-{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        format!(
+            "factory = \"{}\"\ncaller = [\"{}\"]\ninit_code_hash = \"{}\"\ntarget = \
+             \"0xdead\"\nmax_results = 1\nthreads = 2\nprogress = true\n",
+            FACTORY, FACTORY, INIT_CODE_HASH
+        ),
+    )
+    .unwrap();
+    let path = path.to_str().unwrap();
+
+    let from_file = parse(&["--config", path]).unwrap();
+    let parsed = parse(&[
+        FACTORY,
+        FACTORY,
+        INIT_CODE_HASH,
+        "0xdead",
+        "--max-results",
+        "1",
+        "--progress",
+        "--threads",
+        "2",
+    ])
+    .unwrap();
+    assert_eq!(from_file.to_string(), parsed.to_string());
+
+    let overridden = parse(&[
+        "--config",
+        path,
+        FACTORY,
+        FACTORY,
+        INIT_CODE_HASH,
+        "0xbeef",
+        "--threads",
+        "3",
+    ])
+    .unwrap();
+    assert_eq!(overridden.target_start_string, "0xbeef");
+    assert_eq!(overridden.threads, Some(3));
+    assert_eq!(overridden.max_results, Some(1));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn presets_in_a_config_file_resolve_named_addresses() {
+    let factory = "0x0000000000FFe8B47B3e2130213B802212439497";
+    let deployer = "0x59b7B8Dd9E6e1F934C9c3Def4a1Eb69Bc17Ec9cc";
+    let path =
+        std::env::temp_dir().join(format!("create2crunch-presets-{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        format!(
+            r#"factory = "@factory"
+caller = "@deployer"
+init_code_hash = "{}"
+target = "0xdead"
+
+[presets]
+factory = "{}"
+deployer = "{}"
+"#,
+            INIT_CODE_HASH, factory, deployer
+        ),
+    )
+    .unwrap();
+    let path = path.to_str().unwrap();
+
+    let from_file = parse(&["--config", path]).unwrap();
+    let expected = parse(&[factory, deployer, INIT_CODE_HASH, "0xdead"]).unwrap();
+    assert_eq!(from_file.factory_address, expected.factory_address);
+    assert_eq!(from_file.calling_addresses, expected.calling_addresses);
+
+    // names given on the command line are resolved from the file's presets
+    let callers = format!("{},{}", factory, deployer);
+    let from_cli = parse(&["--config", path, "@deployer", "@factory,@deployer"]).unwrap();
+    let expected = parse(&[deployer, &callers, INIT_CODE_HASH, "0xdead"]).unwrap();
+    assert_eq!(from_cli.factory_address, expected.factory_address);
+    assert_eq!(from_cli.calling_addresses, expected.calling_addresses);
+
+    // naming an address the presets don't hold is an error
+    assert!(parse(&["--config", path, "@nobody"]).is_err());
+    // as is an `@name` without a config file
+    assert!(parse(&["@factory", deployer, INIT_CODE_HASH, "0xdead"]).is_err());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn strict_refuses_a_factory_given_as_the_caller() {
+    let args = [
+        "0x59b7B8Dd9E6e1F934C9c3Def4a1Eb69Bc17Ec9cc",
+        "0x0000000000FFe8B47B3e2130213B802212439497",
+        "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392",
+        "0xdead",
+        "--strict",
+    ];
+
+    // without --strict the search only warns
+    let config = parse(&args[..4]).unwrap();
+    assert_eq!(config.warnings().len(), 1);
+    assert!(config
+        .describe()
+        .contains("caller:         0x0000000000FFe8B47B3e2130213B802212439497"));
+    assert!(parse(&args).is_err());
+}
+
+#[test]
+fn the_gpu_device_is_given_once() {
+    let args = [FACTORY, FACTORY, INIT_CODE_HASH, "0x00"];
+    let with = |extra: &[&str]| {
+        let mut all = args.to_vec();
+        all.extend_from_slice(extra);
+        parse(&all)
+    };
+
+    let config = with(&["--gpu", "1", "--leading-zeroes", "4"]).unwrap();
+    assert_eq!(config.gpu_device, 1);
+    assert_eq!(config.leading_zeroes_threshold, 4);
+    assert!(with(&["0", "4", "6"]).unwrap().uses_gpu());
+
+    assert!(with(&["--gpu", "1", "--gpu", "2"]).is_err());
+    assert!(with(&["--gpu", "all", "--gpu", "0"]).is_err());
+    // a positional device (or threshold) alongside --gpu is ambiguous
+    assert!(with(&["0", "--gpu", "1"]).is_err());
+    assert!(with(&["--gpu", "1", "4", "6"]).is_err());
+}
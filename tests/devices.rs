@@ -0,0 +1,36 @@
+//! Splitting the salt space between the devices of a `--gpu all` search.
+#![cfg(feature = "gpu")]
+
+extern crate create2crunch;
+extern crate rand;
+
+use rand::Rng;
+
+use create2crunch::device_segment;
+
+#[test]
+fn each_device_searches_its_own_range_of_segments() {
+    let mut rng = rand::thread_rng();
+    for devices in 1..=5usize {
+        let span = (1u64 << 32) / devices as u64;
+        for slot in 0..devices {
+            let range = slot as u64 * span..(slot as u64 + 1) * span;
+            let mut randoms = vec![0, 1, u32::MAX, span as u32, (span - 1) as u32];
+            randoms.extend((0..100).map(|_| rng.gen::<u32>()));
+
+            // whatever the random draw, the segment stays in the slot's range,
+            // so no two devices can search the same salts
+            for random in randoms {
+                let segment = u64::from(u32::from_be_bytes(device_segment(random, slot, devices)));
+                assert!(
+                    range.contains(&segment),
+                    "device {} of {} drew {:#x} outside {:#x?}",
+                    slot,
+                    devices,
+                    segment,
+                    range
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! The structured output formats, and what each line records about its
+//! result.
+
+extern crate create2crunch;
+extern crate hex;
+extern crate serde_json;
+
+use create2crunch::{
+    create2_address, salt_to_decimal, search, search_once, Config, FoundAddress, LineFormat,
+    OutputFormat,
+};
+
+const CALLER: &str = "0x59b7b8dd9e6e1f934c9c3def4a1eb69bc17ec9cc";
+
+#[test]
+fn json_lines_hold_the_salt_address_and_zero_bytes() {
+    let mut address = [0x11; 20];
+    address[..2].copy_from_slice(&[0, 0]);
+    address[10] = 0;
+    let found = FoundAddress::new([0xab; 32], address);
+
+    let line = OutputFormat::Json.line(&found, &LineFormat::default());
+    let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(json["salt"], format!("0x{}", "ab".repeat(32)));
+    assert_eq!(json["address"], found.checksum_address);
+    assert_eq!(json["score"], found.score());
+    assert_eq!(json["leading_zeros"], 2);
+    assert_eq!(json["total_zeros"], 3);
+}
+
+#[test]
+fn chain_lines_hold_an_eip_3770_address() {
+    let found = FoundAddress::new([0xab; 32], [0x4d; 20]);
+    let salt = format!("0x{}", "ab".repeat(32));
+
+    // plain checksummed addresses by default
+    let plain = LineFormat::default().line(&found);
+    assert_eq!(
+        plain,
+        format!(
+            "{} => {} => {}",
+            salt,
+            found.checksum_address,
+            found.score()
+        )
+    );
+
+    let chain = LineFormat {
+        chain: Some("base"),
+        ..LineFormat::default()
+    };
+    assert_eq!(
+        chain.line(&found),
+        format!(
+            "{} => base:{} => {}",
+            salt,
+            found.checksum_address,
+            found.score()
+        )
+    );
+
+    // only known short names are accepted
+    let config_with = |chain: &str| {
+        let args = [
+            "create2crunch",
+            "0x0000000000000000000000000000000000000000",
+            CALLER,
+            "0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+            "0xdead",
+            "--chain",
+            chain,
+        ];
+        Config::new(args.iter().map(|arg| arg.to_string()))
+    };
+    assert_eq!(config_with("base").unwrap().chain.as_deref(), Some("base"));
+    assert!(config_with("nochain").is_err());
+}
+
+#[test]
+fn lines_hold_the_full_salt_passed_to_create2() {
+    let args = [
+        "create2crunch",
+        CALLER,
+        CALLER,
+        "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392",
+        "0x0",
+        "--seed",
+        "1",
+    ];
+    let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+    let found = search_once(&config, 1 << 16).unwrap();
+
+    let line = OutputFormat::Text.line(&found, &LineFormat::default());
+    let salt = line.split(" => ").next().unwrap();
+    assert_eq!(salt.len(), 66);
+    assert!(salt.starts_with(CALLER));
+    assert_eq!(salt, format!("0x{}", hex::encode(found.salt)));
+    assert_eq!(
+        create2_address(&config.factory_address, &found.salt, &config.init_code_hash),
+        found.address
+    );
+}
+
+#[test]
+fn salts_convert_to_their_exact_decimal_value() {
+    let mut salt = [0; 32];
+    assert_eq!(salt_to_decimal(&salt), "0");
+    salt[28..].copy_from_slice(&[0xca, 0xfe, 0xba, 0xbe]);
+    assert_eq!(salt_to_decimal(&salt), "3405691582");
+
+    // a full salt, as mined (cross-checked with Python's arbitrary precision
+    // integers), and the largest uint256
+    salt.copy_from_slice(
+        &hex::decode("59b7b8dd9e6e1f934c9c3def4a1eb69bc17ec9cc654d8eb9f2cc0000004bad05").unwrap(),
+    );
+    assert_eq!(
+        salt_to_decimal(&salt),
+        "40580452432925820000218051692770500264690053580341484834001847674384941821189"
+    );
+    assert_eq!(
+        salt_to_decimal(&[0xff; 32]),
+        "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+    );
+}
+
+#[test]
+fn attempt_numbers_never_go_backwards_on_a_single_thread() {
+    let args = [
+        "create2crunch",
+        "0x0000000000FFe8B47B3e2130213B802212439497",
+        CALLER,
+        "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392",
+        "0x0",
+        "--match-all",
+        "--attempt-number",
+        "--threads",
+        "1",
+        "--batch-size",
+        "2048",
+        "--max-segments",
+        "2",
+    ];
+    let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+    let found: Vec<FoundAddress> = search(config).collect();
+    let attempts: Vec<u64> = found.iter().map(|found| found.attempt.unwrap()).collect();
+
+    // counted across both segments, in the order the results were found
+    assert!(attempts.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert!(attempts.iter().any(|&attempt| attempt < 2048));
+    assert!(attempts.iter().any(|&attempt| attempt >= 2048));
+    assert!(attempts.iter().all(|&attempt| attempt < 4096));
+
+    let format = LineFormat {
+        attempt: true,
+        ..LineFormat::default()
+    };
+    assert!(format
+        .line(&found[0])
+        .ends_with(&format!(" (attempt {})", attempts[0])));
+}
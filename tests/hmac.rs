@@ -0,0 +1,36 @@
+//! Authentication of lines of output with `--hmac`.
+
+extern crate create2crunch;
+
+use create2crunch::HmacKey;
+
+const LINE: &str = "0x59b7b8dd9e6e1f934c9c3def4a1eb69bc17ec9ccc0b1668ef482000000000044 => \
+                    0xAa08af4AbD6D474C03b7135701cBe9E545303352 => 0";
+
+#[test]
+fn intact_lines_verify_and_tampered_lines_dont() {
+    let key = HmacKey::new(b"a shared secret");
+    let signed = key.sign_line(LINE);
+    assert!(signed.starts_with(LINE));
+    assert!(key.verify_line(&signed));
+
+    // changing any character of the line, or of its hmac, fails
+    for index in [2, 70, LINE.len() - 1, signed.len() - 2].iter().cloned() {
+        let mut tampered = signed.clone().into_bytes();
+        tampered[index] = if tampered[index] == b'1' { b'2' } else { b'1' };
+        let tampered = String::from_utf8(tampered).unwrap();
+        assert!(!key.verify_line(&tampered), "{}", tampered);
+    }
+
+    // as does checking with another key, or a line without an hmac
+    assert!(!HmacKey::new(b"another secret").verify_line(&signed));
+    assert!(!key.verify_line(LINE));
+}
+
+#[test]
+fn keys_longer_than_a_block_are_hashed_first() {
+    let long_key = [0xaa; 200];
+    let key = HmacKey::new(&long_key);
+    assert!(key.verify_line(&key.sign_line(LINE)));
+    assert!(!HmacKey::new(&long_key[..199]).verify_line(&key.sign_line(LINE)));
+}
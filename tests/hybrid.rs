@@ -0,0 +1,66 @@
+//! Searching on the CPU alongside another search (the GPU, with `--hybrid`),
+//! both writing to the same sink.
+
+extern crate create2crunch;
+
+use std::io;
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use create2crunch::{handle_solution, search_alongside, Config, FoundAddress, ResultSink};
+
+/// Holds every address recorded, in memory.
+#[derive(Default)]
+struct MemorySink(Mutex<Vec<FoundAddress>>);
+
+impl ResultSink for MemorySink {
+    fn record(&self, found: &FoundAddress) -> io::Result<()> {
+        self.0.lock().unwrap().push(found.clone());
+        Ok(())
+    }
+}
+
+#[test]
+fn both_searches_write_to_the_sink_and_stop_together() {
+    let args = [
+        "create2crunch",
+        "0x0000000000FFe8B47B3e2130213B802212439497",
+        "0x59b7B8Dd9E6e1F934C9c3Def4a1Eb69Bc17Ec9cc",
+        "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392",
+        "0x",
+        "--match-all",
+        "--fast-first",
+    ];
+    let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+    let sink = MemorySink::default();
+    let stop = AtomicBool::new(false);
+
+    // stands in for the GPU search: once the CPU has found something, it
+    // writes addresses of its own while the CPU carries on writing, then
+    // stops as the GPU search does on an error
+    let other_salt = [0xee; 32];
+    let other = || {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while sink.0.lock().unwrap().is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(1));
+        }
+        for _ in 0..100 {
+            sink.record(&handle_solution(&config, &other_salt).unwrap())?;
+        }
+        Err(io::Error::other("device lost"))
+    };
+
+    let result: io::Result<()> = search_alongside(&config, &sink, &stop, &|_| {}, other);
+    assert_eq!(result.unwrap_err().to_string(), "device lost");
+
+    // the CPU search was stopped along with the other one
+    let found = sink.0.into_inner().unwrap();
+    let from_other = found
+        .iter()
+        .filter(|found| found.salt == other_salt)
+        .count();
+    assert_eq!(from_other, 100);
+    assert!(found.len() > from_other);
+}
@@ -0,0 +1,77 @@
+//! The OpenCL source generated for a search, which bakes the search's inputs
+//! into the kernel as `#define`s. Generating it needs no device.
+#![cfg(feature = "gpu")]
+
+extern crate create2crunch;
+
+use create2crunch::{kernel_source, Config};
+
+fn config(target: &str) -> Config {
+    let args = [
+        "create2crunch",
+        "0x0000000000FFe8B47B3e2130213B802212439497",
+        "0x59b7B8Dd9E6e1F934C9c3Def4a1Eb69Bc17Ec9cc",
+        "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392",
+        target,
+        "--leading-zeroes",
+        "4",
+        "--total-zeroes",
+        "6",
+    ];
+    Config::new(args.iter().map(|arg| arg.to_string())).unwrap()
+}
+
+#[test]
+fn the_kernel_defines_each_byte_of_the_preimage_and_the_thresholds() {
+    let source = kernel_source(&config("0x"));
+    let defines: Vec<&str> = source
+        .lines()
+        .filter(|line| line.starts_with("#define S_"))
+        .collect();
+    // the factory, caller and init code hash; the nonce bytes in between are
+    // filled in by the kernel
+    assert_eq!(defines.len(), 20 + 20 + 32);
+
+    for line in [
+        // the factory
+        "#define S_1 0u",
+        "#define S_6 255u",
+        "#define S_20 151u",
+        // the caller
+        "#define S_21 89u",
+        "#define S_40 204u",
+        // the init code hash
+        "#define S_53 56u",
+        "#define S_84 146u",
+        "#define LEADING_ZEROES 4",
+        "#define TOTAL_ZEROES 6",
+    ]
+    .iter()
+    {
+        assert!(source.lines().any(|l| l == *line), "missing {}", line);
+    }
+}
+
+#[test]
+fn the_kernel_defines_the_bytes_of_the_target() {
+    let define = |source: &str, name: &str| {
+        let start = format!("#define {} ", name);
+        source
+            .lines()
+            .find(|line| line.starts_with(&start))
+            .unwrap_or_else(|| panic!("no {} in the kernel", name))[start.len()..]
+            .to_string()
+    };
+
+    // a whole number of bytes
+    let source = kernel_source(&config("0xdead"));
+    assert_eq!(define(&source, "PREFIX_LENGTH"), "2");
+    assert_eq!(define(&source, "PREFIX_BYTES"), "222u, 173u");
+    assert_eq!(define(&source, "PREFIX_LAST_MASK"), "255u");
+
+    // a lone final nibble only fixes the high nibble of its byte
+    let source = kernel_source(&config("0x00c0f"));
+    assert_eq!(define(&source, "PREFIX_LENGTH"), "3");
+    assert_eq!(define(&source, "PREFIX_BYTES"), "0u, 192u, 240u");
+    assert_eq!(define(&source, "PREFIX_LAST_MASK"), "240u");
+}
@@ -0,0 +1,59 @@
+//! Searching for addresses whose checksummed form sorts before a bound, and
+//! before every address recorded so far, with `--lex-before`.
+
+extern crate create2crunch;
+
+use create2crunch::{search, Config};
+
+fn config(target: &str, extra: &[&str]) -> Config {
+    let mut args = vec![
+        "create2crunch",
+        "0x0000000000FFe8B47B3e2130213B802212439497",
+        "0x59b7B8Dd9E6e1F934C9c3Def4a1Eb69Bc17Ec9cc",
+        "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392",
+        target,
+        "--deterministic",
+        "--batch-size",
+        "4096",
+        "--threads",
+        "1",
+    ];
+    args.extend_from_slice(extra);
+    Config::new(args.iter().map(|arg| arg.to_string())).unwrap()
+}
+
+#[test]
+fn each_address_recorded_sorts_before_the_last() {
+    let bound = "0x08";
+    let found: Vec<String> = search(config("0x0", &["--lex-before", bound]))
+        .map(|found| found.checksum_address)
+        .collect();
+    assert!(found.len() > 1);
+    assert!(found.iter().all(|address| address.starts_with("0x0")));
+    assert!(found.iter().all(|address| address.as_str() < bound));
+    assert!(found.windows(2).all(|pair| pair[1] < pair[0]));
+
+    // the last is the first in order of every match in the segment
+    let every: Vec<String> = search(config("0x0", &["--match-all"]))
+        .map(|found| found.checksum_address)
+        .collect();
+    assert_eq!(found.last(), every.iter().min());
+}
+
+#[test]
+fn the_comparison_is_on_the_checksummed_string() {
+    // digits sort before upper case letters, which sort before lower case
+    let first_characters = |bound: &str| {
+        search(config("0x", &["--lex-before", bound]))
+            .map(|found| found.checksum_address.as_bytes()[2])
+            .collect::<Vec<_>>()
+    };
+
+    let digits = first_characters("0xA");
+    assert!(!digits.is_empty());
+    assert!(digits.iter().all(u8::is_ascii_digit));
+
+    let upper = first_characters("0xa");
+    assert!(!upper.is_empty());
+    assert!(upper.iter().all(|c| !c.is_ascii_lowercase()));
+}
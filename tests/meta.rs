@@ -0,0 +1,53 @@
+//! Reproducing a run from the configuration recorded by `--run-meta`.
+
+extern crate create2crunch;
+
+use std::fs;
+use std::process;
+
+use create2crunch::{search, Config, RunMeta, RUN_META_FILE};
+
+#[test]
+fn run_meta_round_trips_into_a_config_that_reproduces_the_run() {
+    let args = [
+        "create2crunch",
+        "0x0000000000FFe8B47B3e2130213B802212439497",
+        "0x59b7b8dd9e6e1f934c9c3def4a1eb69bc17ec9cc,0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0",
+        "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392",
+        "0x00,0xff",
+        "--deterministic",
+        "--segment",
+        "0x0000000000ab",
+        "--batch-size",
+        "4096",
+        "--run-meta",
+    ];
+    let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+
+    let dir = std::env::temp_dir().join(format!("create2crunch-meta-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(RUN_META_FILE);
+    RunMeta::new(&config).write(&path).unwrap();
+    let meta = RunMeta::read(&path).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(meta.engine, "cpu");
+    let recorded = meta.config;
+    assert_eq!(recorded.factory_address, config.factory_address);
+    assert_eq!(recorded.calling_addresses, config.calling_addresses);
+    assert_eq!(recorded.init_code_hash, config.init_code_hash);
+    assert_eq!(recorded.extra_targets, config.extra_targets);
+    assert_eq!(recorded.segment, config.segment);
+    assert_eq!(recorded.target_selectivity(), config.target_selectivity());
+
+    // the recorded configuration searches the same salts and finds the same
+    // addresses
+    let original: Vec<_> = search(config)
+        .map(|found| (found.salt, found.address))
+        .collect();
+    let reproduced: Vec<_> = search(recorded)
+        .map(|found| (found.salt, found.address))
+        .collect();
+    assert!(!original.is_empty());
+    assert_eq!(original, reproduced);
+}
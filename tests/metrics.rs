@@ -0,0 +1,78 @@
+//! The metrics written for Prometheus with `--metrics-file`.
+
+extern crate create2crunch;
+
+use std::collections::HashMap;
+use std::fs;
+use std::process;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use create2crunch::Progress;
+
+/// Parse text in the Prometheus exposition format into the type and value of
+/// each metric, checking that each sample follows its `HELP` and `TYPE` lines.
+fn parse(text: &str) -> HashMap<String, (String, f64)> {
+    let valid_name = |name: &str| {
+        !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+    };
+
+    let mut metrics = HashMap::new();
+    let mut lines = text.lines();
+    while let Some(help) = lines.next() {
+        let mut help = help.splitn(4, ' ');
+        assert_eq!((help.next(), help.next()), (Some("#"), Some("HELP")));
+        let name = help.next().unwrap();
+        assert!(valid_name(name), "{}", name);
+        assert!(!help.next().unwrap_or_default().is_empty());
+
+        let kind = lines.next().unwrap();
+        let kind = kind
+            .strip_prefix(&format!("# TYPE {} ", name))
+            .unwrap_or_else(|| panic!("no TYPE for {}", name));
+        assert!(["counter", "gauge"].contains(&kind));
+
+        let sample = lines.next().unwrap();
+        let value = sample
+            .strip_prefix(&format!("{} ", name))
+            .unwrap_or_else(|| panic!("no sample for {}", name));
+        metrics.insert(name.to_string(), (kind.to_string(), value.parse().unwrap()));
+    }
+    assert!(text.ends_with('\n'));
+    metrics
+}
+
+#[test]
+fn the_exposition_is_valid_prometheus_text() {
+    let progress = Progress::default();
+    progress.count_attempts(3000);
+    progress.found.fetch_add(4, Ordering::Relaxed);
+
+    let text = progress.exposition(Duration::from_secs(2));
+    let metrics = parse(&text);
+    assert_eq!(metrics.len(), 3);
+    assert_eq!(
+        metrics["create2crunch_attempts_total"],
+        ("counter".to_string(), 3000.0)
+    );
+    assert_eq!(
+        metrics["create2crunch_found_total"],
+        ("counter".to_string(), 4.0)
+    );
+    assert_eq!(
+        metrics["create2crunch_attempts_per_second"],
+        ("gauge".to_string(), 1500.0)
+    );
+
+    // and is written as is
+    let path = std::env::temp_dir().join(format!("create2crunch-{}.prom", process::id()));
+    progress
+        .write_metrics(&path, Duration::from_secs(2))
+        .unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), text);
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,180 @@
+//! Matching addresses by the pattern of their nibbles: palindromes, runs of
+//! a repeated nibble, runs of increasing or decreasing bytes, forbidden
+//! characters, and custom predicates.
+
+extern crate create2crunch;
+extern crate hex;
+
+use create2crunch::{
+    handle_solution, is_palindrome, longest_repeat_run, search, Config, Direction, FoundAddress,
+    LineFormat, MonotoneBytes,
+};
+
+fn address(hex_address: &str) -> [u8; 20] {
+    let mut address = [0; 20];
+    address.copy_from_slice(&hex::decode(&hex_address[2..]).unwrap());
+    address
+}
+
+#[test]
+fn palindromes_read_the_same_backwards_by_nibble() {
+    assert!(is_palindrome(&address(
+        "0x1234567890abcdef00000000fedcba0987654321"
+    )));
+    assert!(!is_palindrome(&address(
+        "0x4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38"
+    )));
+}
+
+#[test]
+fn repeat_runs_are_counted_anywhere_in_the_address() {
+    // salt zero from the zero address, with keccak256(0x00) as the init code
+    // hash, deploys to `0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38` (EIP-1014)
+    let deployed = address("0x4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38");
+    assert_eq!(longest_repeat_run(&deployed), 4);
+    assert_eq!(longest_repeat_run(&[0x11; 20]), 40);
+
+    let config_with_run = |run: &str| {
+        let args = [
+            "create2crunch",
+            "0x0000000000000000000000000000000000000000",
+            "0x0000000000000000000000000000000000000000",
+            "0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+            "0x",
+            "--min-repeat-run",
+            run,
+        ];
+        Config::new(args.iter().map(|arg| arg.to_string())).unwrap()
+    };
+    assert!(handle_solution(&config_with_run("4"), &[0; 32]).is_some());
+    assert!(handle_solution(&config_with_run("5"), &[0; 32]).is_none());
+}
+
+#[test]
+fn monotone_runs_are_counted_from_the_first_byte() {
+    let increasing = |len| MonotoneBytes {
+        len,
+        direction: Direction::Increasing,
+    };
+    let decreasing = |len| MonotoneBytes {
+        len,
+        direction: Direction::Decreasing,
+    };
+
+    let rising = address("0x0415a3f7f700000000000000000000000000ffee");
+    assert_eq!(increasing(4).run_length(&rising), 4);
+    assert!(increasing(4).matches(&rising));
+    // a repeated byte ends the run
+    assert!(!increasing(5).matches(&rising));
+    assert_eq!(decreasing(2).run_length(&rising), 1);
+    assert!(!decreasing(2).matches(&rising));
+
+    let falling = address("0xfedcba9876543210fedcba9876543210fedcba98");
+    assert_eq!(decreasing(2).run_length(&falling), 8);
+    assert!(decreasing(8).matches(&falling));
+    assert!(!decreasing(9).matches(&falling));
+
+    let mut whole = [0; 20];
+    for (i, byte) in whole.iter_mut().enumerate() {
+        *byte = i as u8 * 10;
+    }
+    assert_eq!(increasing(20).run_length(&whole), 20);
+
+    // the run is reported alongside each result
+    let format = LineFormat {
+        monotone: Some(increasing(4)),
+        ..LineFormat::default()
+    };
+    let line = format.line(&FoundAddress::new([0; 32], rising));
+    assert!(line.ends_with(" (run 4)"), "{}", line);
+
+    // and applies on top of the target, e.g. to the EIP-1014 address
+    // `0x4D1A2e2b...`, whose first two bytes fall
+    let config_with_run = |direction: &str, len: &str| {
+        let args = [
+            "create2crunch",
+            "0x0000000000000000000000000000000000000000",
+            "0x0000000000000000000000000000000000000000",
+            "0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+            "0x4d",
+            direction,
+            len,
+        ];
+        Config::new(args.iter().map(|arg| arg.to_string())).unwrap()
+    };
+    assert!(handle_solution(&config_with_run("--decreasing", "2"), &[0; 32]).is_some());
+    assert!(handle_solution(&config_with_run("--decreasing", "3"), &[0; 32]).is_none());
+    assert!(handle_solution(&config_with_run("--increasing", "2"), &[0; 32]).is_none());
+}
+
+#[test]
+fn custom_predicates_filter_the_matches() {
+    let args = [
+        "create2crunch",
+        "0x0000000000FFe8B47B3e2130213B802212439497",
+        "0x59b7B8Dd9E6e1F934C9c3Def4a1Eb69Bc17Ec9cc",
+        "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392",
+        "0xa",
+        "--match-all",
+        "--deterministic",
+        "--batch-size",
+        "4096",
+    ];
+    let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+    let ends_low = |address: &[u8; 20]| address[19] < 0x80;
+    let filtered = config.clone().with_predicate(ends_low);
+
+    // the predicate applies on top of the target, to the same salts
+    let (mut expected, rejected): (Vec<_>, Vec<_>) =
+        search(config).partition(|found| ends_low(&found.address));
+    let mut found: Vec<_> = search(filtered.clone()).map(|found| found.salt).collect();
+    let mut expected: Vec<_> = expected.drain(..).map(|found| found.salt).collect();
+    found.sort();
+    expected.sort();
+    assert!(!found.is_empty() && !rejected.is_empty());
+    assert_eq!(found, expected);
+
+    // and to a salt checked on its own
+    assert!(handle_solution(&filtered, &found[0]).is_some());
+    assert!(handle_solution(&filtered, &rejected[0].salt).is_none());
+}
+
+#[test]
+fn forbidden_characters_are_kept_out_of_the_matches() {
+    let config_with = |flags: &[&str]| {
+        let args = [
+            "create2crunch",
+            "0x0000000000FFe8B47B3e2130213B802212439497",
+            "0x59b7B8Dd9E6e1F934C9c3Def4a1Eb69Bc17Ec9cc",
+            "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392",
+            "0xa",
+            "--match-all",
+            "--deterministic",
+            "--batch-size",
+            "4096",
+        ];
+        Config::new(args.iter().chain(flags).map(|arg| arg.to_string())).unwrap()
+    };
+
+    // the same salts, less those whose checksummed address has a `B` or `8`
+    let allowed = |found: &FoundAddress| !found.checksum_address[2..].contains(['B', '8']);
+    let (mut expected, rejected): (Vec<_>, Vec<_>) = search(config_with(&[])).partition(allowed);
+    let mut found: Vec<_> = search(config_with(&["--forbid", "B8"])).collect();
+    assert!(!found.is_empty() && !rejected.is_empty());
+    assert!(found.iter().all(allowed));
+    found.sort_by_key(|found| found.salt);
+    expected.sort_by_key(|found| found.salt);
+    assert_eq!(
+        found.iter().map(|found| found.salt).collect::<Vec<_>>(),
+        expected.iter().map(|found| found.salt).collect::<Vec<_>>()
+    );
+
+    // a letter is only forbidden in the case given
+    let lowercase_b = search(config_with(&["--forbid", "b"])).collect::<Vec<_>>();
+    assert!(lowercase_b
+        .iter()
+        .all(|found| !found.checksum_address[2..].contains('b')));
+    assert!(lowercase_b
+        .iter()
+        .any(|found| found.checksum_address.contains('B')));
+}
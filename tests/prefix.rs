@@ -0,0 +1,218 @@
+//! Matching of targets by nibble, for both even and odd target lengths, with
+//! wildcards, against a rotated address or one rendered in another base, and
+//! of several targets at once, with and without regard to case, and the bits
+//! of the address each kind of target fixes.
+
+extern crate create2crunch;
+
+use create2crunch::{handle_solution, search_once, simulate, Base, Config, PREVIEW_MAX_ATTEMPTS};
+
+/// Salt zero from the zero address, with keccak256(0x00) as the init code
+/// hash, deploys to `0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38` (EIP-1014).
+fn config_for(target: &str) -> Config {
+    config_with(target, &[])
+}
+
+fn config_with(target: &str, flags: &[&str]) -> Config {
+    let args = [
+        "create2crunch",
+        "0x0000000000000000000000000000000000000000",
+        "0x0000000000000000000000000000000000000000",
+        "0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+        target,
+        "--match-all",
+    ];
+    Config::new(args.iter().chain(flags).map(|arg| arg.to_string())).unwrap()
+}
+
+#[test]
+fn even_length_targets_match_whole_bytes() {
+    assert!(handle_solution(&config_for("0x4D"), &[0; 32]).is_some());
+    assert!(handle_solution(&config_for("0x4D1A"), &[0; 32]).is_some());
+    assert!(handle_solution(&config_for("0x4D1B"), &[0; 32]).is_none());
+}
+
+#[test]
+fn odd_length_targets_match_the_high_nibble_of_the_last_byte() {
+    assert!(handle_solution(&config_for("0x4"), &[0; 32]).is_some());
+    assert!(handle_solution(&config_for("0x4D1"), &[0; 32]).is_some());
+    assert!(handle_solution(&config_for("0x4D1A2"), &[0; 32]).is_some());
+    assert!(handle_solution(&config_for("0x4D2"), &[0; 32]).is_none());
+    assert!(handle_solution(&config_for("0x4D1A3"), &[0; 32]).is_none());
+}
+
+#[test]
+fn odd_length_targets_are_found_by_searching() {
+    let found = search_once(&config_for("0xabc"), PREVIEW_MAX_ATTEMPTS).unwrap();
+    assert!(found.checksum_address.to_lowercase().starts_with("0xabc"));
+}
+
+#[test]
+fn wildcards_match_any_nibble() {
+    assert!(handle_solution(&config_for("0x4?1A"), &[0; 32]).is_some());
+    assert!(handle_solution(&config_for("0x??1a2e?"), &[0; 32]).is_some());
+    assert!(handle_solution(&config_for("0x4D1A2e?bB4F8"), &[0; 32]).is_some());
+    assert!(handle_solution(&config_for("0x?D1B"), &[0; 32]).is_none());
+
+    let config = config_with("0x4?1a", &["--case-sensitive"]);
+    assert!(handle_solution(&config, &[0; 32]).is_none());
+    assert_eq!(config.target_selectivity(), 13);
+}
+
+#[test]
+fn any_of_several_targets_matches_and_is_noted() {
+    let found = handle_solution(&config_for("0xdead,0x4D1A,0x4D"), &[0; 32]).unwrap();
+    assert_eq!(found.target.as_deref(), Some("0x4D1A"));
+    assert!(handle_solution(&config_for("0xdead,0x4D1B"), &[0; 32]).is_none());
+
+    // with a single target there's nothing to note
+    assert_eq!(
+        handle_solution(&config_for("0x4D"), &[0; 32])
+            .unwrap()
+            .target,
+        None
+    );
+}
+
+#[test]
+fn letters_match_either_case_unless_case_sensitive() {
+    assert!(handle_solution(&config_for("0x4d1a2E"), &[0; 32]).is_some());
+    assert!(handle_solution(&config_with("0x4D1A2e", &["--case-sensitive"]), &[0; 32]).is_some());
+    assert!(handle_solution(&config_with("0x4d1a2E", &["--case-sensitive"]), &[0; 32]).is_none());
+}
+
+#[test]
+fn rotations_match_a_later_byte_and_keep_the_address_canonical() {
+    let checksummed = "0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38";
+    let rotated = |target: &str, flags: &[&str]| {
+        let mut all = vec!["--rotation", "2"];
+        all.extend_from_slice(flags);
+        handle_solution(&config_with(target, &all), &[0; 32])
+    };
+
+    // rotated left by two bytes, the address starts with `2e2bB4F8`
+    assert!(rotated("0x4D1A", &[]).is_none());
+    assert!(rotated("0x2e2bB4", &["--case-sensitive"]).is_some());
+    assert!(rotated("0x2e2bb4", &["--case-sensitive"]).is_none());
+
+    // and by nineteen, with the last byte first
+    let wrapped = handle_solution(&config_with("0x384D", &["--rotation", "19"]), &[0; 32]);
+    assert!(wrapped.is_some());
+
+    // the address is still recorded in its usual, unrotated form
+    let found = rotated("0x2e2b", &[]).unwrap();
+    assert_eq!(found.checksum_address, checksummed);
+    assert_eq!(found.address, wrapped.unwrap().address);
+    assert_eq!(found.to_string().split(" => ").nth(1), Some(checksummed));
+}
+
+#[test]
+fn encoded_prefixes_match_the_address_rendered_in_that_base() {
+    let deployed = [
+        0x4d, 0x1a, 0x2e, 0x2b, 0xb4, 0xf8, 0x8f, 0x02, 0x50, 0xf2, 0x6f, 0xff, 0xf0, 0x98, 0xb0,
+        0xb3, 0x0b, 0x26, 0xbf, 0x38,
+    ];
+    assert_eq!(
+        Base::Base58.encode(&deployed),
+        "25JVKYhytZAZ5u9RjVmwrEVieWGf"
+    );
+    assert_eq!(
+        Base::Base32.encode(&deployed),
+        "JUNC4K5U7CHQEUHSN777BGFQWMFSNPZY"
+    );
+    // each leading zero byte is a `1` in base58
+    let mut leading_zeroes = [0xff; 20];
+    leading_zeroes[..2].copy_from_slice(&[0, 0]);
+    assert_eq!(
+        Base::Base58.encode(&leading_zeroes),
+        "11BcrMA6SqZZvEpAezV9QmfHqhG"
+    );
+
+    let encoded = |target: &str, base: &str, prefix: &str| {
+        handle_solution(&config_with(target, &[base, prefix]), &[0; 32])
+    };
+    assert!(encoded("0x4", "--base58", "25JVK").is_some());
+    assert!(encoded("0x4", "--base58", "25JVL").is_none());
+    assert!(encoded("0x4", "--base32", "JUNC4").is_some());
+    // the hex target still applies as well
+    assert!(encoded("0x5", "--base58", "25JVK").is_none());
+}
+
+#[test]
+fn a_checksummed_full_address_target_is_matched_case_sensitively() {
+    let checksummed = "0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38";
+    let lowercase = checksummed.to_lowercase();
+    let miscased = "0x4d1A2e2bB4F88F0250f26Ffff098B0b30B26BF38";
+
+    // a pasted full address in mixed case makes the search case-sensitive,
+    // so only the casing the checksum actually gives matches
+    for target in &[checksummed, miscased] {
+        assert!(config_for(target).case_sensitive, "{}", target);
+    }
+    assert!(handle_solution(&config_for(checksummed), &[0; 32]).is_some());
+    assert!(handle_solution(&config_for(miscased), &[0; 32]).is_none());
+
+    // while one in a single case, or a short prefix, matches either case
+    for target in &[&lowercase, "0x4d1A"] {
+        let config = config_for(target);
+        assert!(!config.case_sensitive, "{}", target);
+        assert!(handle_solution(&config, &[0; 32]).is_some(), "{}", target);
+    }
+
+    // and the flag still forces it on
+    let case_sensitive = |target: &str| config_with(target, &["--case-sensitive"]);
+    assert!(case_sensitive(&lowercase).case_sensitive);
+    assert!(handle_solution(&case_sensitive(&lowercase), &[0; 32]).is_none());
+    assert!(handle_solution(&case_sensitive("0x4D1A"), &[0; 32]).is_some());
+    assert!(handle_solution(&case_sensitive("0x4d1A"), &[0; 32]).is_none());
+}
+
+#[test]
+fn target_selectivity_counts_the_bits_each_criterion_fixes() {
+    let bits = |target: &str, flags: &[&str]| config_with(target, flags).target_selectivity();
+
+    // four bits a nibble, plus a bit a letter when its case must match
+    assert_eq!(bits("0xdead", &[]), 16);
+    assert_eq!(bits("0x00a0", &["--case-sensitive"]), 17);
+    assert_eq!(bits("0xdead", &["--case-sensitive"]), 20);
+
+    // a wildcard fixes nothing, and a suffix adds to the target
+    assert_eq!(bits("0xde?d", &[]), 12);
+    assert_eq!(bits("0xdead", &["--suffix", "0xbeef"]), 32);
+
+    // either of two targets matches twice as often as one
+    assert_eq!(bits("0xdead,0xbeef", &[]), 15);
+
+    assert_eq!(bits("0x00", &["--min-leading-zero-nibbles", "4"]), 24);
+}
+
+#[test]
+fn simulated_counts_match_the_odds_of_each_target() {
+    let attempts = 0x8000;
+    let targets: Vec<String> = ["0x0", "0xab", "0x00?", "0xAb"]
+        .iter()
+        .map(|target| target.to_string())
+        .collect();
+
+    for &case_sensitive in [false, true].iter() {
+        let config = if case_sensitive {
+            config_with("0x", &["--case-sensitive"])
+        } else {
+            config_for("0x")
+        };
+        let counts = simulate(&config, &targets, attempts);
+
+        for (target, count) in targets.iter().zip(counts) {
+            // within five standard deviations of the expected count
+            let expected = attempts as f64 / 2f64.powi(config.bits_of(target) as i32);
+            let margin = 5.0 * expected.sqrt();
+            assert!(
+                (count as f64 - expected).abs() < margin,
+                "{} matched {} times, expected {}",
+                target,
+                count,
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! How an address meeting several criteria at once is scored and labeled.
+
+extern crate create2crunch;
+
+use create2crunch::{handle_solution, Config, FoundAddress, LEADING_ZERO_WEIGHT};
+
+/// An address with the given zero bytes, all others `0x11`.
+fn address_with_zeros(zeros: &[usize]) -> FoundAddress {
+    let mut address = [0x11; 20];
+    for &i in zeros {
+        address[i] = 0;
+    }
+    FoundAddress::new([0; 32], address)
+}
+
+#[test]
+fn leading_zero_bytes_take_precedence_over_total_zero_bytes() {
+    let leading = address_with_zeros(&[0]);
+    let scattered = address_with_zeros(&(1..20).collect::<Vec<_>>());
+    assert_eq!(leading.score(), LEADING_ZERO_WEIGHT + 1);
+    assert_eq!(scattered.score(), 19);
+    assert!(leading.score() > scattered.score());
+
+    // with as many leading zero bytes, more zero bytes in all ranks higher
+    let more = address_with_zeros(&[0, 1, 7, 12]);
+    let fewer = address_with_zeros(&[0, 1, 7]);
+    assert_eq!(more.score(), 2 * LEADING_ZERO_WEIGHT + 4);
+    assert!(more.score() > fewer.score());
+}
+
+/// Salt zero from the zero address, with keccak256(0x00) as the init code
+/// hash, deploys to `0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38` (EIP-1014).
+fn found_with(targets: &str, flags: &[&str]) -> FoundAddress {
+    let args = [
+        "create2crunch",
+        "0x0000000000000000000000000000000000000000",
+        "0x0000000000000000000000000000000000000000",
+        "0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+        targets,
+    ];
+    let config = Config::new(args.iter().chain(flags).map(|arg| arg.to_string())).unwrap();
+    handle_solution(&config, &[0; 32]).unwrap()
+}
+
+#[test]
+fn an_address_matching_several_targets_is_labeled_with_the_first_listed() {
+    assert_eq!(
+        found_with("0x4d,0x4d1a", &[]).target.as_deref(),
+        Some("0x4d")
+    );
+    assert_eq!(
+        found_with("0x4d1a,0x4d", &[]).target.as_deref(),
+        Some("0x4d1a")
+    );
+    assert_eq!(
+        found_with("0xdead,0x4d1a", &[]).target.as_deref(),
+        Some("0x4d1a")
+    );
+
+    // a single target (with or without a suffix) needs no label
+    assert_eq!(found_with("0x4d", &["--suffix", "0xbf38"]).target, None);
+}
+
+#[test]
+fn the_score_is_the_same_whichever_criteria_matched() {
+    let scores = [
+        found_with("0x4d", &[]).score(),
+        found_with("0x4d1a,0x4d", &[]).score(),
+        found_with("0x4d", &["--suffix", "0xbf38"]).score(),
+        found_with("0x", &["--match-all"]).score(),
+    ];
+    // `0x4D1A...` has no zero bytes at all
+    assert!(scores.iter().all(|&score| score == 0));
+}
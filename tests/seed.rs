@@ -0,0 +1,29 @@
+//! Reproducible searches with `--seed`.
+
+extern crate create2crunch;
+
+use create2crunch::{search_once, Config, PREVIEW_MAX_ATTEMPTS};
+
+fn config_with_seed(seed: &str) -> Config {
+    let args = [
+        "create2crunch",
+        "0x0000000000000000000000000000000000000000",
+        "0x0000000000000000000000000000000000000000",
+        "0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+        "0x000",
+        "--seed",
+        seed,
+    ];
+    Config::new(args.iter().map(|arg| arg.to_string())).unwrap()
+}
+
+#[test]
+fn the_same_seed_searches_the_same_salts() {
+    let first = search_once(&config_with_seed("42"), PREVIEW_MAX_ATTEMPTS).unwrap();
+    let second = search_once(&config_with_seed("42"), PREVIEW_MAX_ATTEMPTS).unwrap();
+    let other = search_once(&config_with_seed("43"), PREVIEW_MAX_ATTEMPTS).unwrap();
+
+    // the nonces are searched in parallel, so compare the random segments
+    assert_eq!(first.salt[20..26], second.salt[20..26]);
+    assert_ne!(first.salt[20..26], other.salt[20..26]);
+}
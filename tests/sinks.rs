@@ -0,0 +1,209 @@
+//! Where the result sinks write found addresses, checked against the files
+//! they leave in a scratch directory.
+
+extern crate create2crunch;
+
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use create2crunch::{
+    BestSink, DedupSink, FileSink, FoundAddress, LineFormat, ResultSink, ScoreRoutedSink,
+    SortedSink,
+};
+
+/// A fresh scratch directory for the named test.
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("create2crunch-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A found address starting with the given number of zero bytes, told apart
+/// from others by `id` (its last byte, and the last byte of its salt).
+fn found(leading_zero_bytes: usize, id: u8) -> FoundAddress {
+    let mut address = [0x11; 20];
+    for byte in &mut address[..leading_zero_bytes] {
+        *byte = 0;
+    }
+    address[19] = id;
+    let mut salt = [0; 32];
+    salt[31] = id;
+    FoundAddress::new(salt, address)
+}
+
+/// The lines of a file, or none if it doesn't exist.
+fn lines(path: &PathBuf) -> Vec<String> {
+    fs::read_to_string(path)
+        .map(|contents| contents.lines().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn score_routed_results_land_in_the_file_for_their_score() {
+    let dir = scratch("routed");
+    let sink = ScoreRoutedSink::new(&dir, LineFormat::default());
+    for found in &[found(2, 1), found(3, 2), found(2, 3), found(0, 4)] {
+        sink.record(found).unwrap();
+    }
+
+    let salts_in = |leading_zero_bytes: u8| -> Vec<u8> {
+        lines(&sink.path_for(leading_zero_bytes))
+            .iter()
+            .map(|line| u8::from_str_radix(&line[64..66], 16).unwrap())
+            .collect()
+    };
+    assert_eq!(salts_in(0), vec![4]);
+    assert_eq!(salts_in(2), vec![1, 3]);
+    assert_eq!(salts_in(3), vec![2]);
+
+    // files are only created for the scores actually found
+    assert!(!sink.path_for(1).exists());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn rotated_files_hold_every_line_between_them() {
+    let dir = scratch("rotate");
+    let path = dir.join("efficient_addresses.txt");
+    let sink = FileSink::open(&path, LineFormat::default())
+        .unwrap()
+        .rotate_at(300);
+    for id in 0..20 {
+        sink.record(&found(1, id)).unwrap();
+    }
+    drop(sink);
+
+    // the full files were moved aside with the time (and, within the same
+    // second, a number) appended
+    let files: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert!(files.len() > 2);
+    assert!(files.contains(&path));
+
+    let mut ids = Vec::new();
+    for file in &files {
+        let lines = lines(file);
+        if *file != path {
+            // rotated just after the line that took it over the size
+            assert!(fs::metadata(file).unwrap().len() > 300);
+            assert!(lines.len() <= 3);
+        }
+        ids.extend(
+            lines
+                .iter()
+                .map(|line| u8::from_str_radix(&line[64..66], 16).unwrap()),
+        );
+    }
+
+    // no line was lost or repeated
+    ids.sort();
+    assert_eq!(ids, (0..20).collect::<Vec<_>>());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn deduplicated_addresses_are_skipped_whatever_the_salt() {
+    let dir = scratch("dedup");
+    let path = dir.join("efficient_addresses.txt");
+    FileSink::open(&path, LineFormat::default())
+        .unwrap()
+        .record(&found(1, 1))
+        .unwrap();
+
+    let sink = DedupSink::new(FileSink::open(&path, LineFormat::default()).unwrap())
+        .load(&path)
+        .unwrap();
+
+    // the address from the earlier run, reached via a different salt
+    let mut again = found(1, 1);
+    again.salt[0] = 0xff;
+    sink.record(&again).unwrap();
+
+    // a new address is written once, however it's reached
+    sink.record(&found(1, 2)).unwrap();
+    let mut again = found(1, 2);
+    again.salt[0] = 0xff;
+    sink.record(&again).unwrap();
+
+    let salts: Vec<u8> = lines(&path)
+        .iter()
+        .map(|line| u8::from_str_radix(&line[64..66], 16).unwrap())
+        .collect();
+    assert_eq!(salts, vec![1, 2]);
+    // each with the salt it was first reached by
+    assert!(lines(&path).iter().all(|line| line.starts_with("0x00")));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn sorted_results_are_written_best_first_on_shutdown() {
+    let dir = scratch("sorted");
+    let path = dir.join("efficient_addresses.txt");
+    let sink = SortedSink::new(FileSink::open(&path, LineFormat::default()).unwrap(), 100);
+    for (id, &leading_zero_bytes) in [1, 4, 0, 2, 4, 3].iter().enumerate() {
+        sink.record(&found(leading_zero_bytes, id as u8)).unwrap();
+    }
+
+    // nothing is written until the search stops
+    assert!(lines(&path).is_empty());
+    sink.flush().unwrap();
+
+    let leading_zero_bytes: Vec<usize> = lines(&path)
+        .iter()
+        .map(|line| {
+            let address = line.split(" => ").nth(1).unwrap();
+            address[2..].find(|c| c != '0').unwrap() / 2
+        })
+        .collect();
+    assert_eq!(leading_zero_bytes, vec![4, 4, 3, 2, 1, 0]);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn the_best_file_converges_on_the_best_results() {
+    let dir = scratch("best");
+    let path = dir.join("efficient_addresses.txt");
+    let scores_in = |path: &PathBuf| -> Vec<u32> {
+        lines(path)
+            .iter()
+            .map(|line| line.split(" => ").nth(2).unwrap().parse().unwrap())
+            .collect()
+    };
+
+    // candidates of many scores, in no particular order
+    let candidates: Vec<FoundAddress> = (0..120u32)
+        .map(|i| ((i * 37) % 120) as u8)
+        .map(|id| {
+            let mut found = found(usize::from(id % 5), id);
+            if id % 3 == 0 {
+                found.address[10] = 0;
+                found = FoundAddress::new(found.salt, found.address);
+            }
+            found
+        })
+        .collect();
+    let mut expected: Vec<u32> = candidates.iter().map(FoundAddress::score).collect();
+    expected.sort_by(|a, b| b.cmp(a));
+    expected.truncate(6);
+
+    let sink = BestSink::open(&path, LineFormat::default(), 6).unwrap();
+    for found in &candidates {
+        sink.record(found).unwrap();
+    }
+    assert_eq!(scores_in(&path), expected);
+
+    // a later run picks up where it left off, only admitting better results
+    let sink = BestSink::open(&path, LineFormat::default(), 6).unwrap();
+    sink.record(&found(1, 200)).unwrap();
+    assert_eq!(scores_in(&path), expected);
+    let better = found(6, 201);
+    sink.record(&better).unwrap();
+    let scores = scores_in(&path);
+    assert_eq!(scores[0], better.score());
+    assert_eq!(scores[1..], expected[..5]);
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,44 @@
+//! Checkpointing the search with `--resume`.
+
+extern crate create2crunch;
+
+use std::fs;
+use std::process;
+
+use create2crunch::SearchState;
+
+#[test]
+fn checkpoints_replace_the_state_file_whole() {
+    let dir = std::env::temp_dir().join(format!("create2crunch-state-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("search.state");
+
+    let mut state = SearchState {
+        factory_address: [0x11; 20],
+        calling_addresses: vec![[0x22; 20], [0x33; 20]],
+        init_code_hash: [0x44; 32],
+        segments: vec![[0x55; 6], [0x66; 6]],
+        next_nonce: 1 << 40,
+        segments_searched: 3,
+        attempts_before: 1 << 50,
+    };
+    state.write(&path).unwrap();
+    assert_eq!(SearchState::read(&path).unwrap(), Some(state.clone()));
+
+    // a shorter checkpoint leaves nothing of the longer one behind
+    state.calling_addresses.truncate(1);
+    state.segments.truncate(1);
+    state.next_nonce = 0;
+    state.write(&path).unwrap();
+    assert_eq!(SearchState::read(&path).unwrap(), Some(state));
+
+    // and only the state file itself is left
+    let files: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(files, vec!["search.state"]);
+    assert_eq!(SearchState::read(dir.join("missing.state")).unwrap(), None);
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,159 @@
+//! The zero byte (and zero nibble) thresholds, as applied on the CPU to a
+//! bare `0x` target, and the improvements-only safeguard for targets that
+//! match nearly everything.
+
+extern crate create2crunch;
+extern crate hex;
+
+use std::collections::HashSet;
+
+use create2crunch::{estimate_attempts, handle_solution, leading_zero_nibbles, search, Config};
+
+/// This salt deploys to `0x00001db6676cfF53193cD8a825000a46660a6aA1`, with
+/// two leading and three total zero bytes.
+const SALT: &str = "59b7b8dd9e6e1f934c9c3def4a1eb69bc17ec9cc654d8eb9f2cc0000004bad05";
+
+fn matches_with(flags: &[&str]) -> bool {
+    let mut args = vec![
+        "create2crunch",
+        "0x59b7B8Dd9E6e1F934C9c3Def4a1Eb69Bc17Ec9cc",
+        "0x0000000000000000000000000000000000000000",
+        "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392",
+        "0x",
+    ];
+    args.extend_from_slice(flags);
+    let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+
+    let mut salt = [0; 32];
+    salt.copy_from_slice(&hex::decode(SALT).unwrap());
+    handle_solution(&config, &salt).is_some()
+}
+
+#[test]
+fn addresses_must_meet_either_threshold() {
+    // the defaults are 3 leading or 5 total zero bytes
+    assert!(!matches_with(&[]));
+    assert!(matches_with(&["--leading-zeroes", "2"]));
+    assert!(matches_with(&["--total-zeroes", "3"]));
+    assert!(!matches_with(&[
+        "--leading-zeroes",
+        "3",
+        "--total-zeroes",
+        "4"
+    ]));
+}
+
+#[test]
+fn match_all_ignores_the_thresholds() {
+    assert!(matches_with(&["--match-all"]));
+}
+
+fn search_bare_target(flags: &[&str]) -> (Config, usize) {
+    let mut args = vec![
+        "create2crunch",
+        "0x59b7B8Dd9E6e1F934C9c3Def4a1Eb69Bc17Ec9cc",
+        "0x0000000000000000000000000000000000000000",
+        "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392",
+        "0x",
+        "--deterministic",
+        "--batch-size",
+        "4096",
+    ];
+    args.extend_from_slice(flags);
+    let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+    let found = search(config.clone()).collect::<Vec<_>>().len();
+    (config, found)
+}
+
+#[test]
+fn a_bare_target_does_not_match_everything_by_default() {
+    // with nothing else to match on, only gas-efficient addresses count, and
+    // none of these few salts comes close
+    let (config, found) = search_bare_target(&[]);
+    assert!(config.zero_thresholds);
+    assert_eq!(found, 0);
+}
+
+#[test]
+fn a_bare_target_matches_everything_with_match_all() {
+    let (config, found) = search_bare_target(&["--match-all"]);
+    assert!(!config.zero_thresholds);
+    assert_eq!(found, 4096);
+}
+
+#[test]
+fn leading_zero_nibbles_replace_the_thresholds() {
+    // `0x00001d...` starts with four zero nibbles
+    assert!(matches_with(&["--min-leading-zero-nibbles", "4"]));
+    assert!(!matches_with(&["--min-leading-zero-nibbles", "5"]));
+}
+
+#[test]
+fn a_zero_high_nibble_counts_as_half_a_byte() {
+    let with_start = |start: &[u8]| {
+        let mut address = [0x11; 20];
+        address[..start.len()].copy_from_slice(start);
+        leading_zero_nibbles(&address)
+    };
+
+    // `0x0X...`, for any X
+    assert_eq!(with_start(&[0x0f]), 1);
+    assert_eq!(with_start(&[0x01]), 1);
+    assert_eq!(with_start(&[0x00, 0x0a]), 3);
+    // `0xX0...` and `0x00X0...` stop at X
+    assert_eq!(with_start(&[0x10]), 0);
+    assert_eq!(with_start(&[0xf0]), 0);
+    assert_eq!(with_start(&[0x00, 0x10]), 2);
+    // up to the last nibble
+    assert_eq!(with_start(&[0; 19]), 38);
+    assert_eq!(with_start(&[[0; 19].as_ref(), &[0x01]].concat()), 39);
+    assert_eq!(leading_zero_nibbles(&[0; 20]), 40);
+}
+
+#[test]
+fn attempts_are_estimated_from_the_target_or_the_thresholds() {
+    let config_for = |target: &str| {
+        let args = [
+            "create2crunch",
+            "0x59b7B8Dd9E6e1F934C9c3Def4a1Eb69Bc17Ec9cc",
+            "0x0000000000000000000000000000000000000000",
+            "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392",
+            target,
+        ];
+        Config::new(args.iter().map(|arg| arg.to_string())).unwrap()
+    };
+
+    // eight nibbles, in either case
+    assert_eq!(estimate_attempts(&config_for("0xdeadbeef")), 2f64.powi(32));
+
+    // 3 leading zero bytes (1 in 2^24) or 5 zero bytes (about 1 in 2^26)
+    assert_eq!(config_for("0x").target_selectivity(), 24);
+    let attempts = estimate_attempts(&config_for("0x"));
+    assert!(attempts > 13.5e6 && attempts < 13.8e6, "{}", attempts);
+}
+
+#[test]
+fn very_short_targets_only_record_improvements() {
+    let args = [
+        "create2crunch",
+        "0x59b7B8Dd9E6e1F934C9c3Def4a1Eb69Bc17Ec9cc",
+        "0x0000000000000000000000000000000000000000",
+        "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392",
+        "0x0",
+        "--deterministic",
+        "--batch-size",
+        "65536",
+    ];
+    let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+    assert!(config.improvements_only);
+
+    // about 4,000 of these salts match, but only a handful improve on the
+    // best so far, and no score is written twice
+    let found: Vec<_> = search(config).collect();
+    let scores: HashSet<_> = found.iter().map(|found| found.score()).collect();
+    assert!(!found.is_empty() && found.len() < 64, "{}", found.len());
+    assert_eq!(scores.len(), found.len());
+    assert!(found
+        .iter()
+        .all(|found| found.checksum_address.starts_with("0x0")));
+}
@@ -0,0 +1,582 @@
+//! Known CREATE2 derivations, checked against every way the crate derives an
+//! address. These anchor any change to the hashing (engines, single-block
+//! keccak, truncation) against ground truth.
+
+extern crate create2crunch;
+extern crate hex;
+extern crate rand;
+extern crate tiny_keccak;
+
+use rand::Rng;
+use tiny_keccak::{Hasher, Keccak};
+
+use create2crunch::{
+    create2_address, create_address, derive_batch, handle_solution, init_code_hash, preimage_for,
+    salt_nonce_for, search, to_checksum_address, verify, Checksummer, Config, KeccakEngine,
+    NonceOrder, PREIMAGE_LENGTH,
+};
+
+/// A deployer, salt and init code hash, along with the checksummed address
+/// CREATE2 deploys to.
+struct Vector {
+    factory: &'static str,
+    salt: &'static str,
+    init_code_hash: &'static str,
+    address: &'static str,
+}
+
+const VECTORS: &[Vector] = &[
+    // the examples from EIP-1014 (the first is the zero-caller case)
+    Vector {
+        factory: "0x0000000000000000000000000000000000000000",
+        salt: "0x0000000000000000000000000000000000000000000000000000000000000000",
+        // keccak256(0x00)
+        init_code_hash: "0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+        address: "0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38",
+    },
+    Vector {
+        factory: "0xdeadbeef00000000000000000000000000000000",
+        salt: "0x0000000000000000000000000000000000000000000000000000000000000000",
+        // keccak256(0x00)
+        init_code_hash: "0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+        address: "0xB928f69Bb1D91Cd65274e3c79d8986362984fDA3",
+    },
+    Vector {
+        factory: "0xdeadbeef00000000000000000000000000000000",
+        salt: "0x000000000000000000000000feed000000000000000000000000000000000000",
+        // keccak256(0x00)
+        init_code_hash: "0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+        address: "0xD04116cDd17beBE565EB2422F2497E06cC1C9833",
+    },
+    Vector {
+        factory: "0x0000000000000000000000000000000000000000",
+        salt: "0x0000000000000000000000000000000000000000000000000000000000000000",
+        // keccak256(0xdeadbeef)
+        init_code_hash: "0xd4fd4e189132273036449fc9e11198c739161b4c0116a9a2dccdfa1c492006f1",
+        address: "0x70f2b2914A2a4b783FaEFb75f459A580616Fcb5e",
+    },
+    Vector {
+        factory: "0x00000000000000000000000000000000deadbeef",
+        salt: "0x00000000000000000000000000000000000000000000000000000000cafebabe",
+        // keccak256(0xdeadbeef)
+        init_code_hash: "0xd4fd4e189132273036449fc9e11198c739161b4c0116a9a2dccdfa1c492006f1",
+        address: "0x60f3f640a8508fC6a86d45DF051962668E1e8AC7",
+    },
+    Vector {
+        factory: "0x00000000000000000000000000000000deadbeef",
+        salt: "0x00000000000000000000000000000000000000000000000000000000cafebabe",
+        // keccak256(0xdeadbeef repeated 11 times)
+        init_code_hash: "0xdba4863677690f1376cf73b7bcb3dee7f78ba4cceb9f1973dc10536d2f470ca8",
+        address: "0x1d8bfDC5D46DC4f61D6b6115972536eBE6A8854C",
+    },
+    Vector {
+        factory: "0x0000000000000000000000000000000000000000",
+        salt: "0x0000000000000000000000000000000000000000000000000000000000000000",
+        // keccak256(0x)
+        init_code_hash: "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+        address: "0xE33C0C7F7df4809055C3ebA6c09CFe4BaF1BD9e0",
+    },
+    // the Uniswap V2 USDC/WETH pair on mainnet, salted with
+    // keccak256(abi.encodePacked(USDC, WETH))
+    Vector {
+        factory: "0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f",
+        salt: "0x85053f65cd1ece2bb37b70c13d66eadebf2779df5ddd68cf12f3ccfdc6bfe760",
+        init_code_hash: "0x96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f",
+        address: "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc",
+    },
+    // an address with two leading zero bytes, as mined by this crate and
+    // cross-checked with an independent keccak-256 implementation
+    Vector {
+        factory: "0x59b7B8Dd9E6e1F934C9c3Def4a1Eb69Bc17Ec9cc",
+        salt: "0x59b7b8dd9e6e1f934c9c3def4a1eb69bc17ec9cc654d8eb9f2cc0000004bad05",
+        init_code_hash: "0x3847e0003082aad368f8a9c0e3c51cd710d88ee44ea207de3f02eeeef677d392",
+        address: "0x00001db6676cfF53193cD8a825000a46660a6aA1",
+    },
+];
+
+/// A configuration that matches every address derived with the vector's
+/// factory and init code hash.
+fn config_for(vector: &Vector) -> Config {
+    let args = [
+        "create2crunch",
+        vector.factory,
+        "0x0000000000000000000000000000000000000000",
+        vector.init_code_hash,
+        "0x",
+        "--match-all",
+    ];
+    Config::new(args.iter().map(|arg| arg.to_string())).unwrap()
+}
+
+fn factory_of(vector: &Vector) -> [u8; 20] {
+    let mut factory = [0; 20];
+    factory.copy_from_slice(&hex::decode(&vector.factory[2..]).unwrap());
+    factory
+}
+
+fn init_code_hash_of(vector: &Vector) -> [u8; 32] {
+    let mut init_code_hash = [0; 32];
+    init_code_hash.copy_from_slice(&hex::decode(&vector.init_code_hash[2..]).unwrap());
+    init_code_hash
+}
+
+fn salt_of(vector: &Vector) -> [u8; 32] {
+    let mut salt = [0; 32];
+    salt.copy_from_slice(&hex::decode(&vector.salt[2..]).unwrap());
+    salt
+}
+
+#[test]
+fn handle_solution_reproduces_each_vector() {
+    for vector in VECTORS {
+        let found = handle_solution(&config_for(vector), &salt_of(vector)).unwrap();
+        assert_eq!(found.checksum_address, vector.address);
+    }
+}
+
+#[test]
+fn derive_batch_reproduces_each_vector() {
+    for vector in VECTORS {
+        let address = derive_batch(&config_for(vector), &[salt_of(vector)])[0];
+        assert_eq!(hex::encode(address), vector.address[2..].to_lowercase());
+    }
+}
+
+#[test]
+fn create2_address_reproduces_each_vector() {
+    for vector in VECTORS {
+        let address = create2_address(
+            &factory_of(vector),
+            &salt_of(vector),
+            &init_code_hash_of(vector),
+        );
+        assert_eq!(hex::encode(address), vector.address[2..].to_lowercase());
+    }
+}
+
+#[test]
+fn preimage_for_lays_out_each_vector_as_create2_hashes_it() {
+    for vector in VECTORS {
+        let preimage = preimage_for(&config_for(vector), &salt_of(vector));
+        assert_eq!(preimage.len(), 85);
+        assert_eq!(preimage[0], 0xff);
+        assert_eq!(preimage[1..21], factory_of(vector));
+        assert_eq!(preimage[21..53], salt_of(vector));
+        assert_eq!(preimage[53..], init_code_hash_of(vector));
+
+        // hashed elsewhere, it yields the address
+        let mut keccak = Keccak::v256();
+        keccak.update(&preimage);
+        let mut hash = [0; 32];
+        keccak.finalize(&mut hash);
+        assert_eq!(hex::encode(&hash[12..]), vector.address[2..].to_lowercase());
+    }
+}
+
+#[test]
+fn verify_reproduces_each_vector() {
+    for vector in VECTORS {
+        assert_eq!(
+            verify(
+                &factory_of(vector),
+                &salt_of(vector),
+                &init_code_hash_of(vector)
+            ),
+            vector.address
+        );
+    }
+}
+
+/// Addresses deployed to via CREATE by
+/// `0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0`, by nonce: each length of
+/// RLP-encoded nonce (empty, a single byte, and one to eight bytes) is covered.
+const CREATE_VECTORS: &[(u64, &str)] = &[
+    (0, "cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d"),
+    (1, "343c43a37d37dff08ae8c4a11544c718abb4fcf8"),
+    (2, "f778b86fa74e846c4f0a1fbd1335fe81c00a0c91"),
+    (3, "fffd933a0bc612844eaf0c6fe3e5b8e9b6c1d19c"),
+    (0x7f, "06d9a77f5e4b311bae8d559db9cdb4df94104aa0"),
+    (0x80, "08e190dcb7b73f5fcdabb43e102215c83659a76d"),
+    (1000, "b9cdb7f5e62043c1e4eb7a6d76ef8ee246d364ec"),
+    (u64::MAX, "9bc924993b60399df164c3763a964301d3db95ca"),
+];
+
+#[test]
+fn create_address_reproduces_each_create_vector() {
+    let mut deployer = [0; 20];
+    deployer.copy_from_slice(&hex::decode("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0").unwrap());
+
+    let args = [
+        "create2crunch",
+        "0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0",
+        "0x0000000000000000000000000000000000000000",
+        "0x0000000000000000000000000000000000000000000000000000000000000000",
+        "0x",
+        "--match-all",
+        "--create",
+    ];
+    let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+
+    for &(nonce, address) in CREATE_VECTORS {
+        assert_eq!(hex::encode(create_address(&deployer, nonce)), address);
+
+        // a salt holds the nonce in its last eight bytes
+        let mut salt = [0; 32];
+        salt[24..].copy_from_slice(&nonce.to_be_bytes());
+        let found = handle_solution(&config, &salt).unwrap();
+        assert_eq!(hex::encode(found.address), address);
+        assert_eq!(hex::encode(derive_batch(&config, &[salt])[0]), address);
+    }
+}
+
+#[test]
+fn init_code_hash_matches_the_eip_1014_init_code() {
+    let hashes = [
+        (&[0x00][..], VECTORS[0].init_code_hash),
+        (&[0xde, 0xad, 0xbe, 0xef][..], VECTORS[3].init_code_hash),
+        (&[][..], VECTORS[6].init_code_hash),
+    ];
+    for &(init_code, hash) in hashes.iter() {
+        assert_eq!(hex::encode(init_code_hash(init_code)), hash[2..]);
+    }
+}
+
+#[test]
+fn to_checksum_address_matches_eip_55() {
+    // the examples from EIP-55
+    let addresses = [
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        "0x52908400098527886E0F7030069857D2E4169EE7",
+        "0x8617E340B3D01FA5F11F306F4090FD50E238070D",
+        "0xde709f2102306220921060314715629080e2fb77",
+        "0x27b1fdb04752bbc536007a920d24acb045561c26",
+    ];
+    for address in addresses.iter() {
+        let mut bytes = [0; 20];
+        bytes.copy_from_slice(&hex::decode(&address[2..]).unwrap());
+        assert_eq!(to_checksum_address(&bytes), *address);
+    }
+}
+
+/// EIP-55 as the EIP spells it out: hash the lowercase hex, then uppercase
+/// each letter whose nibble of the hash is 8 or more.
+fn naive_checksum(address: &[u8; 20]) -> String {
+    let lowercase = hex::encode(address);
+    let mut keccak = Keccak::v256();
+    keccak.update(lowercase.as_bytes());
+    let mut hash = [0; 32];
+    keccak.finalize(&mut hash);
+
+    let recased: String = lowercase
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = if i % 2 == 0 {
+                hash[i / 2] >> 4
+            } else {
+                hash[i / 2] & 0xf
+            };
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("0x{}", recased)
+}
+
+#[test]
+fn checksummer_matches_the_naive_checksum() {
+    // one checksummer and output buffer, reused as the search reuses them
+    let mut checksummer = Checksummer::default();
+    let mut out = String::new();
+    let mut rng = rand::thread_rng();
+    for _ in 0..10_000 {
+        let mut address = [0; 20];
+        rng.fill_bytes(&mut address);
+
+        checksummer.checksum_into(&address, &mut out);
+        assert_eq!(out, naive_checksum(&address));
+        assert_eq!(to_checksum_address(&address), out);
+    }
+}
+
+#[test]
+fn leading_zero_vector_scores_its_zero_bytes() {
+    let vector = &VECTORS[VECTORS.len() - 1];
+    let found = handle_solution(&config_for(vector), &salt_of(vector)).unwrap();
+    assert_eq!(found.leading_zero_bytes(), 2);
+    assert_eq!(found.leading_zero_nibbles(), 4);
+}
+
+#[test]
+fn salts_hold_the_nonce_in_the_chosen_byte_order() {
+    for &(order, one) in [("big", [0, 0, 0, 0, 0, 1]), ("little", [1, 0, 0, 0, 0, 0])].iter() {
+        let args = [
+            "create2crunch",
+            VECTORS[0].factory,
+            "0x0000000000000000000000000000000000000000",
+            VECTORS[0].init_code_hash,
+            "0x",
+            "--match-all",
+            "--deterministic",
+            "--batch-size",
+            "2",
+            "--nonce-order",
+            order,
+        ];
+        let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+
+        // the salts searched are nonces zero and one under a segment of zeros
+        let mut found: Vec<_> = search(config.clone()).collect();
+        found.sort_by_key(|found| found.salt);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].salt, [0; 32]);
+        assert_eq!(found[1].salt[26..], one);
+        assert_eq!(config.nonce_order.nonce_bytes(1), one);
+
+        // the address searched is the one the salt deploys to
+        for found in found {
+            let derived = handle_solution(&config, &found.salt).unwrap();
+            assert_eq!(derived.address, found.address);
+        }
+    }
+    assert_eq!(
+        NonceOrder::LittleEndian.nonce_bytes(0x0102),
+        [2, 1, 0, 0, 0, 0]
+    );
+}
+
+#[cfg(feature = "gpu")]
+#[test]
+fn gpu_salts_match_little_endian_cpu_salts() {
+    let caller = "0x59b7B8Dd9E6e1F934C9c3Def4a1Eb69Bc17Ec9cc";
+    let args = [
+        "create2crunch",
+        VECTORS[0].factory,
+        caller,
+        VECTORS[0].init_code_hash,
+        "0x",
+        "--match-all",
+        "--deterministic",
+        "--batch-size",
+        "2",
+        "--nonce-order",
+        "little",
+    ];
+    let config = Config::new(args.iter().map(|arg| arg.to_string())).unwrap();
+    let mut found: Vec<_> = search(config.clone()).map(|found| found.salt).collect();
+    found.sort();
+
+    // under a segment of zeros, the CPU's nonce is the GPU's shifted past the
+    // two bytes that the CPU's six-byte segment takes from it
+    let caller = config.calling_addresses[0];
+    let gpu_salt = create2crunch::gpu_salt;
+    assert_eq!(
+        found,
+        vec![
+            gpu_salt(&caller, [0; 4], 0),
+            gpu_salt(&caller, [0; 4], 1 << 16)
+        ]
+    );
+
+    // and the segment and nonce sit where the CPU's segment and nonce do
+    let salt = gpu_salt(&caller, [1, 2, 3, 4], 0x0807_0605_0403_0201);
+    assert_eq!(salt[..20], caller);
+    assert_eq!(salt[20..26], [1, 2, 3, 4, 1, 2]);
+    assert_eq!(
+        salt[26..],
+        NonceOrder::LittleEndian.nonce_bytes(0x0807_0605_0403_0201 >> 16)
+    );
+}
+
+#[test]
+fn safe_salts_are_salt_nonces_hashed_with_the_initializer() {
+    // Safe's proxy factory and CreateX, with the initializer hash standing in
+    // for keccak256 of a `setup` call
+    let safe = "0x4e1DCf7AD4e460CfD30791CCC4F9c8a4f820ec67";
+    let createx = "0xba5Ed099633D3B313e4D5F7bdc1305d3c28ba5Ed";
+    let initializer_hash = [0x5a; 32];
+    let config = |factory: &str, flags: &[&str]| {
+        let args = [
+            "create2crunch",
+            factory,
+            "0x59b7B8Dd9E6e1F934C9c3Def4a1Eb69Bc17Ec9cc",
+            VECTORS[0].init_code_hash,
+            "0x",
+            "--match-all",
+            "--deterministic",
+            "--batch-size",
+            "4",
+        ];
+        Config::new(args.iter().chain(flags).map(|arg| arg.to_string())).unwrap()
+    };
+    let initializer_hash_arg = format!("0x{}", hex::encode(initializer_hash));
+    let safe = config(safe, &["--safe-initializer-hash", &initializer_hash_arg]);
+    let createx = config(createx, &[]);
+
+    let found: Vec<_> = search(safe.clone()).collect();
+    assert_eq!(found.len(), 4);
+    for found in found {
+        // the salt found is the nonce to pass, and the factory hashes it
+        assert_eq!(salt_nonce_for(&safe, &found.salt), Some(found.salt));
+        let mut keccak = Keccak::v256();
+        keccak.update(&initializer_hash);
+        keccak.update(&found.salt);
+        let mut salt = [0; 32];
+        keccak.finalize(&mut salt);
+        let init_code_hash = safe.init_code_hash;
+        assert_eq!(
+            create2_address(&safe.factory_address, &salt, &init_code_hash),
+            found.address
+        );
+        assert_eq!(derive_batch(&safe, &[found.salt]), vec![found.address]);
+    }
+
+    // a factory taking a raw salt has no nonce to give
+    let found: Vec<_> = search(createx.clone()).collect();
+    assert_eq!(found.len(), 4);
+    for found in found {
+        assert_eq!(salt_nonce_for(&createx, &found.salt), None);
+        let init_code_hash = createx.init_code_hash;
+        assert_eq!(
+            create2_address(&createx.factory_address, &found.salt, &init_code_hash),
+            found.address
+        );
+    }
+}
+
+#[test]
+fn salts_hold_only_the_chosen_number_of_random_bytes() {
+    let config = |random_bytes: &str| {
+        let args = [
+            "create2crunch",
+            VECTORS[0].factory,
+            "0x0000000000000000000000000000000000000000",
+            VECTORS[0].init_code_hash,
+            "0x",
+            "--match-all",
+            "--batch-size",
+            "16",
+            "--max-segments",
+            "4",
+            "--random-bytes",
+            random_bytes,
+        ];
+        Config::new(args.iter().map(|arg| arg.to_string())).unwrap()
+    };
+
+    // the segment is zero but for its last two bytes
+    let found: Vec<_> = search(config("2")).collect();
+    assert_eq!(found.len(), 64);
+    assert!(found.iter().all(|found| found.salt[20..24] == [0; 4]));
+
+    // with none, every run searches the same salts
+    let salts = || {
+        let mut salts: Vec<_> = search(config("0")).map(|found| found.salt).collect();
+        salts.sort();
+        salts
+    };
+    assert_eq!(salts().len(), 16);
+    assert_eq!(salts(), salts());
+}
+
+/// Check that the engine hashes random preimages as `tiny_keccak` does.
+fn check_engine(engine: KeccakEngine) {
+    let mut rng = rand::thread_rng();
+    for _ in 0..1000 {
+        let mut preimage = [0; PREIMAGE_LENGTH];
+        rng.fill_bytes(&mut preimage);
+
+        let mut keccak = Keccak::v256();
+        keccak.update(&preimage);
+        let mut expected = [0; 32];
+        keccak.finalize(&mut expected);
+
+        assert_eq!(engine.hash_preimage(&preimage), expected, "{:?}", engine);
+    }
+}
+
+#[test]
+fn the_detected_engine_hashes_preimages_correctly() {
+    check_engine(KeccakEngine::Scalar);
+    check_engine(KeccakEngine::detect());
+}
+
+#[cfg(target_arch = "aarch64")]
+#[test]
+fn the_neon_engine_hashes_preimages_correctly() {
+    // the engine needs the ARMv8.2 SHA3 instructions
+    if std::arch::is_aarch64_feature_detected!("sha3") {
+        check_engine(KeccakEngine::Neon);
+    }
+}
+
+#[test]
+fn addresses_are_taken_from_the_hash_at_the_truncation_offset() {
+    let vector = &VECTORS[0];
+    let config_at = |offset: Option<&str>| {
+        let mut args = vec![
+            "create2crunch",
+            vector.factory,
+            "0x0000000000000000000000000000000000000000",
+            vector.init_code_hash,
+            "0x",
+            "--match-all",
+            "--deterministic",
+            "--batch-size",
+            "16",
+        ];
+        if let Some(offset) = offset {
+            args.extend_from_slice(&["--truncation-offset", offset]);
+        }
+        Config::new(args.iter().map(|arg| arg.to_string())).unwrap()
+    };
+    let hash_of = |config: &Config, salt: &[u8; 32]| {
+        let mut keccak = Keccak::v256();
+        keccak.update(&preimage_for(config, salt));
+        let mut hash = [0; 32];
+        keccak.finalize(&mut hash);
+        hash
+    };
+
+    // by default, the last 20 bytes as CREATE2 does
+    let default = config_at(None);
+    assert_eq!(default.truncation_offset, 12);
+    let found = handle_solution(&default, &salt_of(vector)).unwrap();
+    assert_eq!(found.checksum_address, vector.address);
+
+    for &offset in [0, 5, 12].iter() {
+        let config = config_at(Some(&offset.to_string()));
+        let expected = |salt: &[u8; 32]| hash_of(&config, salt)[offset..offset + 20].to_vec();
+
+        // however the address is derived
+        let salt = salt_of(vector);
+        assert_eq!(
+            handle_solution(&config, &salt).unwrap().address.to_vec(),
+            expected(&salt)
+        );
+        assert_eq!(derive_batch(&config, &[salt])[0].to_vec(), expected(&salt));
+        let found: Vec<_> = search(config.clone()).collect();
+        assert_eq!(found.len(), 16);
+        for found in found {
+            assert_eq!(found.address.to_vec(), expected(&found.salt));
+        }
+    }
+
+    // anything past 12 would run off the end of the hash
+    let args = [
+        "create2crunch",
+        vector.factory,
+        "0x0000000000000000000000000000000000000000",
+        vector.init_code_hash,
+        "0x",
+        "--truncation-offset",
+        "13",
+    ];
+    assert!(Config::new(args.iter().map(|arg| arg.to_string())).is_err());
+}